@@ -108,7 +108,7 @@ class APIClient {
  
 
   async getChallenge(address: string): Promise<ChallengeResponse> {
-    return this.request(`/api/auth/challenge?address=${encodeURIComponent(address)}`);
+    return this.request(`/api/v1/auth/challenge?address=${encodeURIComponent(address)}`);
   }
 
   async login(data: {
@@ -128,7 +128,7 @@ class APIClient {
     console.log("Normalized public_key (64 hex chars):", normalizedPublicKey);
     console.log("Sending full COSE signature (not normalized)");
     
-    const response = await this.request<AuthResponse>('/api/auth/login', {
+    const response = await this.request<AuthResponse>('/api/v1/auth/login', {
       method: 'POST',
       body: JSON.stringify({
         address: data.address,
@@ -141,7 +141,7 @@ class APIClient {
     // Save tokens
     this.setAccessToken(response.access_token);
     if (typeof window !== 'undefined') {
-      localStorage.setItem('refresh_token', response.referesh_token);
+      localStorage.setItem('refresh_token', response.refresh_token);
     }
 
     return response;
@@ -149,7 +149,7 @@ class APIClient {
 
   async logout(): Promise<void> {
     try {
-      await this.request('/api/auth/logout', { method: 'POST' });
+      await this.request('/api/v1/auth/logout', { method: 'POST' });
     } finally {
       this.clearTokens();
     }
@@ -165,7 +165,7 @@ class APIClient {
     }
 
     const response = await this.request<{ access_token: string }>(
-      '/api/auth/refresh',
+      '/api/v1/auth/refresh',
       {
         method: 'POST',
         body: JSON.stringify({ refresh_token: refreshToken }),
@@ -177,7 +177,7 @@ class APIClient {
   }
 
   async getCurrentUser(): Promise<User> {
-    return this.request('/api/auth/me');
+    return this.request('/api/v1/auth/me');
   }
 
   async addWallet(data: {
@@ -190,7 +190,7 @@ class APIClient {
     const normalizedPublicKey = normalizePublicKey(data.public_key);
     const normalizedSignature = normalizeSignature(data.signature);
     
-    return this.request('/api/auth/add-wallet', {
+    return this.request('/api/v1/auth/add-wallet', {
       method: 'POST',
       body: JSON.stringify({
         address: data.address,
@@ -205,15 +205,15 @@ class APIClient {
   
 
   async getBlocks(limit: number = 20) {
-    return this.request(`/api/blocks?limit=${limit}`);
+    return this.request(`/api/v1/blocks?limit=${limit}`);
   }
 
   async getTransactions(limit: number = 50) {
-    return this.request(`/api/transactions?limit=${limit}`);
+    return this.request(`/api/v1/transactions?limit=${limit}`);
   }
 
   async getLatestBlock() {
-    return this.request('/api/blocks/latest');
+    return this.request('/api/v1/blocks/latest');
   }
 
  async getUserTransactions(addresses?: string[], limit: number = 50) {
@@ -222,20 +222,20 @@ class APIClient {
   if (addresses && addresses.length > 0) {
     params.append('addresses', addresses.join(','));
   }
-  return this.request(`/api/user/transactions?${params.toString()}`);
+  return this.request(`/api/v1/user/transactions?${params.toString()}`);
 }
 
 async getUserBalance(addresses?: string[]) {
   if (addresses && addresses.length > 0) {
     const params = new URLSearchParams();
     params.append('addresses', addresses.join(','));
-    return this.request(`/api/user/balance?${params.toString()}`);
+    return this.request(`/api/v1/user/balance?${params.toString()}`);
   }
-  return this.request('/api/user/balance');
+  return this.request('/api/v1/user/balance');
 }
 
   async getUserWallets() {
-    return this.request('/api/user/wallets');
+    return this.request('/api/v1/user/wallets');
   }
 
 
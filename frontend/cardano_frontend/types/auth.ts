@@ -6,7 +6,7 @@ export interface User {
 
 export interface AuthResponse {
     access_token: string;
-    referesh_token: string;
+    refresh_token: string;
     token_type: string;
     expires_in: number;
     user: User;
@@ -0,0 +1,267 @@
+//Shelley / Byron / reward address decoding
+use pallas_addresses::{
+    Address, ByronAddress, Network, ShelleyDelegationPart, ShelleyPaymentPart, StakeAddress, StakePayload,
+};
+use pallas_addresses::byron::{AddrAttrProperty, AddrType};
+use serde::Serialize;
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
+use std::str::FromStr;
+
+use crate::network::NETWORK;
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema, SimpleObject)]
+pub struct Credential {
+    pub kind: &'static str, //key or script
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema, SimpleObject)]
+pub struct PointerInfo {
+    pub slot: u64,
+    pub tx_index: u64,
+    pub cert_index: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema, SimpleObject)]
+pub struct ByronInfo {
+    pub root: String,
+    pub address_type: &'static str, //pubkey, script or redeem
+    pub protocol_magic: Option<u32>, //None on mainnet
+    pub has_derivation_path: bool, //legacy daedalus wallets
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema, SimpleObject)]
+#[graphql(complex)]
+pub struct AddressInfo {
+    pub address: String, //bech32, or base58 for byron
+    pub hex: String,
+    pub era: &'static str,
+    pub kind: &'static str, //base, pointer, enterprise, reward or byron
+    pub type_id: u8, //CIP-19 header type
+    pub network_id: u8,
+    pub network: &'static str,
+    pub payment: Option<Credential>,
+    pub stake: Option<Credential>,
+    pub pointer: Option<PointerInfo>,
+    pub stake_address: Option<String>, //reward address the funds are delegated through
+    pub byron: Option<ByronInfo>,
+}
+
+fn network_id(network: Network) -> u8 {
+    match network {
+        Network::Testnet => 0,
+        Network::Mainnet => 1,
+        Network::Other(id) => id,
+    }
+}
+
+fn network_name(id: u8) -> &'static str {
+    match id {
+        0 => "testnet",
+        1 => "mainnet",
+        _ => "unknown",
+    }
+}
+
+fn payment_credential(payment: &ShelleyPaymentPart) -> Credential {
+    Credential {
+        kind: if payment.is_script() { "script" } else { "key" },
+        hash: payment.as_hash().to_string(),
+    }
+}
+
+fn stake_credential(payload: &StakePayload) -> Credential {
+    Credential {
+        kind: if payload.is_script() { "script" } else { "key" },
+        hash: payload.as_hash().to_string(),
+    }
+}
+
+/// Parses bech32, base58 (byron) or hex and describes the address
+pub fn inspect(address: &str) -> Result<AddressInfo, String> {
+    let parsed = Address::from_str(address.trim()).map_err(|_| "Not a valid Cardano address".to_string())?;
+
+    match parsed {
+        Address::Shelley(shelley) => {
+            let id = network_id(shelley.network());
+            let (kind, stake, pointer) = match shelley.delegation() {
+                ShelleyDelegationPart::Key(hash) => {
+                    ("base", Some(Credential { kind: "key", hash: hash.to_string() }), None)
+                }
+                ShelleyDelegationPart::Script(hash) => {
+                    ("base", Some(Credential { kind: "script", hash: hash.to_string() }), None)
+                }
+                ShelleyDelegationPart::Pointer(p) => (
+                    "pointer",
+                    None,
+                    Some(PointerInfo { slot: p.slot(), tx_index: p.tx_idx(), cert_index: p.cert_idx() }),
+                ),
+                ShelleyDelegationPart::Null => ("enterprise", None, None),
+            };
+            let stake_address = StakeAddress::try_from(shelley.clone())
+                .ok()
+                .and_then(|s| s.to_bech32().ok());
+
+            Ok(AddressInfo {
+                address: shelley.to_bech32().map_err(|e| e.to_string())?,
+                hex: shelley.to_hex(),
+                era: "shelley",
+                kind,
+                type_id: shelley.typeid(),
+                network_id: id,
+                network: network_name(id),
+                payment: Some(payment_credential(shelley.payment())),
+                stake,
+                pointer,
+                stake_address,
+                byron: None,
+            })
+        }
+        Address::Stake(stake) => {
+            let id = network_id(stake.network());
+            let bech32 = stake.to_bech32().map_err(|e| e.to_string())?;
+            Ok(AddressInfo {
+                address: bech32.clone(),
+                hex: stake.to_hex(),
+                era: "shelley",
+                kind: "reward",
+                type_id: stake.typeid(),
+                network_id: id,
+                network: network_name(id),
+                payment: None,
+                stake: Some(stake_credential(stake.payload())),
+                pointer: None,
+                stake_address: Some(bech32),
+                byron: None,
+            })
+        }
+        Address::Byron(byron) => byron_info(&byron),
+    }
+}
+
+fn byron_info(byron: &ByronAddress) -> Result<AddressInfo, String> {
+    let payload = byron.decode().map_err(|e| format!("Invalid byron address: {}", e))?;
+
+    let mut protocol_magic = None;
+    let mut has_derivation_path = false;
+    for attribute in payload.attributes.iter() {
+        match attribute {
+            AddrAttrProperty::NetworkTag(tag) => {
+                protocol_magic = pallas_codec::minicbor::decode::<u32>(tag).ok();
+            }
+            AddrAttrProperty::DerivationPath(_) => has_derivation_path = true,
+            AddrAttrProperty::AddrDistr(_) => {}
+        }
+    }
+    // byron addresses only carry a network tag off mainnet
+    let id = if protocol_magic.is_some() { 0 } else { 1 };
+
+    Ok(AddressInfo {
+        address: byron.to_base58(),
+        hex: byron.to_hex(),
+        era: "byron",
+        kind: "byron",
+        type_id: byron.typeid(),
+        network_id: id,
+        network: network_name(id),
+        payment: None,
+        stake: None,
+        pointer: None,
+        stake_address: None,
+        byron: Some(ByronInfo {
+            root: payload.root.to_string(),
+            address_type: match payload.addrtype {
+                AddrType::PubKey => "pubkey",
+                AddrType::Script => "script",
+                AddrType::Redeem => "redeem",
+                AddrType::Other(_) => "other",
+            },
+            protocol_magic,
+            has_derivation_path,
+        }),
+    })
+}
+
+/// Network id addresses must carry on the network this explorer follows
+pub fn expected_network_id() -> u8 {
+    if NETWORK.name == "mainnet" { 1 } else { 0 }
+}
+
+/// Valid address for the given network id, used for wallet addresses from clients
+pub fn validate_for(address: &str, network_id: u8) -> Result<AddressInfo, String> {
+    let info = inspect(address)?;
+    if info.network_id != network_id {
+        return Err(format!("Address is for {}, expected {}", info.network, network_name(network_id)));
+    }
+    Ok(info)
+}
+
+pub fn validate(address: &str) -> Result<AddressInfo, String> {
+    validate_for(address, expected_network_id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // CIP-19 test vectors
+    const PAYMENT_KEY_HASH: &str = "9493315cd92eb5d8c4304e67b7e16ae36d61d34502694657811a2c8e";
+    const STAKE_KEY_HASH: &str = "337b62cfff6403a06a3acbc34f8c46003c69fe79a3628cefa9c47251";
+    const SCRIPT_HASH: &str = "c37b1b5dc0669f1d3c61a6fddb2e8fde96be87b881c60bce8e8d542f";
+
+    #[test]
+    fn test_shelley_addresses() {
+        let base = inspect("addr1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgse35a3x").unwrap();
+        assert_eq!(base.kind, "base");
+        assert_eq!(base.type_id, 0);
+        assert_eq!(base.network, "mainnet");
+        assert_eq!(base.payment, Some(Credential { kind: "key", hash: PAYMENT_KEY_HASH.to_string() }));
+        assert_eq!(base.stake, Some(Credential { kind: "key", hash: STAKE_KEY_HASH.to_string() }));
+        assert_eq!(
+            base.stake_address.as_deref(),
+            Some("stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw")
+        );
+
+        let script = inspect("addr1z8phkx6acpnf78fuvxn0mkew3l0fd058hzquvz7w36x4gten0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgs9yc0hh").unwrap();
+        assert_eq!(script.payment, Some(Credential { kind: "script", hash: SCRIPT_HASH.to_string() }));
+
+        let pointer = inspect("addr1gx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer5pnz75xxcrzqf96k").unwrap();
+        assert_eq!(pointer.kind, "pointer");
+        assert_eq!(pointer.pointer, Some(PointerInfo { slot: 2498243, tx_index: 27, cert_index: 3 }));
+        assert_eq!(pointer.stake_address, None);
+
+        let enterprise = inspect("addr_test1vz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzerspjrlsz").unwrap();
+        assert_eq!(enterprise.kind, "enterprise");
+        assert_eq!(enterprise.network_id, 0);
+        assert!(enterprise.stake.is_none());
+
+        let reward = inspect("stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw").unwrap();
+        assert_eq!(reward.kind, "reward");
+        assert_eq!(reward.stake, Some(Credential { kind: "key", hash: STAKE_KEY_HASH.to_string() }));
+
+        // hex input decodes to the same address
+        assert_eq!(inspect(&enterprise.hex).unwrap().address, enterprise.address);
+    }
+
+    #[test]
+    fn test_byron_address() {
+        let yoroi = inspect("Ae2tdPwUPEZFRbyhz3cpfC2CumGzNkFBN2L42rcUc2yjQpEkxDbkPodpMAi").unwrap();
+        assert_eq!(yoroi.era, "byron");
+        assert_eq!(yoroi.network, "mainnet");
+        let byron = yoroi.byron.unwrap();
+        assert_eq!(byron.address_type, "pubkey");
+        assert!(!byron.has_derivation_path);
+
+        let daedalus = inspect("37btjrVyb4KDXBNC4haBVPCrro8AQPHwvCMp3RFhhSVWwfFmZ6wwzSK6JK1hY6wHNmtrpTf1kdbva8TCneM2YsiXT7mrzT21EacHnPpz5YyUdj64na").unwrap();
+        assert!(daedalus.byron.unwrap().has_derivation_path);
+    }
+
+    #[test]
+    fn test_validate_network() {
+        let testnet = "addr_test1vz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzerspjrlsz";
+        assert!(validate_for(testnet, 0).is_ok());
+        assert!(validate_for(testnet, 1).is_err());
+        assert!(validate_for("not an address", 0).is_err());
+    }
+}
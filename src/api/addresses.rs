@@ -0,0 +1,15 @@
+//address inspection endpoint
+use actix_web::{get,web};
+use crate::address::{self, AddressInfo};
+use crate::error::{ApiError, ApiResult, ErrorBody};
+
+// {addr} is bech32, byron base58 or hex
+#[utoipa::path(
+    tag = "addresses",
+    params(("addr" = String, Path, description = "Bech32, byron base58 or hex address")),
+    responses((status = 200, body = AddressInfo), (status = 400, body = ErrorBody)),
+)]
+#[get("/api/v1/addresses/{addr}/info")]
+pub async fn get_address_info(path: web::Path<String>) -> ApiResult<AddressInfo> {
+    address::inspect(&path).map(web::Json).map_err(ApiError::InvalidAddress)
+}
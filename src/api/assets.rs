@@ -0,0 +1,76 @@
+//native asset endpoints (CIP-25 / CIP-68 metadata)
+use actix_web::{get,web};
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::sync::Arc;
+use crate::cip;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::models::AssetMetadata;
+use crate::oura_stream::BlockChainState;
+
+#[derive(Serialize, ToSchema)]
+pub struct AssetsResponse {
+    pub assets: Vec<AssetMetadata>,
+    pub count: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PolicyAssetsResponse {
+    pub policy_id: String,
+    pub assets: Vec<AssetMetadata>,
+    pub count: usize,
+}
+
+#[utoipa::path(
+    tag = "assets",
+    params(("limit" = Option<usize>, Query, description = "Number of assets, default 50, max 200")),
+    responses((status = 200, description = "Recently minted assets with metadata", body = AssetsResponse)),
+)]
+#[get("/api/v1/assets")]
+pub async fn get_assets(
+    state: web::Data<Arc<BlockChainState>>,
+    query: web::Query<std::collections::HashMap<String,String>>,
+) -> ApiResult<AssetsResponse> {
+    let limit = query.get("limit").and_then(|s| s.parse::<usize>().ok()).unwrap_or(50).min(200);
+
+    let assets = state.get_assets(limit).await;
+    Ok(web::Json(AssetsResponse { count: assets.len(), assets }))
+}
+
+#[utoipa::path(
+    tag = "assets",
+    params(("policy_id" = String, Path, description = "Policy id")),
+    responses((status = 200, body = PolicyAssetsResponse)),
+)]
+#[get("/api/v1/assets/policy/{policy_id}")]
+pub async fn get_policy_assets(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+) -> ApiResult<PolicyAssetsResponse> {
+    let policy_id = path.into_inner();
+    let assets = state.get_policy_assets(&policy_id).await;
+    Ok(web::Json(PolicyAssetsResponse { policy_id, count: assets.len(), assets }))
+}
+
+// {asset} is the unit (policy id + asset name hex) or the asset1... fingerprint
+#[utoipa::path(
+    tag = "assets",
+    params(("asset" = String, Path, description = "Unit (policy id + asset name hex) or asset1... fingerprint")),
+    responses((status = 200, body = AssetMetadata), (status = 400, body = ErrorBody), (status = 404, body = ErrorBody)),
+)]
+#[get("/api/v1/assets/{asset}")]
+pub async fn get_asset(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+) -> ApiResult<AssetMetadata> {
+    if !path.starts_with("asset1") && !cip::is_unit(&path) {
+        return Err(ApiError::BadRequest(
+            "Invalid asset, expected a unit (policy id + asset name hex) or asset1... fingerprint".to_string(),
+        ));
+    }
+    state
+        .get_asset(&path)
+        .await
+        .map(web::Json)
+        .ok_or_else(|| ApiError::NotFound("Asset metadata not found".to_string()))
+}
@@ -1,32 +1,66 @@
 //block endpoints
-use actix_web::{get,web,HttpResponse,Responder};
+use actix_web::{get,web};
+use serde::Serialize;
+use utoipa::ToSchema;
 use std::sync::Arc;
-use crate::{ oura_stream::BlockChainState};
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::models::Block;
+use crate::oura_stream::BlockChainState;
 
-#[get("/api/blocks")]
+#[derive(Serialize, ToSchema)]
+pub struct BlocksResponse {
+    pub blocks: Vec<Block>,
+    pub count: usize,
+}
+
+#[utoipa::path(
+    tag = "blocks",
+    params(("limit" = Option<usize>, Query, description = "Number of blocks, default 20, max 100")),
+    responses((status = 200, description = "Latest blocks, newest first", body = BlocksResponse)),
+)]
+#[get("/api/v1/blocks")]
 pub async fn get_blocks(
     state: web::Data<Arc<BlockChainState>>,
-    query: web::Query<std::collections::HashMap<String,String>>,   
-) -> impl Responder {
+    query: web::Query<std::collections::HashMap<String,String>>,
+) -> ApiResult<BlocksResponse> {
     let limit = query .get("limit").and_then(|s| s.parse::<usize>().ok()).unwrap_or(20).min(100); //maximumm 100 blocks
 
     let blocks =state.get_blocks(limit).await;
-    HttpResponse::Ok().json(serde_json::json!({
-        "blocks":blocks,
-        "count": blocks.len(),
-    }))
+    Ok(web::Json(BlocksResponse { count: blocks.len(), blocks }))
 }
 
-#[get("api/blocks/latest")]
-pub async fn get_latest_block(state: web::Data<Arc<BlockChainState>>)-> impl Responder{
+#[utoipa::path(
+    tag = "blocks",
+    responses((status = 200, body = Block), (status = 404, description = "No blocks ingested yet", body = ErrorBody)),
+)]
+#[get("/api/v1/blocks/latest")]
+pub async fn get_latest_block(state: web::Data<Arc<BlockChainState>>)-> ApiResult<Block> {
     let blocks = state.get_blocks(1).await;
 
-    if let Some(block) =blocks.first(){
-        HttpResponse::Ok().json(block)
-    }
-    else {
-        HttpResponse::NotFound().json(serde_json::json!({
-            "error": "no blocks avilanble"
-        }))
-    }
+    blocks
+        .into_iter()
+        .next()
+        .map(web::Json)
+        .ok_or_else(|| ApiError::NotFound("No blocks available yet".to_string()))
+}
+
+// {id} is a block hash or block number
+#[utoipa::path(
+    tag = "blocks",
+    params(("id" = String, Path, description = "Block hash or block number")),
+    responses((status = 200, body = Block), (status = 404, body = ErrorBody)),
+)]
+#[get("/api/v1/blocks/{id}")]
+pub async fn get_block(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+) -> ApiResult<Block> {
+    let block = match path.parse::<u64>() {
+        Ok(number) => state.get_block_by_number(number).await,
+        Err(_) => state.get_block_by_hash(&path.to_lowercase()).await,
+    };
+
+    block
+        .map(web::Json)
+        .ok_or_else(|| ApiError::NotFound("Block not seen by this explorer".to_string()))
 }
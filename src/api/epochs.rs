@@ -0,0 +1,55 @@
+//epoch summary endpoints
+use actix_web::{get,web};
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::sync::Arc;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::models::EpochSummary;
+use crate::oura_stream::BlockChainState;
+
+#[derive(Serialize, ToSchema)]
+pub struct EpochsResponse {
+    pub epochs: Vec<EpochSummary>,
+    pub count: usize,
+}
+
+#[utoipa::path(
+    tag = "epochs",
+    params(("limit" = Option<usize>, Query, description = "Number of epochs, default 10, max 100")),
+    responses((status = 200, body = EpochsResponse)),
+)]
+#[get("/api/v1/epochs")]
+pub async fn get_epochs(
+    state: web::Data<Arc<BlockChainState>>,
+    query: web::Query<std::collections::HashMap<String,String>>,
+) -> ApiResult<EpochsResponse> {
+    let limit = query.get("limit").and_then(|s| s.parse::<usize>().ok()).unwrap_or(10).min(100);
+
+    let epochs = state.get_epochs(limit).await;
+    Ok(web::Json(EpochsResponse { count: epochs.len(), epochs }))
+}
+
+// {n} is an epoch number or "latest"
+#[utoipa::path(
+    tag = "epochs",
+    params(("n" = String, Path, description = "Epoch number or latest")),
+    responses((status = 200, body = EpochSummary), (status = 400, body = ErrorBody), (status = 404, body = ErrorBody)),
+)]
+#[get("/api/v1/epochs/{n}")]
+pub async fn get_epoch(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+) -> ApiResult<EpochSummary> {
+    let summary = if path.as_str() == "latest" {
+        state.get_epochs(1).await.into_iter().next()
+    } else {
+        let epoch = path
+            .parse::<u64>()
+            .map_err(|_| ApiError::BadRequest("Epoch must be a number or latest".to_string()))?;
+        state.get_epoch(epoch).await
+    };
+
+    summary
+        .map(web::Json)
+        .ok_or_else(|| ApiError::NotFound("Epoch not seen by this explorer".to_string()))
+}
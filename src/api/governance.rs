@@ -0,0 +1,119 @@
+//Conway governance endpoints: proposals, votes and dreps
+use actix_web::{get,web};
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::sync::Arc;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::governance;
+use crate::models::{Certificate, Proposal, Vote, VoteTally};
+use crate::oura_stream::BlockChainState;
+
+#[derive(Serialize, ToSchema)]
+pub struct ProposalSummary {
+    pub proposal: Proposal,
+    pub tally: VoteTally,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProposalsResponse {
+    pub proposals: Vec<ProposalSummary>,
+    pub count: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProposalResponse {
+    pub proposal: Proposal,
+    pub tally: VoteTally,
+    pub votes: Vec<Vote>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DrepResponse {
+    pub drep: String,
+    pub registered: Option<bool>,
+    pub anchor: Option<serde_json::Value>,
+    pub certificates: Vec<Certificate>,
+    pub votes: Vec<Vote>,
+    pub delegators: Vec<String>,
+    pub delegator_count: usize,
+}
+
+#[utoipa::path(
+    tag = "governance",
+    params(("action" = Option<String>, Query, description = "Filter by action type"), ("limit" = Option<usize>, Query, description = "Number of proposals, default 20, max 100")),
+    responses((status = 200, body = ProposalsResponse)),
+)]
+#[get("/api/v1/governance/proposals")]
+pub async fn get_proposals(
+    state: web::Data<Arc<BlockChainState>>,
+    query: web::Query<std::collections::HashMap<String,String>>,
+) -> ApiResult<ProposalsResponse> {
+    let limit = query.get("limit").and_then(|s| s.parse::<usize>().ok()).unwrap_or(20).min(100);
+    let proposals = state.get_proposals(query.get("action").map(String::as_str), limit).await;
+
+    let mut items = Vec::with_capacity(proposals.len());
+    for proposal in proposals {
+        let tally = governance::tally(&state.get_proposal_votes(&proposal.id).await);
+        items.push(ProposalSummary { proposal, tally });
+    }
+    Ok(web::Json(ProposalsResponse { count: items.len(), proposals: items }))
+}
+
+// Proposal ids contain '#', send it percent-encoded (%23) or use tx_hash:index
+#[utoipa::path(
+    tag = "governance",
+    params(("id" = String, Path, description = "tx_hash#index (percent-encoded) or tx_hash:index")),
+    responses((status = 200, body = ProposalResponse), (status = 400, body = ErrorBody), (status = 404, body = ErrorBody)),
+)]
+#[get("/api/v1/governance/proposals/{id}")]
+pub async fn get_proposal(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+) -> ApiResult<ProposalResponse> {
+    let id = governance::parse_proposal_id(&path)
+        .ok_or_else(|| ApiError::BadRequest("Invalid proposal id, expected <tx_hash>#<index>".to_string()))?;
+    let proposal = state
+        .get_proposal(&id)
+        .await
+        .ok_or_else(|| ApiError::NotFound("Proposal not seen by this explorer".to_string()))?;
+
+    let votes = state.get_proposal_votes(&id).await;
+    Ok(web::Json(ProposalResponse { proposal, tally: governance::tally(&votes), votes }))
+}
+
+#[utoipa::path(
+    tag = "governance",
+    params(("id" = String, Path, description = "CIP-105 or CIP-129 drep id")),
+    responses((status = 200, body = DrepResponse), (status = 400, body = ErrorBody), (status = 404, body = ErrorBody)),
+)]
+#[get("/api/v1/governance/dreps/{id}")]
+pub async fn get_drep(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+) -> ApiResult<DrepResponse> {
+    let drep = governance::normalize_drep_id(&path)
+        .ok_or_else(|| ApiError::BadRequest("Invalid drep id, expected drep1... or drep_script1...".to_string()))?;
+
+    let certificates = state.get_drep_certificates(&drep).await;
+    let votes = state.get_voter_votes(&drep).await;
+    let delegators = state.get_drep_delegators(&drep).await;
+    if certificates.is_empty() && votes.is_empty() && delegators.is_empty() {
+        return Err(ApiError::NotFound("DRep not seen by this explorer".to_string()));
+    }
+
+    // certificates are newest first
+    let registered = certificates.first().map(|c| c.kind != "drep_deregistration");
+    let anchor = certificates
+        .iter()
+        .find(|c| c.kind != "drep_deregistration")
+        .and_then(|c| c.details.get("anchor").cloned());
+    Ok(web::Json(DrepResponse {
+        drep,
+        registered,
+        anchor,
+        certificates,
+        votes,
+        delegator_count: delegators.len(),
+        delegators,
+    }))
+}
@@ -0,0 +1,114 @@
+//GraphQL endpoint, GraphiQL and the subscription websocket
+use actix_web::{get, post, web, Error, HttpRequest, HttpResponse};
+use actix_web::http::header::{HeaderValue, SEC_WEBSOCKET_PROTOCOL};
+use actix_ws::{CloseCode, CloseReason, Message};
+use async_graphql::http::{GraphiQLSource, WebSocket, WebSocketProtocols, WsMessage};
+use async_graphql::{BatchRequest, BatchResponse};
+use futures_util::future::ready;
+use futures_util::StreamExt;
+use std::str::FromStr;
+use crate::error::ErrorBody;
+use crate::graphql::ExplorerSchema;
+use crate::metrics::LiveConnection;
+use crate::ratelimit;
+use crate::shutdown;
+
+// Queries, or a batch of them, as {"query", "operationName", "variables"}
+#[utoipa::path(
+    tag = "graphql",
+    request_body(content = Object, description = "GraphQL request {\"query\", \"operationName\", \"variables\"} or an array of them"),
+    responses((status = 200, description = "GraphQL response {\"data\", \"errors\"}, errors carry the REST error code in extensions.code", body = Object), (status = 400, body = ErrorBody)),
+)]
+#[post("/api/v1/graphql")]
+pub async fn graphql(schema: web::Data<ExplorerSchema>, req: web::Json<BatchRequest>) -> web::Json<BatchResponse> {
+    web::Json(schema.execute_batch(req.into_inner()).await)
+}
+
+#[utoipa::path(
+    tag = "graphql",
+    responses((status = 200, description = "GraphiQL IDE", content_type = "text/html")),
+)]
+#[get("/api/v1/graphql")]
+pub async fn graphiql() -> HttpResponse {
+    let page = GraphiQLSource::build()
+        .endpoint("/api/v1/graphql")
+        .subscription_endpoint("/api/v1/graphql/ws")
+        .title("Cardano PreProd Explorer GraphQL")
+        .finish();
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(page)
+}
+
+// Subscriptions over graphql-transport-ws, or the older graphql-ws when the client asks for it
+#[utoipa::path(
+    tag = "graphql",
+    responses((status = 101, description = "WebSocket upgrade speaking graphql-transport-ws or graphql-ws (Sec-WebSocket-Protocol)"), (status = 429, description = "Too many live connections from this client", body = ErrorBody)),
+)]
+#[get("/api/v1/graphql/ws")]
+pub async fn graphql_ws(
+    req: HttpRequest,
+    body: web::Payload,
+    schema: web::Data<ExplorerSchema>,
+) -> Result<HttpResponse, Error> {
+    let protocol = req
+        .headers()
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|list| list.split(',').find_map(|p| WebSocketProtocols::from_str(p.trim()).ok()))
+        .unwrap_or(WebSocketProtocols::GraphQLWS);
+
+    let slot = ratelimit::live_slot(&req, "graphql_ws")?;
+    let (mut response, mut session, msg_stream) = actix_ws::handle(&req, body)?;
+    response
+        .headers_mut()
+        .insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(protocol.sec_websocket_protocol()));
+
+    let schema = schema.get_ref().clone();
+    let pong = session.clone();
+    actix_web::rt::spawn(async move {
+        // text frames go to the graphql protocol, pings are answered here, close ends the stream
+        let incoming = msg_stream
+            .take_while(|msg| ready(matches!(msg, Ok(msg) if !matches!(msg, Message::Close(_)))))
+            .filter_map(move |msg| {
+                let mut session = pong.clone();
+                async move {
+                    match msg {
+                        Ok(Message::Text(text)) => Some(text.to_string()),
+                        Ok(Message::Ping(bytes)) => {
+                            let _ = session.pong(&bytes).await;
+                            None
+                        }
+                        _ => None,
+                    }
+                }
+            });
+
+        let _slot = slot;
+        let connection = LiveConnection::open("graphql_ws");
+        let mut outgoing = Box::pin(WebSocket::new(schema, incoming, protocol));
+        loop {
+            let msg = tokio::select! {
+                msg = outgoing.next() => msg,
+                _ = shutdown::requested() => {
+                    let reason = CloseReason { code: CloseCode::Away, description: Some("server shutting down".to_string()) };
+                    let _ = session.close(Some(reason)).await;
+                    return;
+                }
+            };
+            let Some(msg) = msg else { break };
+            match msg {
+                WsMessage::Text(text) => {
+                    if session.text(text).await.is_err() {
+                        return;
+                    }
+                    connection.sent();
+                }
+                WsMessage::Close(code, reason) => {
+                    let _ = session.close(Some(CloseReason { code: code.into(), description: Some(reason) })).await;
+                    return;
+                }
+            }
+        }
+        let _ = session.close(None).await;
+    });
+    Ok(response)
+}
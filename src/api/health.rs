@@ -0,0 +1,49 @@
+//service health endpoints: liveness and readiness probes
+use actix_web::{get,web,http::StatusCode,HttpResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::sync::Arc;
+use crate::error::ApiResult;
+use crate::health::{readiness, Readiness};
+use crate::oura_stream::BlockChainState;
+
+#[derive(Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: &'static str,
+    pub service: &'static str,
+}
+
+// Kept for existing monitors, same as /health/live
+#[utoipa::path(
+    tag = "health",
+    responses((status = 200, body = HealthResponse)),
+)]
+#[get("/health")]
+pub async fn health() -> ApiResult<HealthResponse> {
+    Ok(web::Json(HealthResponse { status: "healthy", service: "cardano-backend" }))
+}
+
+// The process is up and serving, restart it when this fails
+#[utoipa::path(
+    tag = "health",
+    responses((status = 200, body = HealthResponse)),
+)]
+#[get("/health/live")]
+pub async fn live() -> ApiResult<HealthResponse> {
+    Ok(web::Json(HealthResponse { status: "alive", service: "cardano-backend" }))
+}
+
+// Chain data is fresh enough to serve, 503 while syncing, stale beyond READINESS_MAX_LAG or with storage unavailable
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready to serve", body = Readiness),
+        (status = 503, description = "Syncing, stale or storage unavailable, same body", body = Readiness),
+    ),
+)]
+#[get("/health/ready")]
+pub async fn ready(state: web::Data<Arc<BlockChainState>>) -> HttpResponse {
+    let report = readiness(&state).await;
+    let status = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    HttpResponse::build(status).json(report)
+}
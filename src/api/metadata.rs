@@ -0,0 +1,91 @@
+//transaction metadata endpoints
+use actix_web::{get,web};
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::sync::Arc;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::metadata::{render, MetadataSchema};
+use crate::models::TxMetadata;
+use crate::oura_stream::BlockChainState;
+
+#[derive(Serialize, ToSchema)]
+pub struct MetadataEntry {
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub slot: u64,
+    pub timestamp: u64,
+    pub label: u64,
+    pub cbor: Option<String>, //null unless taken from the block cbor
+    pub json: serde_json::Value, //rendered in the requested schema
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LabelMetadataResponse {
+    pub label: u64,
+    pub metadata: Vec<MetadataEntry>,
+    pub count: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TxMetadataResponse {
+    pub tx_hash: String,
+    pub metadata: Vec<MetadataEntry>,
+}
+
+fn to_response(entry: TxMetadata, schema: MetadataSchema) -> MetadataEntry {
+    MetadataEntry {
+        json: render(&entry.json, schema),
+        tx_hash: entry.tx_hash,
+        block_number: entry.block_number,
+        slot: entry.slot,
+        timestamp: entry.timestamp,
+        label: entry.label,
+        cbor: entry.cbor,
+    }
+}
+
+// ?schema=detailed for the typed rendering, no schema otherwise
+#[utoipa::path(
+    tag = "metadata",
+    params(("label" = u64, Path, description = "Metadata label"), ("limit" = Option<usize>, Query, description = "Number of entries, default 50, max 200"), ("schema" = Option<String>, Query, description = "detailed for the typed rendering, no schema otherwise")),
+    responses((status = 200, body = LabelMetadataResponse), (status = 400, body = ErrorBody)),
+)]
+#[get("/api/v1/metadata/{label}")]
+pub async fn get_metadata_by_label(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String,String>>,
+) -> ApiResult<LabelMetadataResponse> {
+    let label = path
+        .parse::<u64>()
+        .map_err(|_| ApiError::BadRequest("Metadata label must be an unsigned integer".to_string()))?;
+    let limit = query.get("limit").and_then(|s| s.parse::<usize>().ok()).unwrap_or(50).min(200);
+    let schema = MetadataSchema::from_query(query.get("schema"));
+
+    let entries = state.get_metadata_by_label(label, limit).await;
+    let metadata: Vec<MetadataEntry> = entries.into_iter().map(|e| to_response(e, schema)).collect();
+    Ok(web::Json(LabelMetadataResponse { label, count: metadata.len(), metadata }))
+}
+
+#[utoipa::path(
+    tag = "metadata",
+    params(("hash" = String, Path, description = "Transaction hash"), ("schema" = Option<String>, Query, description = "detailed for the typed rendering, no schema otherwise")),
+    responses((status = 200, body = TxMetadataResponse), (status = 404, body = ErrorBody)),
+)]
+#[get("/api/v1/transactions/{hash}/metadata")]
+pub async fn get_transaction_metadata(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String,String>>,
+) -> ApiResult<TxMetadataResponse> {
+    let schema = MetadataSchema::from_query(query.get("schema"));
+    let entries = state.get_tx_metadata(&path).await;
+
+    if entries.is_empty() {
+        return Err(ApiError::NotFound("No metadata found for transaction".to_string()));
+    }
+    Ok(web::Json(TxMetadataResponse {
+        tx_hash: path.into_inner(),
+        metadata: entries.into_iter().map(|e| to_response(e, schema)).collect(),
+    }))
+}
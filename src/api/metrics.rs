@@ -0,0 +1,20 @@
+//prometheus scrape endpoint
+use actix_web::{get,web,HttpResponse};
+use std::sync::Arc;
+use crate::auth::SessionStore;
+use crate::error::{ApiError, ErrorBody};
+use crate::metrics::METRICS;
+use crate::oura_stream::BlockChainState;
+
+#[utoipa::path(
+    tag = "health",
+    responses((status = 200, description = "Prometheus text exposition format", content_type = "text/plain"), (status = 500, body = ErrorBody)),
+)]
+#[get("/metrics")]
+pub async fn get_metrics(
+    state: web::Data<Arc<BlockChainState>>,
+    sessions: web::Data<Arc<SessionStore>>,
+) -> Result<HttpResponse, ApiError> {
+    let text = METRICS.render(&state, &sessions).await.map_err(ApiError::Internal)?;
+    Ok(HttpResponse::Ok().content_type("text/plain; version=0.0.4; charset=utf-8").body(text))
+}
@@ -1,3 +1,20 @@
 pub mod blocks;
 pub mod transactions;
-pub mod user_transactions;
\ No newline at end of file
+pub mod user_transactions;
+pub mod assets;
+pub mod metadata;
+pub mod pools;
+pub mod time;
+pub mod epochs;
+pub mod stats;
+pub mod search;
+pub mod addresses;
+pub mod tools;
+pub mod submit;
+pub mod scripts;
+pub mod stake;
+pub mod governance;
+pub mod health;
+pub mod graphql;
+pub mod stream;
+pub mod metrics;
@@ -0,0 +1,136 @@
+//stake pool / block producer endpoints
+use actix_web::{get,web};
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::sync::Arc;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::models::{Block, Certificate};
+use crate::oura_stream::BlockChainState;
+use crate::pools::normalize_pool_id;
+
+#[derive(Serialize, ToSchema)]
+pub struct PoolBlockCount {
+    pub pool_id: String,
+    pub blocks: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EpochBlockCount {
+    pub epoch: u64,
+    pub blocks: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PoolsResponse {
+    pub epoch: u64,
+    pub pools: Vec<PoolBlockCount>,
+    pub count: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PoolBlocksResponse {
+    pub pool_id: String,
+    pub blocks: Vec<Block>,
+    pub count: usize,
+    pub epochs: Vec<EpochBlockCount>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PoolRegistrationsResponse {
+    pub pool_id: String,
+    pub current_params: Option<serde_json::Value>,
+    pub retiring_epoch: Option<u64>,
+    pub registrations: Vec<Certificate>,
+    pub count: usize,
+}
+
+fn pool_id(path: &str) -> Result<String, ApiError> {
+    normalize_pool_id(path)
+        .ok_or_else(|| ApiError::BadRequest("Invalid pool id, expected pool1... or a 56 character hex hash".to_string()))
+}
+
+// Pools that produced blocks in an epoch (?epoch=, defaults to the latest one)
+#[utoipa::path(
+    tag = "pools",
+    params(("epoch" = Option<u64>, Query, description = "Epoch, defaults to the latest one")),
+    responses((status = 200, description = "Pools that produced blocks in the epoch", body = PoolsResponse), (status = 404, body = ErrorBody)),
+)]
+#[get("/api/v1/pools")]
+pub async fn get_pools(
+    state: web::Data<Arc<BlockChainState>>,
+    query: web::Query<std::collections::HashMap<String,String>>,
+) -> ApiResult<PoolsResponse> {
+    let epoch = match query.get("epoch").and_then(|s| s.parse::<u64>().ok()) {
+        Some(e) => e,
+        None => match state.get_blocks(1).await.first() {
+            Some(block) => block.epoch,
+            None => return Err(ApiError::NotFound("No blocks available yet".to_string())),
+        },
+    };
+
+    let pools: Vec<PoolBlockCount> = state
+        .get_epoch_pool_counts(epoch)
+        .await
+        .into_iter()
+        .map(|(pool_id, blocks)| PoolBlockCount { pool_id, blocks })
+        .collect();
+    Ok(web::Json(PoolsResponse { epoch, count: pools.len(), pools }))
+}
+
+#[utoipa::path(
+    tag = "pools",
+    params(("id" = String, Path, description = "pool1... or pool hash hex"), ("limit" = Option<usize>, Query, description = "Number of blocks, default 20, max 100")),
+    responses((status = 200, body = PoolBlocksResponse), (status = 400, body = ErrorBody)),
+)]
+#[get("/api/v1/pools/{id}/blocks")]
+pub async fn get_pool_blocks(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String,String>>,
+) -> ApiResult<PoolBlocksResponse> {
+    let pool_id = pool_id(&path)?;
+    let limit = query.get("limit").and_then(|s| s.parse::<usize>().ok()).unwrap_or(20).min(100);
+
+    let blocks = state.get_pool_blocks(&pool_id, limit).await;
+    let epochs: Vec<EpochBlockCount> = state
+        .get_pool_epoch_counts(&pool_id)
+        .await
+        .into_iter()
+        .rev()
+        .map(|(epoch, blocks)| EpochBlockCount { epoch, blocks })
+        .collect();
+
+    Ok(web::Json(PoolBlocksResponse { pool_id, count: blocks.len(), blocks, epochs }))
+}
+
+#[utoipa::path(
+    tag = "pools",
+    params(("id" = String, Path, description = "pool1... or pool hash hex")),
+    responses((status = 200, body = PoolRegistrationsResponse), (status = 400, body = ErrorBody)),
+)]
+#[get("/api/v1/pools/{id}/registrations")]
+pub async fn get_pool_registrations(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+) -> ApiResult<PoolRegistrationsResponse> {
+    let pool_id = pool_id(&path)?;
+
+    let certificates = state.get_pool_certificates(&pool_id).await;
+    // newest first, so the first certificate tells the current state
+    let retiring_epoch = certificates
+        .first()
+        .filter(|c| c.kind == "pool_retirement")
+        .and_then(|c| c.epoch);
+    let current_params = certificates
+        .iter()
+        .find(|c| c.kind == "pool_registration")
+        .map(|c| c.details.clone());
+
+    Ok(web::Json(PoolRegistrationsResponse {
+        pool_id,
+        current_params,
+        retiring_epoch,
+        count: certificates.len(),
+        registrations: certificates,
+    }))
+}
@@ -0,0 +1,29 @@
+//script usage endpoint
+
+use actix_web::{get,web};
+use std::sync::Arc;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::models::ScriptSummary;
+use crate::oura_stream::BlockChainState;
+
+#[utoipa::path(
+    tag = "scripts",
+    params(("hash" = String, Path, description = "Script hash")),
+    responses((status = 200, body = ScriptSummary), (status = 400, body = ErrorBody), (status = 404, body = ErrorBody)),
+)]
+#[get("/api/v1/scripts/{hash}")]
+pub async fn get_script(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+) -> ApiResult<ScriptSummary> {
+    let hash = path.to_lowercase();
+    if hash.len() != 56 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ApiError::BadRequest("Script hash must be 28 bytes of hex".to_string()));
+    }
+
+    state
+        .get_script(&hash)
+        .await
+        .map(web::Json)
+        .ok_or_else(|| ApiError::NotFound("Script not seen by this explorer".to_string()))
+}
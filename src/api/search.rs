@@ -0,0 +1,40 @@
+//universal search endpoint
+use actix_web::{get,web};
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::sync::Arc;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::oura_stream::BlockChainState;
+use crate::search::{self, QueryKind, SearchResult};
+
+#[derive(Serialize, ToSchema)]
+pub struct SearchResponse {
+    pub query: String,
+    pub results: Vec<SearchResult>,
+    pub count: usize,
+}
+
+// ?q= block hash/number, slot, tx hash, address, stake address, pool id, fingerprint, policy id or $handle
+#[utoipa::path(
+    tag = "search",
+    params(("q" = String, Query, description = "Block hash/number, slot, tx hash, address, stake address, pool id, fingerprint, policy id or $handle")),
+    responses((status = 200, body = SearchResponse), (status = 400, body = ErrorBody)),
+)]
+#[get("/api/v1/search")]
+pub async fn get_search(
+    state: web::Data<Arc<BlockChainState>>,
+    query: web::Query<std::collections::HashMap<String,String>>,
+) -> ApiResult<SearchResponse> {
+    let q = query.get("q").map(|q| q.trim()).unwrap_or("");
+    if q.is_empty() {
+        return Err(ApiError::BadRequest("Missing search query ?q=".to_string()));
+    }
+
+    let kind = search::classify(q);
+    if kind == QueryKind::Unknown {
+        return Err(ApiError::BadRequest("Unrecognised identifier".to_string()));
+    }
+
+    let results = search::resolve(&state, kind).await;
+    Ok(web::Json(SearchResponse { query: q.to_string(), count: results.len(), results }))
+}
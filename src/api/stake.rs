@@ -0,0 +1,71 @@
+//stake address endpoint: delegation history and withdrawals
+use actix_web::{get,web};
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::sync::Arc;
+use crate::address;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::models::{Certificate, Withdrawal};
+use crate::oura_stream::BlockChainState;
+
+#[derive(Serialize, ToSchema)]
+pub struct StakeResponse {
+    pub stake_address: String,
+    pub registered: Option<bool>,
+    pub pool_id: Option<String>,
+    pub drep: Option<String>,
+    pub delegations: Vec<Certificate>,
+    pub certificates: Vec<Certificate>,
+    pub withdrawals: Vec<Withdrawal>,
+    pub total_withdrawn: u64,
+}
+
+#[utoipa::path(
+    tag = "stake",
+    params(("stake_address" = String, Path, description = "stake... / stake_test... address")),
+    responses((status = 200, body = StakeResponse), (status = 400, body = ErrorBody)),
+)]
+#[get("/api/v1/stake/{stake_address}")]
+pub async fn get_stake(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+) -> ApiResult<StakeResponse> {
+    let stake_address = match address::validate(&path) {
+        Ok(info) if info.kind == "reward" => info.address,
+        Ok(_) => return Err(ApiError::InvalidAddress("Expected a stake address".to_string())),
+        Err(e) => return Err(ApiError::InvalidAddress(format!("Invalid address: {}", e))),
+    };
+
+    let certificates = state.get_stake_certificates(&stake_address).await;
+    let withdrawals = state.get_withdrawals(&stake_address).await;
+
+    // certificates are newest first
+    let registered = certificates.iter().find_map(|c| {
+        if c.kind.starts_with("stake_deregistration") {
+            Some(false)
+        } else if c.kind.contains("registration") {
+            Some(true)
+        } else {
+            None
+        }
+    });
+    let pool_id = certificates.iter().find_map(|c| c.pool_id.clone());
+    let drep = certificates.iter().find_map(|c| c.drep.clone());
+    let delegations: Vec<Certificate> = certificates
+        .iter()
+        .filter(|c| c.pool_id.is_some() || c.drep.is_some())
+        .cloned()
+        .collect();
+    let total_withdrawn: u64 = withdrawals.iter().map(|w| w.amount).sum();
+
+    Ok(web::Json(StakeResponse {
+        stake_address,
+        registered,
+        pool_id,
+        drep,
+        delegations,
+        certificates,
+        withdrawals,
+        total_withdrawn,
+    }))
+}
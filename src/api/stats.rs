@@ -0,0 +1,16 @@
+//network statistics endpoint
+use actix_web::{get,web};
+use std::sync::Arc;
+use crate::error::ApiResult;
+use crate::oura_stream::BlockChainState;
+use crate::stats::StatsSnapshot;
+
+// TPS, fee percentiles, block fullness and empty block rate for the last 10 min / hour / day
+#[utoipa::path(
+    tag = "stats",
+    responses((status = 200, body = StatsSnapshot)),
+)]
+#[get("/api/v1/stats")]
+pub async fn get_stats(state: web::Data<Arc<BlockChainState>>) -> ApiResult<StatsSnapshot> {
+    Ok(web::Json(state.get_stats().await))
+}
@@ -0,0 +1,142 @@
+//server-sent events stream of chain events, for clients that can not keep a websocket open
+use actix_web::{get, http::header, web, HttpRequest, HttpResponse};
+use actix_web::web::Bytes;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::{interval_at, Instant, Interval};
+
+use crate::events::{self, Sequenced};
+use crate::error::{ApiError, ErrorBody};
+use crate::metrics::LiveConnection;
+use crate::oura_stream::BlockChainState;
+use crate::ratelimit::{self, LiveSlot};
+use crate::shutdown;
+
+// Comment line sent when nothing happened for this long, keeps proxies from timing the stream out
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+// Reconnect delay suggested to EventSource clients, in milliseconds
+const RETRY_MS: u64 = 3000;
+
+struct Feed {
+    state: Arc<BlockChainState>,
+    events: broadcast::Receiver<Sequenced>,
+    topics: HashSet<&'static str>,
+    backlog: VecDeque<String>, //frames to send before live events
+    last_id: u64, //newest event id handled, live events up to it were already replayed
+    keepalive: Interval,
+    connection: LiveConnection,
+    _slot: LiveSlot, //held for as long as the stream is open
+}
+
+impl Feed {
+    // Replay of the events after last_id, or a resync frame when they are gone
+    fn replay(&mut self) {
+        match self.state.events_since(self.last_id) {
+            Some(missed) => {
+                for event in missed {
+                    self.last_id = event.id;
+                    if self.topics.contains(event.event.topic()) {
+                        self.backlog.extend(events::sse_frame(&event));
+                    }
+                }
+            }
+            None => {
+                self.backlog.push_back(events::sse_resync_frame(self.last_id));
+                // carry on from now, the id may be from before a restart and ahead of ours
+                self.last_id = self.state.latest_event_id();
+            }
+        }
+    }
+
+    async fn next_frame(&mut self) -> Option<String> {
+        if let Some(frame) = self.backlog.pop_front() {
+            return Some(frame);
+        }
+        loop {
+            tokio::select! {
+                event = self.events.recv() => match event {
+                    Ok(event) if event.id <= self.last_id => {}
+                    Ok(event) => {
+                        self.last_id = event.id;
+                        if self.topics.contains(event.event.topic()) {
+                            if let Some(frame) = events::sse_frame(&event) {
+                                self.keepalive.reset();
+                                return Some(frame);
+                            }
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Event stream client lagged, replaying {} events", skipped);
+                        self.replay();
+                        if let Some(frame) = self.backlog.pop_front() {
+                            return Some(frame);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+                _ = self.keepalive.tick() => return Some(": keepalive\n\n".to_string()),
+                // ending the stream lets the server finish the response before it stops
+                _ = shutdown::requested() => return None,
+            }
+        }
+    }
+}
+
+// Resume point from the Last-Event-ID header EventSource sends on reconnect, or ?last_event_id=
+fn last_event_id(req: &HttpRequest, query: &std::collections::HashMap<String, String>) -> Option<u64> {
+    req.headers()
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .or(query.get("last_event_id").map(|s| s.as_str()))
+        .and_then(|id| id.trim().parse().ok())
+}
+
+#[utoipa::path(
+    tag = "live",
+    params(
+        ("topics" = Option<String>, Query, description = "Comma separated topics, default is every topic"),
+        ("last_event_id" = Option<u64>, Query, description = "Resume after this event id, same as the Last-Event-ID header"),
+        ("Last-Event-ID" = Option<u64>, Header, description = "Resume after this event id"),
+    ),
+    responses((status = 200, description = "text/event-stream of chain events: `id`, `event` (the type) and `data` ({\"type\", \"data\"} as on /ws)", content_type = "text/event-stream"), (status = 429, description = "Too many live connections from this client", body = ErrorBody)),
+)]
+#[get("/api/v1/stream")]
+pub async fn get_stream(
+    req: HttpRequest,
+    state: web::Data<Arc<BlockChainState>>,
+    query: web::Query<std::collections::HashMap<String,String>>,
+) -> Result<HttpResponse, ApiError> {
+    let slot = ratelimit::live_slot(&req, "sse")?;
+    let state = state.get_ref().clone();
+    // live events come after `latest`, a resuming client gets the ones it missed up to there replayed
+    let (events, latest) = state.subscribe_after();
+
+    let mut feed = Feed {
+        events,
+        topics: events::parse_topics(query.get("topics").map(|t| t.as_str())),
+        backlog: VecDeque::from([format!("retry: {}\n\n", RETRY_MS)]),
+        last_id: latest,
+        keepalive: interval_at(Instant::now() + KEEPALIVE_INTERVAL, KEEPALIVE_INTERVAL),
+        connection: LiveConnection::open("sse"),
+        _slot: slot,
+        state,
+    };
+    if let Some(last_id) = last_event_id(&req, &query) {
+        feed.last_id = last_id;
+        feed.replay();
+    }
+
+    let stream = futures_util::stream::unfold(feed, |mut feed| async move {
+        let frame = feed.next_frame().await?;
+        feed.connection.sent();
+        Some((Ok::<_, actix_web::Error>(Bytes::from(frame)), feed))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no")) //nginx would otherwise buffer the stream
+        .streaming(stream))
+}
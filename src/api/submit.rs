@@ -0,0 +1,63 @@
+//transaction submission endpoint
+use actix_web::{post,web,HttpResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::sync::Arc;
+use crate::error::{ApiError, ErrorBody};
+use crate::models::PendingTx;
+use crate::oura_stream::BlockChainState;
+use crate::submit::{self, SubmitError, SUBMIT_TARGET};
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct SubmitTxRequest {
+    cbor: String, //hex of the signed transaction
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SubmitTxResponse {
+    pub hash: String,
+    pub status: &'static str,
+    pub via: &'static str,
+}
+
+#[utoipa::path(
+    tag = "transactions",
+    request_body = SubmitTxRequest,
+    responses((status = 202, description = "Accepted, tracked as pending until seen in a block", body = SubmitTxResponse), (status = 400, body = ErrorBody), (status = 429, description = "Rate limited, retry after Retry-After seconds", body = ErrorBody), (status = 502, body = ErrorBody), (status = 503, body = ErrorBody)),
+)]
+#[post("/api/v1/transactions/submit")]
+pub async fn submit_tx(
+    state: web::Data<Arc<BlockChainState>>,
+    req: web::Json<SubmitTxRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let target = SUBMIT_TARGET
+        .as_ref()
+        .ok_or_else(|| ApiError::NotConfigured("Transaction submission not configured".to_string()))?;
+
+    let tx = submit::validate(&req.cbor).map_err(ApiError::BadRequest)?;
+
+    if let Err(e) = submit::submit(target, &tx).await {
+        tracing::warn!("Submission of {} failed: {}", tx.hash, e);
+        return Err(match e {
+            SubmitError::Rejected(_) => ApiError::TxRejected(e.to_string()),
+            SubmitError::Unavailable(_) => ApiError::Upstream(e.to_string()),
+        });
+    }
+
+    tracing::info!("Submitted transaction {} via {}", tx.hash, target.name());
+    state
+        .add_pending(PendingTx {
+            hash: tx.hash.clone(),
+            era: tx.era.to_string(),
+            size: tx.cbor.len() as u64,
+            submitted_at: chrono::Utc::now().timestamp() as u64,
+            via: target.name().to_string(),
+        })
+        .await;
+
+    Ok(HttpResponse::Accepted().json(SubmitTxResponse {
+        hash: tx.hash,
+        status: "pending",
+        via: target.name(),
+    }))
+}
@@ -0,0 +1,58 @@
+//slot / time conversion endpoints
+use actix_web::{get,web};
+use serde::Serialize;
+use utoipa::ToSchema;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::time::{SlotInfo, NETWORK_TIME};
+
+#[derive(Serialize, ToSchema)]
+pub struct SlotTimeResponse {
+    pub network: String,
+    #[serde(flatten)]
+    pub info: SlotInfo,
+    pub time: Option<String>, //rfc3339
+}
+
+fn slot_response(slot: u64) -> Result<SlotTimeResponse, ApiError> {
+    let info = NETWORK_TIME
+        .slot_info(slot)
+        .ok_or_else(|| ApiError::BadRequest("Slot is too far in the future to convert".to_string()))?;
+    let time = i64::try_from(info.timestamp)
+        .ok()
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+        .map(|t| t.to_rfc3339());
+    Ok(SlotTimeResponse { network: NETWORK_TIME.network.clone(), info, time })
+}
+
+#[utoipa::path(
+    tag = "time",
+    params(("slot" = u64, Path, description = "Absolute slot")),
+    responses((status = 200, body = SlotTimeResponse), (status = 400, body = ErrorBody)),
+)]
+#[get("/api/v1/time/slot/{slot}")]
+pub async fn get_slot_time(path: web::Path<String>) -> ApiResult<SlotTimeResponse> {
+    let slot = path
+        .parse::<u64>()
+        .map_err(|_| ApiError::BadRequest("Slot must be an unsigned integer".to_string()))?;
+    slot_response(slot).map(web::Json)
+}
+
+// posix seconds -> slot in progress at that time
+#[utoipa::path(
+    tag = "time",
+    params(("timestamp" = u64, Path, description = "Posix seconds")),
+    responses((status = 200, description = "Slot in progress at that time", body = SlotTimeResponse), (status = 400, body = ErrorBody)),
+)]
+#[get("/api/v1/time/posix/{timestamp}")]
+pub async fn get_posix_slot(path: web::Path<String>) -> ApiResult<SlotTimeResponse> {
+    let timestamp = path
+        .parse::<u64>()
+        .map_err(|_| ApiError::BadRequest("Timestamp must be posix seconds".to_string()))?;
+    if timestamp < NETWORK_TIME.system_start {
+        return Err(ApiError::BadRequest("Timestamp is before the network system start".to_string()));
+    }
+    let slot = NETWORK_TIME
+        .time_to_slot(timestamp)
+        .ok_or_else(|| ApiError::BadRequest("Timestamp is too far in the future to convert".to_string()))?;
+    slot_response(slot).map(web::Json)
+}
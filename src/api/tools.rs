@@ -0,0 +1,76 @@
+//developer tools
+use actix_web::{post,web};
+use serde::Serialize;
+use utoipa::ToSchema;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::{blueprint, plutus, tx_decoder};
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct DecodeTxRequest {
+    cbor: String, //hex
+}
+
+#[utoipa::path(
+    tag = "tools",
+    request_body = DecodeTxRequest,
+    responses((status = 200, description = "Decoded transaction body, witnesses and fees", body = Object), (status = 400, body = ErrorBody)),
+)]
+#[post("/api/v1/tools/decode-tx")]
+pub async fn decode_tx(req: web::Json<DecodeTxRequest>) -> ApiResult<serde_json::Value> {
+    tx_decoder::decode_tx(&req.cbor).map(web::Json).map_err(ApiError::BadRequest)
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct DecodeDatumRequest {
+    cbor: String, //hex
+    script_hash: Option<String>, //typed rendering when a blueprint is registered for it
+    purpose: Option<String>, //datum (default) or redeemer
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BlueprintRef {
+    pub title: String,
+    pub purpose: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DecodeDatumResponse {
+    pub hash: String,
+    pub json: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blueprint: Option<BlueprintRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typed: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typed_error: Option<String>,
+}
+
+#[utoipa::path(
+    tag = "tools",
+    request_body = DecodeDatumRequest,
+    responses((status = 200, body = DecodeDatumResponse), (status = 400, body = ErrorBody), (status = 404, description = "No blueprint registered for the script", body = ErrorBody)),
+)]
+#[post("/api/v1/tools/decode-datum")]
+pub async fn decode_datum(req: web::Json<DecodeDatumRequest>) -> ApiResult<DecodeDatumResponse> {
+    let (hash, json) = plutus::decode_data(&req.cbor).map_err(ApiError::BadRequest)?;
+    let mut body = DecodeDatumResponse { hash, json, blueprint: None, typed: None, typed_error: None };
+
+    let purpose = req.purpose.as_deref().unwrap_or("datum");
+    if purpose != "datum" && purpose != "redeemer" {
+        return Err(ApiError::BadRequest("purpose must be datum or redeemer".to_string()));
+    }
+    let Some(script_hash) = req.script_hash.as_deref().map(str::to_lowercase) else {
+        return Ok(web::Json(body));
+    };
+    let validator = blueprint::lookup(&script_hash)
+        .ok_or_else(|| ApiError::NotFound("No blueprint registered for this script".to_string()))?;
+
+    body.blueprint = Some(BlueprintRef { title: validator.title.clone(), purpose: purpose.to_string() });
+    let schema = if purpose == "datum" { &validator.datum } else { &validator.redeemer };
+    match schema.as_ref().map(|schema| blueprint::render(schema, &body.json, &validator.definitions)) {
+        Some(Ok(typed)) => body.typed = Some(typed),
+        Some(Err(e)) => body.typed_error = Some(e),
+        None => body.typed_error = Some(format!("Blueprint has no {} schema", purpose)),
+    }
+    Ok(web::Json(body))
+}
@@ -1,19 +1,143 @@
 //transactions endpoint
 
-use actix_web::{get,web,Responder,HttpResponse};
+use actix_web::{get,web};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use utoipa::ToSchema;
 use std::sync::Arc;
-use crate::{oura_stream::BlockChainState};
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::models::{CollateralOutput, Datum, InputRef, Redeemer, ScriptWitness, Transaction};
+use crate::{blueprint, oura_stream::BlockChainState};
 
-#[get("/api/transaction")]
+// Blocks on top before a transaction counts as confirmed (the k in "k deep")
+static CONFIRMATION_DEPTH: Lazy<u64> = Lazy::new(|| {
+    std::env::var("CONFIRMATION_DEPTH").ok().and_then(|v| v.parse().ok()).unwrap_or(15)
+});
+
+#[derive(Serialize, ToSchema)]
+pub struct TransactionsResponse {
+    pub transactions: Vec<Transaction>,
+    pub count: usize,
+}
+
+//script witnesses, redeemers, datums and collateral of a transaction
+#[derive(Serialize, ToSchema)]
+pub struct ScriptFields {
+    pub scripts: Vec<ScriptWitness>,
+    pub redeemers: Vec<Redeemer>,
+    pub datums: Vec<Datum>,
+    pub collateral_inputs: Vec<InputRef>,
+    pub collateral_output: Option<CollateralOutput>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TransactionDetail {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    #[serde(flatten)]
+    pub script_data: Option<ScriptFields>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TransactionStatus {
+    pub hash: String,
+    pub status: &'static str, //in_block, confirmed, pending or rolled_back
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submitted_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rolled_back_at: Option<u64>,
+    pub depth: u64,
+}
+
+#[utoipa::path(
+    tag = "transactions",
+    params(("limit" = Option<usize>, Query, description = "Number of transactions, default 50, max 200")),
+    responses((status = 200, description = "Latest transactions, newest first", body = TransactionsResponse)),
+)]
+#[get("/api/v1/transactions")]
 pub async fn get_transactions(
     state: web::Data<Arc<BlockChainState>>,
     query: web::Query<std::collections::HashMap<String,String>>,
-) -> impl  Responder {
+) -> ApiResult<TransactionsResponse> {
     let limit = query .get("limit").and_then(|s| s.parse::<usize>().ok()).unwrap_or(50).min(200);
 
     let transactions = state.get_transactions(limit).await;
-    HttpResponse::Ok().json(serde_json::json!({
-        "transactions":transactions,
-        "count":transactions.len(),
-    }))
-}
\ No newline at end of file
+    Ok(web::Json(TransactionsResponse { count: transactions.len(), transactions }))
+}
+
+#[utoipa::path(
+    tag = "transactions",
+    params(("hash" = String, Path, description = "Transaction hash")),
+    responses((status = 200, description = "Transaction with its script witnesses, redeemers, datums and collateral", body = TransactionDetail), (status = 404, body = ErrorBody)),
+)]
+#[get("/api/v1/transactions/{hash}")]
+pub async fn get_transaction(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+) -> ApiResult<TransactionDetail> {
+    let hash = path.to_lowercase();
+    let transaction = state
+        .get_transaction(&hash)
+        .await
+        .ok_or_else(|| ApiError::NotFound("Transaction not seen by this explorer".to_string()))?;
+
+    // script witnesses, redeemers, datums and collateral next to the summary fields
+    let script_data = state.get_script_data(&hash).await.map(|mut data| {
+        blueprint::annotate(&mut data);
+        ScriptFields {
+            scripts: data.scripts,
+            redeemers: data.redeemers,
+            datums: data.datums,
+            collateral_inputs: data.collateral_inputs,
+            collateral_output: data.collateral_output,
+        }
+    });
+    Ok(web::Json(TransactionDetail { transaction, script_data }))
+}
+
+#[utoipa::path(
+    tag = "transactions",
+    params(("hash" = String, Path, description = "Transaction hash"), ("depth" = Option<u64>, Query, description = "Confirmations before a transaction counts as confirmed, default CONFIRMATION_DEPTH")),
+    responses((status = 200, body = TransactionStatus), (status = 404, body = ErrorBody)),
+)]
+#[get("/api/v1/transactions/{hash}/status")]
+pub async fn get_transaction_status(
+    state: web::Data<Arc<BlockChainState>>,
+    path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String,String>>,
+) -> ApiResult<TransactionStatus> {
+    let hash = path.to_lowercase();
+    let depth = query.get("depth").and_then(|s| s.parse::<u64>().ok()).unwrap_or(*CONFIRMATION_DEPTH);
+    let status = |status| TransactionStatus {
+        hash: hash.clone(),
+        status,
+        block_number: None,
+        confirmations: None,
+        submitted_at: None,
+        rolled_back_at: None,
+        depth,
+    };
+
+    if let Some(tx) = state.get_transaction(&hash).await {
+        return Ok(web::Json(TransactionStatus {
+            block_number: Some(tx.block_number),
+            confirmations: Some(tx.confirmations),
+            ..status(if tx.confirmations >= depth { "confirmed" } else { "in_block" })
+        }));
+    }
+    if let Some(pending) = state.get_pending(&hash).await {
+        return Ok(web::Json(TransactionStatus { submitted_at: Some(pending.submitted_at), ..status("pending") }));
+    }
+    if let Some(rolled_back) = state.get_rolled_back(&hash).await {
+        return Ok(web::Json(TransactionStatus {
+            block_number: Some(rolled_back.block_number),
+            rolled_back_at: Some(rolled_back.rolled_back_at),
+            ..status("rolled_back")
+        }));
+    }
+    Err(ApiError::NotFound("Transaction not seen by this explorer".to_string()))
+}
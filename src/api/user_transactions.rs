@@ -1,104 +1,162 @@
 //filter garne user ko transactions using Blockfrost API
 
-use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use actix_web::{get, web, HttpRequest};
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::sync::Arc;
 
+use crate::address;
+use crate::blockfrost;
+use crate::telemetry::redact_address;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::models::AssetMetadata;
+use crate::oura_stream::BlockChainState;
 
-use crate::auth::middleware::get_claims;
+use crate::auth::middleware::require_claims;
+use crate::auth::Claims;
 
-// Blockfrost API configuration
-const BLOCKFROST_API_URL: &str = "https://cardano-preprod.blockfrost.io/api/v0";
+#[derive(Serialize, ToSchema)]
+pub struct UserTransaction {
+    pub hash: String,
+    pub block_number: Option<u64>,
+    pub block_time: Option<u64>,
+    pub confirmations: Option<u64>, //blocks on top at the tip this backend follows
+    pub fee: u64,
+}
 
-async fn fetch_address_transactions(address: &str, blockfrost_key: &str) -> Result<Vec<serde_json::Value>, String> {
-    let client = reqwest::Client::new();
-    let url = format!("{}/addresses/{}/transactions", BLOCKFROST_API_URL, address);
-    
-    log::info!("Fetching transactions for address: {}", address);
-    
-    let response = client
-        .get(&url)
-        .header("project_id", blockfrost_key)
-        .send()
-        .await
-        .map_err(|e| format!("Blockfrost request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        return Err(format!("Blockfrost API error: {} {}", status, body));
+#[derive(Serialize, ToSchema)]
+pub struct UserTransactionsResponse {
+    pub transactions: Vec<UserTransaction>,
+    pub count: usize,
+    pub user_addresses: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TokenBalance {
+    pub unit: String,
+    pub quantity: String,
+    pub metadata: Option<AssetMetadata>, //CIP-25/CIP-68 metadata when seen on chain
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AddressBalance {
+    pub address: String,
+    pub ada_balance: u64, //lovelace
+    pub tokens: Vec<TokenBalance>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Balances {
+    pub total_ada: u64,
+    pub per_address: Vec<AddressBalance>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UserBalanceResponse {
+    pub addresses: Vec<String>,
+    pub balances: Balances,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UserWalletsResponse {
+    pub primary_address: String,
+    pub all_addresses: Vec<String>,
+    pub wallet_count: usize,
+}
+
+// Each address costs one or more Blockfrost calls, requests past this are refused
+const MAX_ADDRESSES: usize = 20;
+// Transactions looked up in detail per request, one Blockfrost call each
+const MAX_TX_DETAILS: usize = 20;
+
+// Addresses to look up: the ?addresses= list or the wallets in the token, at most MAX_ADDRESSES
+fn user_addresses(query: &std::collections::HashMap<String, String>, claims: &Claims) -> Result<Vec<String>, ApiError> {
+    let addresses = match query.get("addresses") {
+        Some(addresses_param) => parse_addresses(addresses_param, claims)?,
+        // Fallback to JWT claims if no addresses provided (backward compatibility)
+        None => claims.addresses.clone(),
+    };
+    if addresses.len() > MAX_ADDRESSES {
+        return Err(ApiError::BadRequest(format!("At most {} addresses per request", MAX_ADDRESSES)));
     }
-    
-    let transactions = response
-        .json::<Vec<serde_json::Value>>()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-    
-    Ok(transactions)
+    Ok(addresses)
+}
+
+// Comma separated payment addresses from the frontend, normalised to bech32 / base58,
+// each one of the caller's wallets
+fn parse_addresses(param: &str, claims: &Claims) -> Result<Vec<String>, ApiError> {
+    param
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let info = address::validate(s).map_err(|e| ApiError::InvalidAddress(format!("Invalid address {}: {}", s, e)))?;
+            if info.kind == "reward" {
+                return Err(ApiError::InvalidAddress(format!("Invalid address {}: stake addresses hold no UTxOs", s)));
+            }
+            if !owned_by(&info, claims) {
+                return Err(ApiError::Unauthorized(format!("Address {} is not one of your wallets", s)));
+            }
+            Ok(info.address)
+        })
+        .collect()
+}
+
+// A wallet address of the token, or delegated through the stake key of one
+fn owned_by(info: &address::AddressInfo, claims: &Claims) -> bool {
+    claims.addresses.iter().chain(std::iter::once(&claims.sub)).any(|wallet| {
+        let Ok(wallet) = address::validate(wallet) else {
+            return false;
+        };
+        let stake = wallet.stake_address.as_ref().or((wallet.kind == "reward").then_some(&wallet.address));
+        wallet.address == info.address || (stake.is_some() && stake == info.stake_address.as_ref())
+    })
+}
+
+fn blockfrost_key() -> Result<String, ApiError> {
+    blockfrost::api_key().ok_or_else(|| {
+        tracing::error!("BLOCKFROST_API_KEY not set in environment");
+        ApiError::NotConfigured("Blockfrost API not configured".to_string())
+    })
+}
+
+async fn fetch_address_transactions(address: &str, blockfrost_key: &str) -> Result<Vec<serde_json::Value>, String> {
+    tracing::info!(address = %redact_address(address), "Fetching transactions");
+    blockfrost::get(&format!("/addresses/{}/transactions", address), blockfrost_key).await
 }
 
 async fn fetch_transaction_details(tx_hash: &str, blockfrost_key: &str) -> Result<serde_json::Value, String> {
-    let client = reqwest::Client::new();
-    let url = format!("{}/txs/{}", BLOCKFROST_API_URL, tx_hash);
-    
-    let response = client
-        .get(&url)
-        .header("project_id", blockfrost_key)
-        .send()
-        .await
-        .map_err(|e| format!("Blockfrost request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        return Err(format!("Blockfrost API error: {}", response.status()));
-    }
-    
-    let tx_details = response
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-    
-    Ok(tx_details)
+    blockfrost::get(&format!("/txs/{}", tx_hash), blockfrost_key).await
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/user",
+    tag = "user",
+    security(("bearer" = [])),
+    params(("addresses" = Option<String>, Query, description = "Comma separated payment addresses of the caller's wallets (at most 20), defaults to the wallets of the token"), ("limit" = Option<usize>, Query, description = "Number of transactions, default and max 20")),
+    responses((status = 200, body = UserTransactionsResponse), (status = 400, body = ErrorBody), (status = 401, body = ErrorBody), (status = 429, description = "Rate limited, retry after Retry-After seconds", body = ErrorBody), (status = 503, body = ErrorBody)),
+)]
 #[get("/transactions")]
 pub async fn get_user_transactions(
     req: HttpRequest,
     query: web::Query<std::collections::HashMap<String, String>>,
-) -> impl Responder {
+    state: web::Data<Arc<BlockChainState>>,
+) -> ApiResult<UserTransactionsResponse> {
     // Get authenticated user for security check
-    let claims = match get_claims(&req) {
-        Some(c) => c,
-        None => {
-            return HttpResponse::Unauthorized().json(serde_json::json!({
-                "error": "Authentication required"
-            }));
-        }
-    };
+    let claims = require_claims(&req)?;
 
     let limit = query
         .get("limit")
         .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(50)
-        .min(100);
+        .unwrap_or(MAX_TX_DETAILS)
+        .min(MAX_TX_DETAILS);
 
     // Get addresses from query parameter (comma-separated Bech32 addresses from frontend)
-    let user_addresses: Vec<String> = if let Some(addresses_param) = query.get("addresses") {
-        addresses_param.split(',').map(|s| s.trim().to_string()).collect()
-    } else {
-        // Fallback to JWT claims if no addresses provided (backward compatibility)
-        claims.addresses.clone()
-    };
+    let user_addresses = user_addresses(&query, &claims)?;
 
-    log::info!("Fetching transactions for addresses: {:?}", user_addresses);
+    tracing::info!(addresses = user_addresses.len(), "Fetching transactions");
 
-    // Get Blockfrost API key from environment
-    let blockfrost_key = match std::env::var("BLOCKFROST_API_KEY") {
-        Ok(key) => key,
-        Err(_) => {
-            log::error!("BLOCKFROST_API_KEY not set in environment");
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Blockfrost API not configured"
-            }));
-        }
-    };
+    let blockfrost_key = blockfrost_key()?;
 
     let mut all_tx_hashes = std::collections::HashSet::new();
     
@@ -106,7 +164,7 @@ pub async fn get_user_transactions(
     for address in &user_addresses {
         match fetch_address_transactions(address, &blockfrost_key).await {
             Ok(txs) => {
-                log::info!("Found {} transactions for {}", txs.len(), address);
+                tracing::info!(address = %redact_address(address), count = txs.len(), "Found transactions");
                 for tx in txs.iter().take(limit) {
                     if let Some(tx_hash) = tx.get("tx_hash").and_then(|h| h.as_str()) {
                         all_tx_hashes.insert(tx_hash.to_string());
@@ -114,147 +172,125 @@ pub async fn get_user_transactions(
                 }
             }
             Err(e) => {
-                log::warn!("Failed to fetch transactions for {}: {}", address, e);
+                tracing::warn!(address = %redact_address(address), error = %e, "Failed to fetch transactions");
             }
         }
     }
 
     // Fetch details for unique transactions
+    let tip = state.tip().await;
     let mut transactions = Vec::new();
     for tx_hash in all_tx_hashes.iter().take(limit) {
         match fetch_transaction_details(tx_hash, &blockfrost_key).await {
             Ok(tx_detail) => {
                 // Transform Blockfrost format to our format
-                let formatted_tx = serde_json::json!({
-                    "hash": tx_hash,
-                    "block_number": tx_detail.get("block_height").and_then(|v| v.as_u64()),
-                    "block_time": tx_detail.get("block_time").and_then(|v| v.as_u64()),
-                    "fee": tx_detail.get("fees").and_then(|v| v.as_str()).and_then(|s| s.parse::<u64>().ok()).unwrap_or(0),
-                    "inputs": [],
-                    "outputs": [],
-                    "input_count": 0,
-                    "output_count": 0,
+                let block_number = tx_detail.get("block_height").and_then(|v| v.as_u64());
+                transactions.push(UserTransaction {
+                    hash: tx_hash.clone(),
+                    block_number,
+                    block_time: tx_detail.get("block_time").and_then(|v| v.as_u64()),
+                    confirmations: block_number.map(|n| tip.saturating_sub(n)),
+                    fee: tx_detail.get("fees").and_then(|v| v.as_str()).and_then(|s| s.parse::<u64>().ok()).unwrap_or(0),
                 });
-                transactions.push(formatted_tx);
             }
             Err(e) => {
-                log::warn!("Failed to fetch details for {}: {}", tx_hash, e);
+                tracing::warn!("Failed to fetch details for {}: {}", tx_hash, e);
             }
         }
     }
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "transactions": transactions,
-        "count": transactions.len(),
-        "user_addresses": user_addresses,
+    Ok(web::Json(UserTransactionsResponse {
+        count: transactions.len(),
+        transactions,
+        user_addresses,
     }))
 }
 
-// GET /api/user/balance
+// GET /api/v1/user/balance
 
+#[utoipa::path(
+    context_path = "/api/v1/user",
+    tag = "user",
+    security(("bearer" = [])),
+    params(("addresses" = Option<String>, Query, description = "Comma separated payment addresses of the caller's wallets (at most 20), defaults to the wallets of the token")),
+    responses((status = 200, body = UserBalanceResponse), (status = 400, body = ErrorBody), (status = 401, body = ErrorBody), (status = 429, description = "Rate limited, retry after Retry-After seconds", body = ErrorBody), (status = 503, body = ErrorBody)),
+)]
 #[get("/balance")]
 pub async fn get_user_balance(
     req: HttpRequest,
     query: web::Query<std::collections::HashMap<String, String>>,
-) -> impl Responder {
-    let claims = match get_claims(&req) {
-        Some(c) => c,
-        None => {
-            return HttpResponse::Unauthorized().json(serde_json::json!({
-                "error": "Authentication required"
-            }));
-        }
-    };
+    state: web::Data<Arc<BlockChainState>>,
+) -> ApiResult<UserBalanceResponse> {
+    let claims = require_claims(&req)?;
 
     // Get addresses from query parameter or fallback to JWT claims
-    let user_addresses: Vec<String> = if let Some(addresses_param) = query.get("addresses") {
-        addresses_param.split(',').map(|s| s.trim().to_string()).collect()
-    } else {
-        claims.addresses.clone()
-    };
+    let user_addresses = user_addresses(&query, &claims)?;
 
-    log::info!("Fetching balance for addresses: {:?}", user_addresses);
+    tracing::info!(addresses = user_addresses.len(), "Fetching balance");
 
-    // Get Blockfrost API key from environment
-    let blockfrost_key = match std::env::var("BLOCKFROST_API_KEY") {
-        Ok(key) => key,
-        Err(_) => {
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Blockfrost API not configured"
-            }));
-        }
-    };
+    let blockfrost_key = blockfrost_key()?;
 
     let mut total_balance: u64 = 0;
     let mut per_address_balances = Vec::new();
 
     for address in &user_addresses {
-        let client = reqwest::Client::new();
-        let url = format!("{}/addresses/{}", BLOCKFROST_API_URL, address);
-        
-        match client
-            .get(&url)
-            .header("project_id", &blockfrost_key)
-            .send()
-            .await
-        {
-            Ok(response) if response.status().is_success() => {
-                if let Ok(addr_info) = response.json::<serde_json::Value>().await {
-                    let amount = addr_info
-                        .get("amount")
-                        .and_then(|amounts| amounts.as_array())
-                        .and_then(|arr| arr.first())
-                        .and_then(|first| first.get("quantity"))
-                        .and_then(|q| q.as_str())
-                        .and_then(|s| s.parse::<u64>().ok())
-                        .unwrap_or(0);
-                    
-                    total_balance += amount;
-                    
-                    per_address_balances.push(serde_json::json!({
-                        "address": address,
-                        "ada_balance": amount,
-                        "tokens": []
-                    }));
+        let mut balance = AddressBalance { address: address.clone(), ada_balance: 0, tokens: Vec::new() };
+        match blockfrost::get::<serde_json::Value>(&format!("/addresses/{}", address), &blockfrost_key).await {
+            Ok(addr_info) => {
+                let amounts = addr_info
+                    .get("amount")
+                    .and_then(|amounts| amounts.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+
+                for entry in &amounts {
+                    let unit = entry.get("unit").and_then(|u| u.as_str()).unwrap_or("");
+                    let quantity = entry.get("quantity").and_then(|q| q.as_str()).unwrap_or("0");
+                    if unit == "lovelace" {
+                        balance.ada_balance = quantity.parse::<u64>().unwrap_or(0);
+                        continue;
+                    }
+                    // attach CIP-25/CIP-68 metadata when we have seen it on chain
+                    balance.tokens.push(TokenBalance {
+                        unit: unit.to_string(),
+                        quantity: quantity.to_string(),
+                        metadata: state.get_asset(unit).await,
+                    });
                 }
             }
-            _ => {
-                log::warn!("Failed to fetch balance for {}", address);
-                per_address_balances.push(serde_json::json!({
-                    "address": address,
-                    "ada_balance": "0",
-                    "tokens": []
-                }));
+            Err(e) => {
+                tracing::warn!(address = %redact_address(address), error = %e, "Failed to fetch balance");
             }
         }
+        total_balance += balance.ada_balance;
+        per_address_balances.push(balance);
     }
     
-    HttpResponse::Ok().json(serde_json::json!({
-        "addresses": user_addresses,
-        "balances": {
-            "total_ada": total_balance,
-            "per_address": per_address_balances
-        }
+    Ok(web::Json(UserBalanceResponse {
+        addresses: user_addresses,
+        balances: Balances {
+            total_ada: total_balance,
+            per_address: per_address_balances,
+        },
     }))
 }
 
-// GET /api/user/wallets
+// GET /api/v1/user/wallets
 // Get all connected wallets for the user (PROTECTED)
 
+#[utoipa::path(
+    context_path = "/api/v1/user",
+    tag = "user",
+    security(("bearer" = [])),
+    responses((status = 200, body = UserWalletsResponse), (status = 401, body = ErrorBody)),
+)]
 #[get("/wallets")]
-pub async fn get_user_wallets(req: HttpRequest) -> impl Responder {
-    let claims = match get_claims(&req) {
-        Some(c) => c,
-        None => {
-            return HttpResponse::Unauthorized().json(serde_json::json!({
-                "error": "Authentication required"
-            }));
-        }
-    };
+pub async fn get_user_wallets(req: HttpRequest) -> ApiResult<UserWalletsResponse> {
+    let claims = require_claims(&req)?;
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "primary_address": claims.sub,
-        "all_addresses": claims.addresses,
-        "wallet_count": claims.addresses.len()
+    Ok(web::Json(UserWalletsResponse {
+        primary_address: claims.sub,
+        wallet_count: claims.addresses.len(),
+        all_addresses: claims.addresses,
     }))
-}
\ No newline at end of file
+}
@@ -1,8 +1,5 @@
 //jwt generation ra verification
 
-use core::str;
-use std::i64;
-
 use jsonwebtoken::{encode,decode,Header,Validation,EncodingKey,DecodingKey,Algorithm};
 
 use uuid::Uuid;
@@ -69,11 +66,7 @@ impl JwtService {
 
     /// Extract token from Authorization header
     pub fn extract_token_from_header(auth_header: &str) -> Option<String> {
-        if auth_header.starts_with("Bearer ") {
-            Some(auth_header[7..].to_string())
-        } else {
-            None
-        }
+        auth_header.strip_prefix("Bearer ").map(|token| token.to_string())
     }
 }
 
@@ -87,7 +80,7 @@ mod tests {
         let addresses= vec![address.clone()];
 
         let token = JwtService::generate_access_token(&address, addresses.clone()).unwrap();
-        assert!(token.is_empty()); //checks condition is true
+        assert!(!token.is_empty()); //checks condition is true
 
         let claims = JwtService::verify_token(&token).unwrap();
         assert_eq!(claims.sub,address);
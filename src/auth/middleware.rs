@@ -1,6 +1,6 @@
 //middleware 
 use actix_web::{
-    Error, HttpMessage, HttpResponse, body::BoxBody, dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready}
+    Error, HttpMessage, ResponseError, body::BoxBody, dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready}
 };
 use futures_util::future::LocalBoxFuture;
 use std::future::{ready,Ready};
@@ -10,6 +10,7 @@ use std::sync::Arc;
 
 
 use super::{jwt::JwtService,Claims,SessionStore};
+use crate::error::ApiError;
 
 //middleware factory
 
@@ -71,9 +72,7 @@ where
                             if session_store.is_token_revoked(&claims.jti).await {
                                 return Ok(ServiceResponse::new(
                                     req.into_parts().0,
-                                    HttpResponse::Unauthorized().json(serde_json::json!({
-                                        "error": "Token has been revoked"
-                                    }))
+                                    ApiError::Unauthorized("Token has been revoked".to_string()).error_response()
                                 ));
                             }
 
@@ -84,12 +83,10 @@ where
                             return service.call(req).await;
                         }
                         Err(e) => {
-                            log::warn!("JWT verification failed: {}", e);
+                            tracing::warn!("JWT verification failed: {}", e);
                             return Ok(ServiceResponse::new(
                                 req.into_parts().0,
-                                HttpResponse::Unauthorized().json(serde_json::json!({
-                                    "error": "Invalid or expired token"
-                                }))
+                                ApiError::Unauthorized("Invalid or expired token".to_string()).error_response()
                             ));
                         }
                     }
@@ -99,9 +96,7 @@ where
             // No valid authentication
             Ok(ServiceResponse::new(
                 req.into_parts().0,
-                HttpResponse::Unauthorized().json(serde_json::json!({
-                    "error": "Authentication required"
-                }))
+                ApiError::Unauthorized("Authentication required".to_string()).error_response()
             ))
         })
     }
@@ -110,4 +105,8 @@ where
 //helpers to extract claims from request
 pub fn get_claims(req: &actix_web::HttpRequest) -> Option<Claims> {
     req.extensions().get::<Claims>().cloned()
+}
+
+pub fn require_claims(req: &actix_web::HttpRequest) -> Result<Claims, ApiError> {
+    get_claims(req).ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))
 }
\ No newline at end of file
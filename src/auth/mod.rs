@@ -4,10 +4,11 @@ pub mod middleware;
 pub mod verification;
 pub mod routes;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::collections::HashSet;
 
 //request response models
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct  AuthReq{
     pub address: String, //wallet address
     pub message: String, //message sign garna
@@ -15,7 +16,7 @@ pub struct  AuthReq{
     pub public_key: String, //wallet ko public key
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AuthRes{
     pub access_token: String, 
     pub refresh_token: String, 
@@ -24,7 +25,7 @@ pub struct AuthRes{
     pub user: UserInfo, 
 }
 
-#[derive(Debug, Serialize, Clone,Deserialize)]
+#[derive(Debug, Serialize, Clone,Deserialize, ToSchema)]
 pub struct UserInfo{
     pub address: String,
     pub wallet_addresses: Vec<String>, //sabbai wallet addresses haru
@@ -46,14 +47,22 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::collections::HashMap;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct UserSession{
+    #[allow(dead_code)]
     pub address: String,
     pub wallet_addresses: HashSet<String>,
     pub created_at: i64,
     pub last_active_at: i64,
 }
 
+//sessions kept across restarts, revoked tokens must stay revoked
+#[derive(Default, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub sessions: HashMap<String, UserSession>,
+    pub revoked_tokens: HashSet<String>,
+}
+
 //session state
 pub struct  SessionStore {
     sessions: Arc<RwLock<HashMap<String,UserSession>>>,
@@ -101,6 +110,23 @@ impl SessionStore {
         let revoked = self.revoked_tokens.read().await;
         revoked.contains(jti)
     }
+    pub async fn session_count(&self) -> usize {
+        self.sessions.read().await.len()
+    }
+    pub async fn revoked_count(&self) -> usize {
+        self.revoked_tokens.read().await.len()
+    }
+    /// Sessions and revoked token ids, written to disk on shutdown
+    pub async fn snapshot(&self) -> SessionSnapshot {
+        SessionSnapshot {
+            sessions: self.sessions.read().await.clone(),
+            revoked_tokens: self.revoked_tokens.read().await.clone(),
+        }
+    }
+    pub async fn restore(&self, snapshot: SessionSnapshot) {
+        self.sessions.write().await.extend(snapshot.sessions);
+        self.revoked_tokens.write().await.extend(snapshot.revoked_tokens);
+    }
     pub async fn cleanup_expired(&self,max_age_sec:i64){
         let now = chrono::Utc::now().timestamp();
         let mut  sessions =self.sessions.write().await;
@@ -1,7 +1,12 @@
-use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web::{self}};
+use actix_web::{HttpRequest, get, post, web::{self}};
+use serde::Serialize;
+use utoipa::ToSchema;
 use std::{ sync::Arc};
 
-use crate::auth::middleware::get_claims;
+use crate::address;
+use crate::auth::middleware::require_claims;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::telemetry::{redact_address, redact_token};
 
 use super::{
     jwt::JwtService,
@@ -9,53 +14,84 @@ use super::{
     AuthReq,AuthRes,UserInfo,SessionStore,
 };
 
+#[derive(Serialize, ToSchema)]
+pub struct ChallengeRes {
+    pub message: String,
+    pub address: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MessageRes {
+    pub message: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AccessTokenRes {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AddWalletRes {
+    pub message: String,
+    pub address: String,
+}
+
 //challenge message geeneare garne endpoint
 #[derive(serde::Deserialize)]
 pub struct ChallengeReq{
     address:String,
 }
 
-#[get("/api/auth/challenge")]
-pub async fn get_challenge(query: web::Query<ChallengeReq>)-> impl Responder {
+#[utoipa::path(
+    tag = "auth",
+    params(("address" = String, Query, description = "Wallet address to sign in with")),
+    responses((status = 200, body = ChallengeRes), (status = 429, description = "Rate limited, retry after Retry-After seconds", body = ErrorBody)),
+)]
+#[get("/api/v1/auth/challenge")]
+pub async fn get_challenge(query: web::Query<ChallengeReq>)-> ApiResult<ChallengeRes> {
     let challenge = generate_challenge(&query.address);
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "message":challenge,
-        "address":query.address
+    Ok(web::Json(ChallengeRes {
+        message: challenge,
+        address: query.into_inner().address,
     }))
 }
 
 //post sign verify garne endpoint
-#[post("/api/auth/login")]
+#[utoipa::path(
+    tag = "auth",
+    request_body = AuthReq,
+    responses((status = 200, body = AuthRes), (status = 400, body = ErrorBody), (status = 401, body = ErrorBody), (status = 429, description = "Rate limited, retry after Retry-After seconds", body = ErrorBody)),
+)]
+#[post("/api/v1/auth/login")]
 pub async  fn login(
     auth_req: web::Json<AuthReq>,
     session_store: web::Data<Arc<SessionStore>>,
-) -> impl  Responder{
-    log::info!("lofin attempt for address: {}",auth_req.address);
+) -> ApiResult<AuthRes> {
+    tracing::info!(address = %redact_address(&auth_req.address), "Login attempt");
+
+    address::validate(&auth_req.address)
+        .map_err(|e| ApiError::InvalidAddress(format!("Invalid address: {}", e)))?;
 
     //timesatmp verify
     if let Err(e) = verify_challenge_timestamp(&auth_req.message,300){
-        log::warn!("Challenge timestamp verification old /failed: {}",e);
-        return  HttpResponse::BadRequest().json(serde_json::json!({
-            "error":format!("Invlaid challenfe :{}",e)
-        }));
+        tracing::warn!(error = %e, "Challenge timestamp too old or invalid");
+        return Err(ApiError::BadRequest(format!("Invalid challenge: {}", e)));
     }
     //verify sign
     match verify_sign(&auth_req.message, &auth_req.signature, &auth_req.public_key) {
         Ok(true) => {
-            log::info!("Signature verified for : {}",auth_req.address);
+            tracing::info!(address = %redact_address(&auth_req.address), "Signature verified");
         }
         Ok(false)=> {
-            log::warn!("Invalid sign for: {}",auth_req.address);
-            return  HttpResponse::Unauthorized().json(serde_json::json!({
-                "error": "invalid sign"
-            }));
+            tracing::warn!(address = %redact_address(&auth_req.address), "Invalid signature");
+            return Err(ApiError::Unauthorized("Invalid signature".to_string()));
         }
         Err(e)=>{
-            log::error!("Sign verification error:{}",e);
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "error": format!("Sign verificationfailed:{}",e)
-        }));   
+            tracing::error!(error = %e, "Signature verification error");
+            return Err(ApiError::BadRequest(format!("Signature verification failed: {}", e)));
         }
         
     }
@@ -63,26 +99,15 @@ pub async  fn login(
     let session = session_store.create_session(auth_req.address.clone()).await;
     let address: Vec<String> = session.wallet_addresses.iter().cloned().collect();
     //tokens haru
-    let access_token = match JwtService::generate_access_token(&auth_req.address, address.clone()){
-        Ok(token)=> token,
-        Err(e)=>{
-            log::error!("Failed to generate access tokken:{}",e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error":"Failed to generate token"
-            }));
-        }
-    };
-
-    let refresh_token=match  JwtService::generate_refresh_token(&auth_req.address, address.clone()) {
-        Ok(token) => token,
-        Err(e)=>{
-            log::error!("failed to generate referesh token:{}",e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error":"failed to genrate refersh token"
-            }));
-        }
-        
-    };
+    let access_token = JwtService::generate_access_token(&auth_req.address, address.clone()).map_err(|e| {
+        tracing::error!(error = %e, "Failed to generate access token");
+        ApiError::Internal("Failed to generate access token".to_string())
+    })?;
+
+    let refresh_token = JwtService::generate_refresh_token(&auth_req.address, address.clone()).map_err(|e| {
+        tracing::error!(error = %e, "Failed to generate refresh token");
+        ApiError::Internal("Failed to generate refresh token".to_string())
+    })?;
     //return the auth response
 
     let response = AuthRes{
@@ -92,93 +117,90 @@ pub async  fn login(
         user: UserInfo { 
             address: auth_req.address.clone(), wallet_addresses: address, created_at: session.created_at},
     };
-    log::info!("Login sucess for: {}",auth_req.address);
+    tracing::info!(address = %redact_address(&auth_req.address), "Login succeeded");
 
-    HttpResponse::Ok().json(response)
+    Ok(web::Json(response))
 
 }
 
 //logout ko lagi
+#[utoipa::path(
+    context_path = "/api/v1/auth",
+    tag = "auth",
+    security(("bearer" = [])),
+    responses((status = 200, body = MessageRes), (status = 401, body = ErrorBody)),
+)]
 #[post("/logout")]
 pub async fn logout(
     req: HttpRequest,
     session_store: web::Data<Arc<SessionStore>>,
-) -> impl Responder{
-    if let Some(claims) = get_claims(&req){
-        session_store.revoked_token(claims.jti).await;
-
-        log::info!("User logged out:{}",claims.sub);
+) -> ApiResult<MessageRes> {
+    let claims = require_claims(&req)?;
+    tracing::info!(address = %redact_address(&claims.sub), jti = %redact_token(&claims.jti), "User logged out");
+    session_store.revoked_token(claims.jti).await;
 
-        return HttpResponse::Ok().json(serde_json::json!({
-            "message":"loggedout vayo"
-        }));
-    }
-    HttpResponse::Unauthorized().json(serde_json::json!({
-        "error":"unauthorized/not auhtenticated"
-    }))
+    Ok(web::Json(MessageRes { message: "Logged out".to_string() }))
 }
 
-//referesh access token 
-#[derive(serde::Deserialize)]
-pub struct RefereshRequest {
-    referesh_token:String,
+//refresh access token
+#[derive(serde::Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    #[serde(alias = "referesh_token")] //spelling the old /api/auth/referesh clients send
+    refresh_token:String,
 }
 
-#[post("/api/auth/referesh")]
-pub async fn referesh_token(req: web::Json<RefereshRequest>) -> impl Responder{
-    match JwtService::verify_token(&req.referesh_token) {
-        Ok(claims)=>{
-            match JwtService::generate_access_token(&claims.sub, claims.addresses.clone()) {
-                Ok(new_acess_tpoken)=>{
-                    HttpResponse::Ok().json(serde_json::json!({
-                        "access_token":new_acess_tpoken,
-                        "token_type":"Bearer",
-                        "expires_in":900
-                    }))
-                }
-                Err(e)=>{
-                    log::error!("Failed to generate new access token:{}",e);
-                    HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error":"failed to generate refersh token"
-                    }))
-                }
-                
-            }
-        }
-        Err(e)=> {
-            log::warn!("Invalid referesh tokken {}",e);
-            HttpResponse::Unauthorized().json(serde_json::json!({
-                "error":"invalid or expired refereh"
-            }))
-        }
-    }
+#[utoipa::path(
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses((status = 200, body = AccessTokenRes), (status = 401, body = ErrorBody), (status = 429, description = "Rate limited, retry after Retry-After seconds", body = ErrorBody)),
+)]
+#[post("/api/v1/auth/refresh")]
+pub async fn refresh_access_token(req: web::Json<RefreshRequest>) -> ApiResult<AccessTokenRes> {
+    let claims = JwtService::verify_token(&req.refresh_token).map_err(|e| {
+        tracing::warn!(error = %e, "Invalid refresh token");
+        ApiError::Unauthorized("Invalid or expired refresh token".to_string())
+    })?;
+
+    let access_token = JwtService::generate_access_token(&claims.sub, claims.addresses.clone()).map_err(|e| {
+        tracing::error!(error = %e, "Failed to generate new access token");
+        ApiError::Internal("Failed to generate access token".to_string())
+    })?;
+
+    Ok(web::Json(AccessTokenRes {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: 900,
+    }))
 }
 
 //current user info
+#[utoipa::path(
+    context_path = "/api/v1/auth",
+    tag = "auth",
+    security(("bearer" = [])),
+    responses((status = 200, body = UserInfo), (status = 401, body = ErrorBody)),
+)]
 #[get("/me")]
 pub async fn get_current_user(
     req: HttpRequest,
     session_store: web::Data<Arc<SessionStore>>,
-) -> impl Responder {
-    if let Some(claims) = get_claims(&req) {
-        if let Some(session) = session_store.get_session(&claims.sub).await {
-            let addresses: Vec<String> = session.wallet_addresses.iter().cloned().collect();
-            
-            return HttpResponse::Ok().json(UserInfo {
-                address: claims.sub,
-                wallet_addresses: addresses,
-                created_at: session.created_at,
-            });
-        }
-    }
-
-    HttpResponse::Unauthorized().json(serde_json::json!({
-        "error": "Not authenticated"
+) -> ApiResult<UserInfo> {
+    let claims = require_claims(&req)?;
+    let session = session_store
+        .get_session(&claims.sub)
+        .await
+        .ok_or_else(|| ApiError::Unauthorized("Session not found".to_string()))?;
+    let addresses: Vec<String> = session.wallet_addresses.iter().cloned().collect();
+
+    Ok(web::Json(UserInfo {
+        address: claims.sub,
+        wallet_addresses: addresses,
+        created_at: session.created_at,
     }))
 }
 
 //add additional wallet
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, ToSchema)]
 pub struct AddWalletRequest {
     address: String,
     message: String,
@@ -186,21 +208,24 @@ pub struct AddWalletRequest {
     public_key: String,
 }
 
+#[utoipa::path(
+    context_path = "/api/v1/auth",
+    tag = "auth",
+    security(("bearer" = [])),
+    request_body = AddWalletRequest,
+    responses((status = 200, body = AddWalletRes), (status = 400, body = ErrorBody), (status = 401, body = ErrorBody)),
+)]
 #[post("/add-wallet")]
 pub async fn add_wallet(
     req: HttpRequest,
     add_wallet_req: web::Json<AddWalletRequest>,
     session_store: web::Data<Arc<SessionStore>>,
-) -> impl Responder {
+) -> ApiResult<AddWalletRes> {
     // Get current user
-    let claims = match get_claims(&req) {
-        Some(c) => c,
-        None => {
-            return HttpResponse::Unauthorized().json(serde_json::json!({
-                "error": "Not authenticated"
-            }));
-        }
-    };
+    let claims = require_claims(&req)?;
+
+    address::validate(&add_wallet_req.address)
+        .map_err(|e| ApiError::InvalidAddress(format!("Invalid address: {}", e)))?;
 
     // Verify signature for new wallet
     match verify_sign(
@@ -208,26 +233,20 @@ pub async fn add_wallet(
         &add_wallet_req.signature,
         &add_wallet_req.public_key,
     ) {
-        Ok(true) => {
-            // Add wallet to session
-            if let Err(e) = session_store.add_wallet(&claims.sub, add_wallet_req.address.clone()).await {
-                return HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": format!("Failed to add wallet: {}", e)
-                }));
-            }
-
-            log::info!("Added wallet {} to user {}", add_wallet_req.address, claims.sub);
-
-            HttpResponse::Ok().json(serde_json::json!({
-                "message": "Wallet added successfully",
-                "address": add_wallet_req.address
-            }))
-        }
-        Ok(false) | Err(_) => {
-            HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Invalid signature"
-            }))
-        }
+        Ok(true) => {}
+        Ok(false) | Err(_) => return Err(ApiError::BadRequest("Invalid signature".to_string())),
     }
-    
+
+    // Add wallet to session
+    session_store
+        .add_wallet(&claims.sub, add_wallet_req.address.clone())
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to add wallet: {}", e)))?;
+
+    tracing::info!(wallet = %redact_address(&add_wallet_req.address), address = %redact_address(&claims.sub), "Added wallet");
+
+    Ok(web::Json(AddWalletRes {
+        message: "Wallet added successfully".to_string(),
+        address: add_wallet_req.address.clone(),
+    }))
 }
@@ -77,7 +77,7 @@ pub fn verify_sign(
         } else if len <= 0xFFFF {
             out.push(0x59); out.push(((len >> 8) & 0xFF) as u8); out.push((len & 0xFF) as u8);
         } else {
-            out.push(0x59); out.push(((len >> 8) & 0xFF) as u8); out.push((len & 0xFF) as u8);
+            out.push(0x5a); out.extend_from_slice(&(len as u32).to_be_bytes());
         }
     }
     fn cbor_encode_text(s: &str) -> Vec<u8> {
@@ -91,7 +91,7 @@ pub fn verify_sign(
         } else if len <= 0xFFFF {
             out.push(0x79); out.push(((len >> 8) & 0xFF) as u8); out.push((len & 0xFF) as u8);
         } else {
-            out.push(0x79); out.push(((len >> 8) & 0xFF) as u8); out.push((len & 0xFF) as u8);
+            out.push(0x7a); out.extend_from_slice(&(len as u32).to_be_bytes());
         }
         out.extend_from_slice(bytes);
         out
@@ -114,10 +114,10 @@ pub fn verify_sign(
         idx += ulen;
         // payload
         let (paylen, pay_inner_opt) = read_cbor_item(&sig_bytes, idx)?;
-        let payload_inner = pay_inner_opt.unwrap_or_else(|| vec![]);
+        let payload_inner = pay_inner_opt.unwrap_or_default();
         idx += paylen;
         // signature
-        let (slen, s_inner_opt) = read_cbor_item(&sig_bytes, idx)?;
+        let (_slen, s_inner_opt) = read_cbor_item(&sig_bytes, idx)?;
         let signature_inner = s_inner_opt.ok_or("signature not bstr")?;
         if signature_inner.len() != 64 { return Err(format!("signature not 64 bytes (got {})", signature_inner.len())) }
         (protected_inner, payload_inner, signature_inner)
@@ -155,7 +155,7 @@ pub fn verify_sign(
         return Ok(true);
     }
 
-    log::warn!("Signature verification failed; message len {} sig hex {}", message.len(), hex::encode(&signature_raw));
+    tracing::warn!(message_len = message.len(), signature_len = signature_raw.len(), "Signature verification failed");
     Ok(false)
 }
 //now veify address ownership
@@ -175,7 +175,7 @@ pub fn verify_sign(
     
 //     // Aile we'll accept the address if the signature is valid
   
-//     log::info!("Verifying address; {} with public key",address);
+//     tracing::info!("Verifying address; {} with public key",address);
 //     Ok(true)
 
 // }
@@ -230,7 +230,7 @@ mod tests {
         let challenge = generate_challenge(address);
 
         assert!(challenge.contains(address));
-        assert!(challenge.contains("TimestampL"));
+        assert!(challenge.contains("Timestamp"));
         assert!(challenge.contains("Nonce"));
     }
 
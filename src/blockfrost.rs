@@ -0,0 +1,83 @@
+//Blockfrost API client, the chain data provider behind the explorer state
+use once_cell::sync::Lazy;
+use std::time::Instant;
+use tracing::{field, Instrument, Span};
+
+use crate::{metrics, network::NETWORK, telemetry};
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+
+pub fn api_key() -> Option<String> {
+    std::env::var("BLOCKFROST_API_KEY").ok()
+}
+
+async fn request(path: &str, key: &str) -> Result<reqwest::Response, String> {
+    let url = format!("{}{}", NETWORK.blockfrost_url, path);
+    // the endpoint, not the path, so addresses stay out of spans
+    let endpoint = metrics::blockfrost_endpoint(path);
+    let span = tracing::info_span!(
+        "blockfrost",
+        otel.kind = "client",
+        otel.name = %format!("GET {}", endpoint),
+        otel.status_code = field::Empty,
+        http.route = %endpoint,
+        http.status_code = field::Empty,
+        error = field::Empty,
+    );
+    async {
+        let started = Instant::now();
+        let mut request = CLIENT.get(&url).header("project_id", key);
+        for (name, value) in telemetry::trace_headers() {
+            request = request.header(name, value);
+        }
+        let response = request.send().await;
+        // a 404 is an answer (the lookup missed), not a failed call
+        let ok = matches!(&response, Ok(r) if r.status().is_success() || r.status() == reqwest::StatusCode::NOT_FOUND);
+        metrics::blockfrost_call(path, started, ok);
+        let span = Span::current();
+        match &response {
+            Ok(r) => span.record("http.status_code", r.status().as_u16()),
+            Err(e) => span.record("error", field::display(e)),
+        };
+        if !ok {
+            span.record("otel.status_code", "ERROR");
+        }
+        response.map_err(|e| format!("Blockfrost request failed: {}", e))
+    }
+    .instrument(span)
+    .await
+}
+
+/// GET a Blockfrost path (e.g. `/txs/{hash}`), any non 2xx status is an error
+pub async fn get<T: serde::de::DeserializeOwned>(path: &str, key: &str) -> Result<T, String> {
+    let response = request(path, key).await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Blockfrost API error: {} {}", status, body));
+    }
+
+    response
+        .json::<T>()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+/// Like `get` but a 404 is `Ok(None)`, for lookups that may legitimately miss
+pub async fn find(path: &str, key: &str) -> Result<Option<serde_json::Value>, String> {
+    let response = request(path, key).await?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(format!("Blockfrost API error: {}", response.status()));
+    }
+
+    response
+        .json::<serde_json::Value>()
+        .await
+        .map(Some)
+        .map_err(|e| format!("Failed to parse response: {}", e))
+}
@@ -0,0 +1,285 @@
+//CIP-57 blueprints: typed rendering of datums and redeemers for known scripts
+use once_cell::sync::Lazy;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::models::TxScriptData;
+
+// Schemas nested deeper than this are treated as a mismatch
+const MAX_DEPTH: usize = 64;
+
+/// Datum and redeemer schemas of one validator
+#[derive(Debug, Clone)]
+pub struct Validator {
+    pub title: String,
+    pub datum: Option<Value>,
+    pub redeemer: Option<Value>,
+    pub definitions: Arc<Value>,
+}
+
+// Blueprints (plutus.json) found in BLUEPRINTS_DIR, keyed by script hash
+pub static BLUEPRINTS: Lazy<HashMap<String, Validator>> = Lazy::new(|| {
+    let Ok(dir) = std::env::var("BLUEPRINTS_DIR") else {
+        return HashMap::new();
+    };
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::warn!("Could not read blueprints from {}: {}", dir, e);
+            return HashMap::new();
+        }
+    };
+
+    let mut validators = HashMap::new();
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str::<Value>(&content).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(blueprint) => validators.extend(parse_blueprint(&blueprint)),
+            Err(e) => tracing::warn!("Skipping blueprint {}: {}", path.display(), e),
+        }
+    }
+    tracing::info!("Loaded blueprints for {} scripts", validators.len());
+    validators
+});
+
+// Script hash of a compiled validator: blake2b-224 of the language tag and the script bytes
+fn script_hash(compiled_code: &str, plutus_version: &str) -> Option<String> {
+    let tag = match plutus_version {
+        "v1" => 1,
+        "v2" => 2,
+        "v3" => 3,
+        _ => return None,
+    };
+    let mut bytes = vec![tag];
+    bytes.extend(hex::decode(compiled_code).ok()?);
+    Some(pallas_crypto::hash::Hasher::<224>::hash(&bytes).to_string())
+}
+
+/// Validators of a blueprint by script hash. Multi purpose validators appear once per
+/// purpose under the same hash, the datum and redeemer schemas are merged
+pub fn parse_blueprint(blueprint: &Value) -> HashMap<String, Validator> {
+    let definitions = Arc::new(blueprint.get("definitions").cloned().unwrap_or(json!({})));
+    let version = blueprint
+        .pointer("/preamble/plutusVersion")
+        .and_then(|v| v.as_str())
+        .unwrap_or("v2");
+
+    let mut validators: HashMap<String, Validator> = HashMap::new();
+    for validator in blueprint.get("validators").and_then(|v| v.as_array()).into_iter().flatten() {
+        let hash = validator.get("hash").and_then(|h| h.as_str()).map(String::from).or_else(|| {
+            let code = validator.get("compiledCode")?.as_str()?;
+            script_hash(code, version)
+        });
+        let Some(hash) = hash else {
+            continue;
+        };
+        let schema = |key: &str| validator.get(key).and_then(|p| p.get("schema")).cloned();
+
+        let entry = validators.entry(hash).or_insert_with(|| Validator {
+            title: validator.get("title").and_then(|t| t.as_str()).unwrap_or("").to_string(),
+            datum: None,
+            redeemer: None,
+            definitions: definitions.clone(),
+        });
+        if entry.datum.is_none() {
+            entry.datum = schema("datum");
+        }
+        if entry.redeemer.is_none() {
+            entry.redeemer = schema("redeemer");
+        }
+    }
+    validators
+}
+
+pub fn lookup(script_hash: &str) -> Option<&'static Validator> {
+    BLUEPRINTS.get(script_hash)
+}
+
+fn resolve<'a>(reference: &str, definitions: &'a Value) -> Result<&'a Value, String> {
+    let name = reference
+        .strip_prefix("#/definitions/")
+        .ok_or_else(|| format!("Unsupported reference {}", reference))?
+        .replace("~1", "/")
+        .replace("~0", "~");
+    definitions.get(&name).ok_or_else(|| format!("Unknown definition {}", name))
+}
+
+/// Typed rendering of detailed schema Plutus data against a blueprint schema
+pub fn render(schema: &Value, data: &Value, definitions: &Value) -> Result<Value, String> {
+    render_at(schema, data, definitions, 0)
+}
+
+fn render_at(schema: &Value, data: &Value, definitions: &Value, depth: usize) -> Result<Value, String> {
+    if depth > MAX_DEPTH {
+        return Err("Schema nested too deep".to_string());
+    }
+    if let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) {
+        return render_at(resolve(reference, definitions)?, data, definitions, depth + 1);
+    }
+    if let Some(alternatives) = schema.get("anyOf").and_then(|a| a.as_array()) {
+        return alternatives
+            .iter()
+            .find_map(|alternative| render_at(alternative, data, definitions, depth + 1).ok())
+            .ok_or_else(|| "No alternative of the schema matches".to_string());
+    }
+
+    let expect = |key: &str| data.get(key).ok_or_else(|| format!("Expected {} data", key));
+    match schema.get("dataType").and_then(|t| t.as_str()) {
+        Some("integer") => expect("int").cloned(),
+        Some("bytes") => expect("bytes").cloned(),
+        Some("list") => {
+            let items = expect("list")?.as_array().ok_or("Malformed list")?;
+            match schema.get("items") {
+                // tuples list one schema per position
+                Some(Value::Array(schemas)) if schemas.len() == items.len() => schemas
+                    .iter()
+                    .zip(items)
+                    .map(|(s, item)| render_at(s, item, definitions, depth + 1))
+                    .collect(),
+                Some(Value::Array(_)) => Err("Tuple length mismatch".to_string()),
+                Some(item_schema) => items
+                    .iter()
+                    .map(|item| render_at(item_schema, item, definitions, depth + 1))
+                    .collect(),
+                None => Ok(Value::Array(items.clone())),
+            }
+        }
+        Some("map") => {
+            let entries = expect("map")?.as_array().ok_or("Malformed map")?;
+            let any = json!({});
+            let keys = schema.get("keys").unwrap_or(&any);
+            let values = schema.get("values").unwrap_or(&any);
+            entries
+                .iter()
+                .map(|entry| {
+                    Ok(json!({
+                        "key": render_at(keys, entry.get("k").unwrap_or(&Value::Null), definitions, depth + 1)?,
+                        "value": render_at(values, entry.get("v").unwrap_or(&Value::Null), definitions, depth + 1)?,
+                    }))
+                })
+                .collect()
+        }
+        Some("constructor") => {
+            let index = expect("constructor")?.as_u64().ok_or("Malformed constructor")?;
+            if schema.get("index").and_then(|i| i.as_u64()) != Some(index) {
+                return Err(format!("Constructor {} does not match the schema", index));
+            }
+            let fields = data.get("fields").and_then(|f| f.as_array()).ok_or("Malformed constructor")?;
+            let field_schemas = schema.get("fields").and_then(|f| f.as_array()).cloned().unwrap_or_default();
+            if field_schemas.len() != fields.len() {
+                return Err(format!("Constructor {} has {} fields, expected {}", index, fields.len(), field_schemas.len()));
+            }
+            let name = schema.get("title").and_then(|t| t.as_str());
+            // enum like constructors (Bool, unit variants) render as their name
+            if let (Some(name), true) = (name, fields.is_empty()) {
+                return Ok(json!(name));
+            }
+
+            let rendered: Vec<Value> = field_schemas
+                .iter()
+                .zip(fields)
+                .map(|(s, field)| render_at(s, field, definitions, depth + 1))
+                .collect::<Result<_, _>>()?;
+            let titles: Option<Vec<&str>> = field_schemas.iter().map(|s| s.get("title").and_then(|t| t.as_str())).collect();
+            let fields = match titles {
+                Some(titles) => Value::Object(titles.into_iter().map(String::from).zip(rendered).collect::<Map<_, _>>()),
+                None => Value::Array(rendered),
+            };
+            Ok(json!({ "constructor": name.map(|n| json!(n)).unwrap_or(json!(index)), "fields": fields }))
+        }
+        // opaque Data and builtins outside the data encoding stay as they are
+        _ => Ok(data.clone()),
+    }
+}
+
+/// Typed datums and redeemers of a transaction, for the scripts a blueprint is registered for
+pub fn annotate(data: &mut TxScriptData) {
+    if BLUEPRINTS.is_empty() {
+        return;
+    }
+    for redeemer in data.redeemers.iter_mut() {
+        let validator = redeemer.script_hash.as_deref().and_then(lookup);
+        if let Some((validator, schema)) = validator.and_then(|v| Some((v, v.redeemer.as_ref()?))) {
+            redeemer.typed = render(schema, &redeemer.data, &validator.definitions).ok();
+        }
+    }
+    for datum in data.datums.iter_mut() {
+        let validator = datum.script_hash.as_deref().and_then(lookup);
+        if let (Some(validator), Some(value)) = (validator, datum.data.as_ref()) {
+            if let Some(schema) = validator.datum.as_ref() {
+                datum.typed = render(schema, value, &validator.definitions).ok();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blueprint() -> Value {
+        json!({
+            "preamble": { "plutusVersion": "v3" },
+            "validators": [
+                {
+                    "title": "escrow.escrow.spend",
+                    "hash": "aa".repeat(28),
+                    "datum": { "schema": { "$ref": "#/definitions/escrow~1Datum" } },
+                    "redeemer": { "schema": { "$ref": "#/definitions/escrow~1Action" } }
+                },
+                { "title": "escrow.escrow.else", "hash": "aa".repeat(28), "redeemer": { "schema": {} } }
+            ],
+            "definitions": {
+                "ByteArray": { "dataType": "bytes" },
+                "Int": { "dataType": "integer" },
+                "Bool": { "anyOf": [
+                    { "title": "False", "dataType": "constructor", "index": 0, "fields": [] },
+                    { "title": "True", "dataType": "constructor", "index": 1, "fields": [] }
+                ] },
+                "escrow/Datum": { "anyOf": [{
+                    "title": "Datum", "dataType": "constructor", "index": 0,
+                    "fields": [
+                        { "title": "owner", "$ref": "#/definitions/ByteArray" },
+                        { "title": "deadline", "$ref": "#/definitions/Int" },
+                        { "title": "open", "$ref": "#/definitions/Bool" },
+                        { "title": "amounts", "dataType": "list", "items": { "$ref": "#/definitions/Int" } }
+                    ]
+                }] },
+                "escrow/Action": { "anyOf": [
+                    { "title": "Cancel", "dataType": "constructor", "index": 0, "fields": [] },
+                    { "title": "Claim", "dataType": "constructor", "index": 1, "fields": [{ "$ref": "#/definitions/Int" }] }
+                ] }
+            }
+        })
+    }
+
+    #[test]
+    fn test_typed_rendering() {
+        let validators = parse_blueprint(&blueprint());
+        let validator = &validators[&"aa".repeat(28)];
+        assert_eq!(validator.title, "escrow.escrow.spend");
+
+        let datum = json!({ "constructor": 0, "fields": [
+            { "bytes": "cafe" }, { "int": 1700000000 }, { "constructor": 1, "fields": [] }, { "list": [{ "int": 1 }, { "int": 2 }] }
+        ] });
+        assert_eq!(
+            render(validator.datum.as_ref().unwrap(), &datum, &validator.definitions).unwrap(),
+            json!({ "constructor": "Datum", "fields": { "owner": "cafe", "deadline": 1700000000, "open": "True", "amounts": [1, 2] } })
+        );
+
+        let redeemer = json!({ "constructor": 1, "fields": [{ "int": 5 }] });
+        assert_eq!(
+            render(validator.redeemer.as_ref().unwrap(), &redeemer, &validator.definitions).unwrap(),
+            json!({ "constructor": "Claim", "fields": [5] })
+        );
+        // constructor 2 is not an Action
+        let unknown = json!({ "constructor": 2, "fields": [] });
+        assert!(render(validator.redeemer.as_ref().unwrap(), &unknown, &validator.definitions).is_err());
+    }
+}
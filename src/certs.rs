@@ -0,0 +1,440 @@
+//certificates and withdrawals from block cbor or oura certificate records
+use bech32::{Bech32, Hrp};
+use pallas_addresses::Address;
+use pallas_codec::utils::Nullable;
+use pallas_primitives::conway::{Anchor, DRep};
+use pallas_primitives::{alonzo, conway, PoolMetadata, RationalNumber, Relay, StakeCredential};
+use pallas_traverse::{MultiEraBlock, MultiEraTx};
+use serde_json::{json, Value};
+
+use crate::address;
+use crate::models::{Certificate, Withdrawal};
+use crate::pools;
+
+// Oura v1 record keys of the certificate events it emits (pre-Conway only)
+pub const OURA_CERT_RECORDS: [&str; 5] =
+    ["stake_registration", "stake_deregistration", "stake_delegation", "pool_registration", "pool_retirement"];
+
+/// Block the certificates and withdrawals were included in
+#[derive(Debug, Clone, Copy)]
+pub struct Inclusion {
+    pub block_number: u64,
+    pub slot: u64,
+    pub timestamp: u64,
+}
+
+fn certificate(tx_hash: &str, cert_index: u32, at: Inclusion, kind: &str) -> Certificate {
+    Certificate {
+        tx_hash: tx_hash.to_string(),
+        cert_index,
+        block_number: at.block_number,
+        slot: at.slot,
+        timestamp: at.timestamp,
+        kind: kind.to_string(),
+        stake_address: None,
+        pool_id: None,
+        drep: None,
+        deposit: None,
+        epoch: None,
+        details: json!({}),
+    }
+}
+
+/// Reward address from its raw bytes (hex), as found in withdrawals and pool parameters
+pub fn reward_address(bytes_hex: &str) -> Option<String> {
+    let bytes = hex::decode(bytes_hex).ok()?;
+    Address::from_bytes(&bytes).ok().map(|a| a.to_string())
+}
+
+// Reward address of a key (0xe0) or script (0xf0) credential on the followed network
+fn stake_address(is_script: bool, hash: &[u8]) -> Option<String> {
+    let header = if is_script { 0xf0 } else { 0xe0 } | address::expected_network_id();
+    let mut bytes = vec![header];
+    bytes.extend_from_slice(hash);
+    Address::from_bytes(&bytes).ok().map(|a| a.to_string())
+}
+
+fn credential_address(credential: &StakeCredential) -> Option<String> {
+    match credential {
+        StakeCredential::AddrKeyhash(hash) => stake_address(false, hash.as_slice()),
+        StakeCredential::ScriptHash(hash) => stake_address(true, hash.as_slice()),
+    }
+}
+
+pub fn credential_json(credential: &StakeCredential) -> Value {
+    match credential {
+        StakeCredential::AddrKeyhash(hash) => json!({ "kind": "key", "hash": hash.to_string() }),
+        StakeCredential::ScriptHash(hash) => json!({ "kind": "script", "hash": hash.to_string() }),
+    }
+}
+
+/// Hash of the script that has to witness the certificate, None for key credentials
+pub fn script_credential(cert: &Certificate) -> Option<String> {
+    if let Some(stake_address) = &cert.stake_address {
+        let stake = address::inspect(stake_address).ok()?.stake?;
+        return (stake.kind == "script").then_some(stake.hash);
+    }
+    // drep registration, update and retirement are witnessed by the drep itself
+    if let Some(drep) = cert.drep.as_deref().filter(|_| cert.kind.starts_with("drep_")) {
+        let (hrp, hash) = bech32::decode(drep).ok()?;
+        return (hrp.as_str() == "drep_script").then(|| hex::encode(hash));
+    }
+    let cold = cert.details.get("cold_credential")?;
+    match cold.get("kind")?.as_str()? {
+        "script" => cold.get("hash")?.as_str().map(String::from),
+        _ => None,
+    }
+}
+
+/// CIP-105 drep id of a key or script hash
+pub fn drep_id(is_script: bool, hash: &[u8]) -> Option<String> {
+    let hrp = Hrp::parse(if is_script { "drep_script" } else { "drep" }).ok()?;
+    bech32::encode::<Bech32>(hrp, hash).ok()
+}
+
+fn drep(drep: &DRep) -> Option<String> {
+    match drep {
+        DRep::Key(hash) => drep_id(false, hash.as_slice()),
+        DRep::Script(hash) => drep_id(true, hash.as_slice()),
+        DRep::Abstain => Some("drep_always_abstain".to_string()),
+        DRep::NoConfidence => Some("drep_always_no_confidence".to_string()),
+    }
+}
+
+fn drep_credential(credential: &StakeCredential) -> Option<String> {
+    match credential {
+        StakeCredential::AddrKeyhash(hash) => drep_id(false, hash.as_slice()),
+        StakeCredential::ScriptHash(hash) => drep_id(true, hash.as_slice()),
+    }
+}
+
+pub fn anchor_json(anchor: &Nullable<Anchor>) -> Value {
+    match anchor {
+        Nullable::Some(anchor) => json!({ "url": anchor.url, "data_hash": anchor.content_hash.to_string() }),
+        _ => Value::Null,
+    }
+}
+
+fn relay_json(relay: &Relay) -> Value {
+    let port = |port: &Nullable<u32>| match port {
+        Nullable::Some(port) => json!(port),
+        _ => Value::Null,
+    };
+    match relay {
+        Relay::SingleHostAddr(p, ipv4, ipv6) => json!({
+            "type": "single_host_addr",
+            "port": port(p),
+            "ipv4": match ipv4 {
+                Nullable::Some(ip) if ip.len() == 4 => json!(std::net::Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3]).to_string()),
+                _ => Value::Null,
+            },
+            "ipv6": match ipv6 {
+                Nullable::Some(ip) => json!(hex::encode(ip.as_slice())),
+                _ => Value::Null,
+            },
+        }),
+        Relay::SingleHostName(p, dns) => json!({ "type": "single_host_name", "port": port(p), "dns": dns }),
+        Relay::MultiHostName(dns) => json!({ "type": "multi_host_name", "dns": dns }),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn pool_params(
+    operator: &[u8],
+    vrf_keyhash: &[u8],
+    pledge: u64,
+    cost: u64,
+    margin: &RationalNumber,
+    reward_account: &[u8],
+    owners: Vec<String>,
+    relays: &[Relay],
+    metadata: &Nullable<PoolMetadata>,
+) -> Value {
+    json!({
+        "operator": hex::encode(operator),
+        "vrf_keyhash": hex::encode(vrf_keyhash),
+        "pledge": pledge,
+        "cost": cost,
+        "margin": if margin.denominator == 0 { 0.0 } else { margin.numerator as f64 / margin.denominator as f64 },
+        "reward_account": reward_address(&hex::encode(reward_account)),
+        "owners": owners,
+        "relays": relays.iter().map(relay_json).collect::<Vec<_>>(),
+        "metadata": match metadata {
+            Nullable::Some(m) => json!({ "url": m.url, "hash": m.hash.to_string() }),
+            _ => Value::Null,
+        },
+    })
+}
+
+fn from_alonzo(cert: &alonzo::Certificate, mut record: Certificate) -> Certificate {
+    use alonzo::Certificate as C;
+    match cert {
+        C::StakeRegistration(cred) => {
+            record.kind = "stake_registration".to_string();
+            record.stake_address = credential_address(cred);
+        }
+        C::StakeDeregistration(cred) => {
+            record.kind = "stake_deregistration".to_string();
+            record.stake_address = credential_address(cred);
+        }
+        C::StakeDelegation(cred, pool) => {
+            record.kind = "stake_delegation".to_string();
+            record.stake_address = credential_address(cred);
+            record.pool_id = pools::pool_hash_to_bech32(&pool.to_string());
+        }
+        C::PoolRegistration { operator, vrf_keyhash, pledge, cost, margin, reward_account, pool_owners, relays, pool_metadata } => {
+            record.kind = "pool_registration".to_string();
+            record.pool_id = pools::pool_hash_to_bech32(&operator.to_string());
+            let owners = pool_owners.iter().map(|o| o.to_string()).collect();
+            record.details = pool_params(
+                operator.as_slice(), vrf_keyhash.as_slice(), *pledge, *cost, margin, reward_account, owners, relays, pool_metadata,
+            );
+        }
+        C::PoolRetirement(pool, epoch) => {
+            record.kind = "pool_retirement".to_string();
+            record.pool_id = pools::pool_hash_to_bech32(&pool.to_string());
+            record.epoch = Some(*epoch);
+        }
+        C::GenesisKeyDelegation(genesis, delegate, _) => {
+            record.kind = "genesis_key_delegation".to_string();
+            record.details = json!({ "genesis_hash": genesis.to_string(), "delegate_hash": delegate.to_string() });
+        }
+        C::MoveInstantaneousRewardsCert(_) => record.kind = "move_instantaneous_rewards".to_string(),
+    }
+    record
+}
+
+fn from_conway(cert: &conway::Certificate, mut record: Certificate) -> Certificate {
+    use conway::Certificate as C;
+    let (kind, cred, pool, drep_target, deposit) = match cert {
+        C::StakeRegistration(cred) => ("stake_registration", Some(cred), None, None, None),
+        C::StakeDeregistration(cred) => ("stake_deregistration", Some(cred), None, None, None),
+        C::StakeDelegation(cred, pool) => ("stake_delegation", Some(cred), Some(pool), None, None),
+        C::Reg(cred, coin) => ("stake_registration", Some(cred), None, None, Some(*coin)),
+        C::UnReg(cred, coin) => ("stake_deregistration", Some(cred), None, None, Some(*coin)),
+        C::VoteDeleg(cred, d) => ("vote_delegation", Some(cred), None, Some(d), None),
+        C::StakeVoteDeleg(cred, pool, d) => ("stake_vote_delegation", Some(cred), Some(pool), Some(d), None),
+        C::StakeRegDeleg(cred, pool, coin) => ("stake_registration_delegation", Some(cred), Some(pool), None, Some(*coin)),
+        C::VoteRegDeleg(cred, d, coin) => ("vote_registration_delegation", Some(cred), None, Some(d), Some(*coin)),
+        C::StakeVoteRegDeleg(cred, pool, d, coin) => {
+            ("stake_vote_registration_delegation", Some(cred), Some(pool), Some(d), Some(*coin))
+        }
+        C::PoolRegistration { operator, vrf_keyhash, pledge, cost, margin, reward_account, pool_owners, relays, pool_metadata } => {
+            record.kind = "pool_registration".to_string();
+            record.pool_id = pools::pool_hash_to_bech32(&operator.to_string());
+            let owners = pool_owners.iter().map(|o| o.to_string()).collect();
+            record.details = pool_params(
+                operator.as_slice(), vrf_keyhash.as_slice(), *pledge, *cost, margin, reward_account, owners, relays, pool_metadata,
+            );
+            return record;
+        }
+        C::PoolRetirement(pool, epoch) => {
+            record.kind = "pool_retirement".to_string();
+            record.pool_id = pools::pool_hash_to_bech32(&pool.to_string());
+            record.epoch = Some(*epoch);
+            return record;
+        }
+        C::AuthCommitteeHot(cold, hot) => {
+            record.kind = "committee_hot_auth".to_string();
+            record.details = json!({ "cold_credential": credential_json(cold), "hot_credential": credential_json(hot) });
+            return record;
+        }
+        C::ResignCommitteeCold(cold, anchor) => {
+            record.kind = "committee_cold_resign".to_string();
+            record.details = json!({ "cold_credential": credential_json(cold), "anchor": anchor_json(anchor) });
+            return record;
+        }
+        C::RegDRepCert(cred, coin, anchor) => {
+            record.kind = "drep_registration".to_string();
+            record.drep = drep_credential(cred);
+            record.deposit = Some(*coin);
+            record.details = json!({ "anchor": anchor_json(anchor) });
+            return record;
+        }
+        C::UnRegDRepCert(cred, coin) => {
+            record.kind = "drep_deregistration".to_string();
+            record.drep = drep_credential(cred);
+            record.deposit = Some(*coin);
+            return record;
+        }
+        C::UpdateDRepCert(cred, anchor) => {
+            record.kind = "drep_update".to_string();
+            record.drep = drep_credential(cred);
+            record.details = json!({ "anchor": anchor_json(anchor) });
+            return record;
+        }
+    };
+
+    record.kind = kind.to_string();
+    record.stake_address = cred.and_then(credential_address);
+    record.pool_id = pool.and_then(|p| pools::pool_hash_to_bech32(&p.to_string()));
+    record.drep = drep_target.and_then(drep);
+    record.deposit = deposit;
+    record
+}
+
+/// Every certificate and withdrawal in a block, for all eras
+pub fn from_block(block: &MultiEraBlock, at: Inclusion) -> (Vec<Certificate>, Vec<Withdrawal>) {
+    let mut certificates = Vec::new();
+    let mut withdrawals = Vec::new();
+    for tx in block.txs() {
+        let (tx_certificates, tx_withdrawals) = from_tx(&tx, at);
+        certificates.extend(tx_certificates);
+        withdrawals.extend(tx_withdrawals);
+    }
+    (certificates, withdrawals)
+}
+
+/// Certificates and withdrawals of one transaction of any era
+pub fn from_tx(tx: &MultiEraTx, at: Inclusion) -> (Vec<Certificate>, Vec<Withdrawal>) {
+    let tx_hash = tx.hash().to_string();
+    let mut certificates = Vec::new();
+    for (index, cert) in tx.certs().iter().enumerate() {
+        let record = certificate(&tx_hash, index as u32, at, "");
+        if let Some(cert) = cert.as_alonzo() {
+            certificates.push(from_alonzo(cert, record));
+        } else if let Some(cert) = cert.as_conway() {
+            certificates.push(from_conway(cert, record));
+        }
+    }
+    let withdrawals = tx
+        .withdrawals_sorted_set()
+        .into_iter()
+        .filter_map(|(account, amount)| {
+            Some(Withdrawal {
+                tx_hash: tx_hash.clone(),
+                block_number: at.block_number,
+                slot: at.slot,
+                timestamp: at.timestamp,
+                stake_address: reward_address(&hex::encode(account))?,
+                amount,
+            })
+        })
+        .collect();
+    (certificates, withdrawals)
+}
+
+// Oura renders stake credentials as {"AddrKeyhash": hex} or {"Scripthash": hex}
+fn oura_credential_address(credential: Option<&Value>) -> Option<String> {
+    let credential = credential?;
+    let (is_script, hash) = match (credential.get("AddrKeyhash"), credential.get("Scripthash")) {
+        (Some(hash), _) => (false, hash.as_str()?),
+        (_, Some(hash)) => (true, hash.as_str()?),
+        _ => return None,
+    };
+    stake_address(is_script, &hex::decode(hash).ok()?)
+}
+
+/// Certificate from an oura certificate event record, when block cbor is not available
+pub fn from_oura_record(record: &Value, tx_hash: &str, cert_index: u32, at: Inclusion) -> Option<Certificate> {
+    let kind = OURA_CERT_RECORDS.iter().find(|kind| record.get(**kind).is_some())?;
+    let body = &record[*kind];
+    let hex_field = |key: &str| body.get(key).and_then(|v| v.as_str());
+
+    let mut cert = certificate(tx_hash, cert_index, at, kind);
+    cert.stake_address = oura_credential_address(body.get("credential"));
+    match *kind {
+        "stake_delegation" => cert.pool_id = hex_field("pool_hash").and_then(pools::pool_hash_to_bech32),
+        "pool_retirement" => {
+            cert.pool_id = hex_field("pool").and_then(pools::pool_hash_to_bech32);
+            cert.epoch = body.get("epoch").and_then(|e| e.as_u64());
+        }
+        "pool_registration" => {
+            cert.pool_id = hex_field("operator").and_then(pools::pool_hash_to_bech32);
+            let mut details = body.clone();
+            if let Some(account) = hex_field("reward_account") {
+                details["reward_account"] = json!(reward_address(account));
+            }
+            cert.details = details;
+        }
+        _ => {}
+    }
+    Some(cert)
+}
+
+/// Withdrawals listed in an oura transaction record
+pub fn withdrawals_from_record(tx_obj: &Value, tx_hash: &str, at: Inclusion) -> Vec<Withdrawal> {
+    tx_obj
+        .get("withdrawals")
+        .and_then(|w| w.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|w| {
+            Some(Withdrawal {
+                tx_hash: tx_hash.to_string(),
+                block_number: at.block_number,
+                slot: at.slot,
+                timestamp: at.timestamp,
+                stake_address: reward_address(w.get("reward_account")?.as_str()?)?,
+                amount: w.get("coin").and_then(|c| c.as_u64()).unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AT: Inclusion = Inclusion { block_number: 10, slot: 100, timestamp: 1000 };
+    const STAKE_KEY_HASH: &str = "337b62cfff6403a06a3acbc34f8c46003c69fe79a3628cefa9c47251";
+    const POOL_HASH: &str = "0f292fcaa02b8b2f9b3c8f9fd8e0bb21abedb692a6d5058df3ef2735";
+
+    #[test]
+    fn test_conway_certificates() {
+        let credential = StakeCredential::AddrKeyhash(STAKE_KEY_HASH.parse().unwrap());
+        let pool = POOL_HASH.parse().unwrap();
+        let blank = certificate("ab", 0, AT, "");
+
+        let cert = from_conway(&conway::Certificate::StakeVoteRegDeleg(credential.clone(), pool, DRep::Abstain, 2000000), blank.clone());
+        assert_eq!(cert.kind, "stake_vote_registration_delegation");
+        // CIP-19 testnet reward address of the stake key
+        assert_eq!(
+            cert.stake_address.as_deref(),
+            Some("stake_test1uqehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gssrtvn")
+        );
+        assert_eq!(cert.pool_id, pools::pool_hash_to_bech32(POOL_HASH));
+        assert_eq!(cert.drep.as_deref(), Some("drep_always_abstain"));
+        assert_eq!(cert.deposit, Some(2000000));
+
+        let cert = from_conway(&conway::Certificate::RegDRepCert(credential, 500000000, Nullable::Null), blank);
+        assert_eq!(cert.kind, "drep_registration");
+        assert!(cert.drep.unwrap().starts_with("drep1"));
+    }
+
+    #[test]
+    fn test_script_credential() {
+        let script = StakeCredential::ScriptHash(POOL_HASH.parse().unwrap());
+        let key = StakeCredential::AddrKeyhash(STAKE_KEY_HASH.parse().unwrap());
+        let blank = certificate("ab", 0, AT, "");
+        let cert = |c: conway::Certificate| from_conway(&c, blank.clone());
+
+        assert_eq!(script_credential(&cert(conway::Certificate::StakeDeregistration(script.clone()))).as_deref(), Some(POOL_HASH));
+        assert_eq!(script_credential(&cert(conway::Certificate::StakeDeregistration(key.clone()))), None);
+        // the drep a stake credential delegates to does not witness the delegation
+        let vote = cert(conway::Certificate::VoteDeleg(key.clone(), DRep::Script(POOL_HASH.parse().unwrap())));
+        assert_eq!(script_credential(&vote), None);
+        let drep = cert(conway::Certificate::UnRegDRepCert(script.clone(), 500000000));
+        assert_eq!(script_credential(&drep).as_deref(), Some(POOL_HASH));
+        let resign = cert(conway::Certificate::ResignCommitteeCold(script, Nullable::Null));
+        assert_eq!(script_credential(&resign).as_deref(), Some(POOL_HASH));
+        assert_eq!(script_credential(&cert(conway::Certificate::ResignCommitteeCold(key, Nullable::Null))), None);
+    }
+
+    #[test]
+    fn test_oura_records() {
+        let record = json!({ "stake_delegation": { "credential": { "AddrKeyhash": STAKE_KEY_HASH }, "pool_hash": POOL_HASH } });
+        let cert = from_oura_record(&record, "ab", 1, AT).unwrap();
+        assert_eq!((cert.kind.as_str(), cert.cert_index, cert.block_number), ("stake_delegation", 1, 10));
+        assert_eq!(cert.pool_id, pools::pool_hash_to_bech32(POOL_HASH));
+
+        let record = json!({ "pool_retirement": { "pool": POOL_HASH, "epoch": 120 } });
+        assert_eq!(from_oura_record(&record, "ab", 0, AT).unwrap().epoch, Some(120));
+        assert!(from_oura_record(&json!({ "transaction": {} }), "ab", 0, AT).is_none());
+
+        let tx = json!({ "withdrawals": [{ "reward_account": format!("e0{}", STAKE_KEY_HASH), "coin": 42 }] });
+        let withdrawals = withdrawals_from_record(&tx, "ab", AT);
+        assert_eq!(withdrawals[0].amount, 42);
+        assert!(withdrawals[0].stake_address.starts_with("stake_test1"));
+    }
+}
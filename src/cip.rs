@@ -0,0 +1,370 @@
+//CIP-25 ra CIP-68 token metadata decoding
+use bech32::{Bech32, Hrp};
+use serde_json::Value;
+
+use crate::models::{AssetFile, AssetMetadata, MetadataStandard};
+
+// CIP-25 metadata label
+pub const CIP25_LABEL: &str = "721";
+
+// CIP-67 asset name labels used by CIP-68
+pub const CIP68_REFERENCE_LABEL: u16 = 100;
+pub const CIP68_NFT_LABEL: u16 = 222;
+pub const CIP68_FT_LABEL: u16 = 333;
+pub const CIP68_RFT_LABEL: u16 = 444;
+
+/// CIP-14 asset fingerprint (`asset1...`)
+pub fn fingerprint(policy_id: &str, asset_name: &str) -> Option<String> {
+    let mut data = hex::decode(policy_id).ok()?;
+    data.extend(hex::decode(asset_name).ok()?);
+
+    let hash = pallas_crypto::hash::Hasher::<160>::hash(&data);
+    let hrp = Hrp::parse("asset").ok()?;
+    bech32::encode::<Bech32>(hrp, hash.as_ref()).ok()
+}
+
+/// Asset name as text when it is printable UTF-8
+pub fn asset_name_ascii(asset_name: &str) -> Option<String> {
+    let bytes = hex::decode(asset_name).ok()?;
+    let text = String::from_utf8(bytes).ok()?;
+    if text.is_empty() || text.chars().any(|c| c.is_control()) {
+        return None;
+    }
+    Some(text)
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc: u8 = 0;
+    for byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Hex prefix of a CIP-67 label, e.g. 100 -> "000643b0"
+pub fn cip67_prefix(label: u16) -> String {
+    let label_bytes = label.to_be_bytes();
+    let checksum = crc8(&label_bytes);
+    let value: u32 = ((label as u32) << 12) | ((checksum as u32) << 4);
+    format!("{:08x}", value)
+}
+
+/// CIP-67 label of an asset name (hex), if it carries a valid one
+pub fn cip67_label(asset_name: &str) -> Option<u16> {
+    if asset_name.len() < 8 {
+        return None;
+    }
+    let prefix = u32::from_str_radix(asset_name.get(..8)?, 16).ok()?;
+    if prefix & 0xf000_000f != 0 {
+        return None;
+    }
+    let label = ((prefix >> 12) & 0xffff) as u16;
+    if cip67_prefix(label) != asset_name[..8].to_lowercase() {
+        return None;
+    }
+    Some(label)
+}
+
+/// Policy id (56 hex) followed by an asset name of at most 32 bytes, only then is it safe to split
+pub fn is_unit(unit: &str) -> bool {
+    (56..=120).contains(&unit.len()) && unit.len().is_multiple_of(2) && unit.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Reference token unit for a CIP-68 user token (222/333/444)
+pub fn reference_unit(unit: &str) -> Option<String> {
+    if !is_unit(unit) {
+        return None;
+    }
+    let (policy_id, asset_name) = unit.split_at(56);
+    match cip67_label(asset_name)? {
+        CIP68_NFT_LABEL | CIP68_FT_LABEL | CIP68_RFT_LABEL => Some(format!(
+            "{}{}{}",
+            policy_id,
+            cip67_prefix(CIP68_REFERENCE_LABEL),
+            &asset_name[8..]
+        )),
+        _ => None,
+    }
+}
+
+// CIP-25 lets long strings be split into arrays of chunks
+fn joined_string(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(parts) => {
+            let joined: String = parts.iter().filter_map(|p| p.as_str()).collect();
+            if joined.is_empty() { None } else { Some(joined) }
+        }
+        _ => None,
+    }
+}
+
+fn parse_files(value: Option<&Value>) -> Vec<AssetFile> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|files| {
+            files
+                .iter()
+                .filter_map(|file| {
+                    Some(AssetFile {
+                        name: joined_string(file.get("name")),
+                        media_type: joined_string(file.get("mediaType")),
+                        src: joined_string(file.get("src"))?,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_version(metadata: &Value) -> u64 {
+    match metadata.get("version") {
+        Some(Value::Number(n)) => n.as_f64().map(|v| v as u64).unwrap_or(1),
+        Some(Value::String(s)) => s.parse::<f64>().map(|v| v as u64).unwrap_or(1),
+        _ => 1,
+    }
+}
+
+/// Decode the content of a label 721 metadata entry into one record per asset
+pub fn parse_cip25(metadata: &Value, tx_hash: &str, slot: u64) -> Vec<AssetMetadata> {
+    let mut assets = Vec::new();
+    let policies = match metadata.as_object() {
+        Some(p) => p,
+        None => return assets,
+    };
+    let version = parse_version(metadata);
+
+    for (policy_key, policy_assets) in policies {
+        if policy_key == "version" {
+            continue;
+        }
+        let policy_assets = match policy_assets.as_object() {
+            Some(a) => a,
+            None => continue,
+        };
+        for (asset_key, asset_metadata) in policy_assets {
+            if let Some(asset) =
+                parse_cip25_asset(policy_key, asset_key, version, asset_metadata, tx_hash, slot)
+            {
+                assets.push(asset);
+            }
+        }
+    }
+    assets
+}
+
+/// Decode a single CIP-25 asset entry. v1 keys are text, v2 keys are hex bytes
+pub fn parse_cip25_asset(
+    policy_key: &str,
+    asset_key: &str,
+    version: u64,
+    metadata: &Value,
+    tx_hash: &str,
+    slot: u64,
+) -> Option<AssetMetadata> {
+    let policy_id = policy_key.to_lowercase();
+    if policy_id.len() != 56 || hex::decode(&policy_id).is_err() {
+        return None;
+    }
+    let asset_name = if version >= 2 {
+        hex::decode(asset_key).ok()?;
+        asset_key.to_lowercase()
+    } else {
+        hex::encode(asset_key.as_bytes())
+    };
+
+    Some(AssetMetadata {
+        unit: format!("{}{}", policy_id, asset_name),
+        fingerprint: fingerprint(&policy_id, &asset_name)?,
+        asset_name_ascii: asset_name_ascii(&asset_name),
+        policy_id,
+        asset_name,
+        standard: MetadataStandard::Cip25,
+        version,
+        label: None,
+        name: joined_string(metadata.get("name")),
+        image: joined_string(metadata.get("image")),
+        media_type: joined_string(metadata.get("mediaType")),
+        description: joined_string(metadata.get("description")),
+        ticker: joined_string(metadata.get("ticker")),
+        decimals: metadata.get("decimals").and_then(|v| v.as_u64()),
+        files: parse_files(metadata.get("files")),
+        raw: metadata.clone(),
+        tx_hash: tx_hash.to_string(),
+        slot,
+    })
+}
+
+/// Convert detailed-schema plutus data JSON into plain JSON, bytes become text when printable
+pub fn plutus_json_to_plain(value: &Value) -> Value {
+    if let Some(fields) = value.get("fields").and_then(|f| f.as_array()) {
+        return serde_json::json!({
+            "constructor": value.get("constructor").cloned().unwrap_or(Value::Null),
+            "fields": fields.iter().map(plutus_json_to_plain).collect::<Vec<_>>(),
+        });
+    }
+    if let Some(entries) = value.get("map").and_then(|m| m.as_array()) {
+        let mut object = serde_json::Map::new();
+        for entry in entries {
+            let key = match entry.get("k").map(plutus_json_to_plain) {
+                Some(Value::String(s)) => s,
+                Some(other) => other.to_string(),
+                None => continue,
+            };
+            let value = entry.get("v").map(plutus_json_to_plain).unwrap_or(Value::Null);
+            object.insert(key, value);
+        }
+        return Value::Object(object);
+    }
+    if let Some(items) = value.get("list").and_then(|l| l.as_array()) {
+        return Value::Array(items.iter().map(plutus_json_to_plain).collect());
+    }
+    if let Some(int) = value.get("int") {
+        return int.clone();
+    }
+    if let Some(bytes) = value.get("bytes").and_then(|b| b.as_str()) {
+        return match hex::decode(bytes).ok().and_then(|b| String::from_utf8(b).ok()) {
+            Some(text) if !text.chars().any(|c| c.is_control()) => Value::String(text),
+            _ => Value::String(bytes.to_string()),
+        };
+    }
+    for big in ["biguint", "bignint"] {
+        if let Some(v) = value.get(big) {
+            return v.clone();
+        }
+    }
+    value.clone()
+}
+
+/// Decode a CIP-68 reference datum `Constr 0 [metadata, version, extra]`
+pub fn parse_cip68_datum(unit: &str, datum: &Value, tx_hash: &str, slot: u64) -> Option<AssetMetadata> {
+    if datum.get("constructor").and_then(|c| c.as_u64()) != Some(0) {
+        return None;
+    }
+    let fields = datum.get("fields")?.as_array()?;
+    let metadata = plutus_json_to_plain(fields.first()?);
+    if !metadata.is_object() {
+        return None;
+    }
+    let version = fields
+        .get(1)
+        .and_then(|v| v.get("int"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1);
+
+    if !is_unit(unit) || unit.len() < 64 {
+        return None;
+    }
+    let (policy_id, asset_name) = unit.split_at(56);
+    let label = cip67_label(asset_name)?;
+
+    Some(AssetMetadata {
+        unit: unit.to_string(),
+        policy_id: policy_id.to_string(),
+        asset_name: asset_name.to_string(),
+        asset_name_ascii: asset_name_ascii(&asset_name[8..]),
+        fingerprint: fingerprint(policy_id, asset_name)?,
+        standard: MetadataStandard::Cip68,
+        version,
+        label: Some(label),
+        name: joined_string(metadata.get("name")),
+        image: joined_string(metadata.get("image")),
+        media_type: joined_string(metadata.get("mediaType")),
+        description: joined_string(metadata.get("description")),
+        ticker: joined_string(metadata.get("ticker")),
+        decimals: metadata.get("decimals").and_then(|v| v.as_u64()),
+        files: parse_files(metadata.get("files")),
+        raw: metadata,
+        tx_hash: tx_hash.to_string(),
+        slot,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = "1e349c9bdea19fd6c147626a5260bc44b71635f398b67c59881df209";
+
+    #[test]
+    fn test_cip67_prefixes() {
+        assert_eq!(cip67_prefix(100), "000643b0");
+        assert_eq!(cip67_prefix(222), "000de140");
+        assert_eq!(cip67_prefix(333), "0014df10");
+        assert_eq!(cip67_label("000de1404e6f6465"), Some(222));
+        assert_eq!(cip67_label("4e6f64654e6f6465"), None);
+    }
+
+    #[test]
+    fn test_unit_validation() {
+        let user_token = format!("{}000de1404e6f6465", POLICY);
+        assert!(is_unit(&user_token));
+        assert_eq!(reference_unit(&user_token), Some(format!("{}000643b04e6f6465", POLICY)));
+
+        // the 56th byte falls inside a multi-byte character
+        let unit = format!("{}é…", "a".repeat(55));
+        assert!(!is_unit(&unit));
+        assert_eq!(reference_unit(&unit), None);
+        assert!(!is_unit(&format!("{}0", POLICY)));
+        assert!(!is_unit(&format!("{}{}", POLICY, "00".repeat(33))));
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        // CIP-14 test vector
+        assert_eq!(
+            fingerprint("7eae28af2208be856f7a119668ae52a49b73725e326dc16579dcc373", "").unwrap(),
+            "asset1rjklcrnsdzqp65wjgrg55sy9723kw09mlgvlc3"
+        );
+    }
+
+    #[test]
+    fn test_parse_cip25_v1() {
+        let metadata = serde_json::json!({
+            POLICY: {
+                "NFT1": {
+                    "name": "NFT 1",
+                    "image": ["ipfs://QmRhTTbUrPYEw3mJGGhQqQST9k86v1DPBiTTWJGKDJsVFw"],
+                    "mediaType": "image/png",
+                    "files": [{"name": "a", "mediaType": "image/png", "src": "ipfs://a"}]
+                }
+            },
+            "version": "1.0"
+        });
+        let assets = parse_cip25(&metadata, "aa", 10);
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].asset_name, "4e465431");
+        assert_eq!(assets[0].asset_name_ascii.as_deref(), Some("NFT1"));
+        assert_eq!(assets[0].name.as_deref(), Some("NFT 1"));
+        assert_eq!(assets[0].files.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_cip68_datum() {
+        let unit = format!("{}{}{}", POLICY, cip67_prefix(100), "4e4654");
+        let datum = serde_json::json!({
+            "constructor": 0,
+            "fields": [
+                {"map": [
+                    {"k": {"bytes": "6e616d65"}, "v": {"bytes": "4e4654"}},
+                    {"k": {"bytes": "696d616765"}, "v": {"bytes": "697066733a2f2f61"}}
+                ]},
+                {"int": 2},
+                {"constructor": 0, "fields": []}
+            ]
+        });
+        let asset = parse_cip68_datum(&unit, &datum, "bb", 20).unwrap();
+        assert_eq!(asset.label, Some(100));
+        assert_eq!(asset.version, 2);
+        assert_eq!(asset.name.as_deref(), Some("NFT"));
+        assert_eq!(asset.image.as_deref(), Some("ipfs://a"));
+        let unit_with_char = format!("{}é…{}", "a".repeat(55), cip67_prefix(100));
+        assert!(parse_cip68_datum(&unit_with_char, &datum, "bb", 20).is_none());
+
+        let user_unit = format!("{}{}{}", POLICY, cip67_prefix(222), "4e4654");
+        assert_eq!(reference_unit(&user_unit), Some(unit));
+    }
+}
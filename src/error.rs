@@ -0,0 +1,142 @@
+//api error type and the error envelope every endpoint responds with
+use actix_web::{http::{header::RETRY_AFTER, StatusCode}, web, HttpRequest, HttpResponse, ResponseError};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::request_id;
+
+/// Errors returned by the http handlers. Each variant has a stable machine-readable
+/// code, the message is for humans and may change
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    BadRequest(String),
+    InvalidAddress(String),
+    Unauthorized(String),
+    NotFound(String),
+    TxRejected(String),     //the node or submit api refused a transaction
+    NotConfigured(String),  //optional backend (blockfrost, submission) missing from the env
+    Upstream(String),       //blockfrost, node or submit api failed
+    Internal(String),
+    RateLimited { message: String, retry_after: u64 }, //seconds until the client may retry
+}
+
+pub type ApiResult<T> = Result<web::Json<T>, ApiError>;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: String, //message
+    pub code: &'static str,
+    pub request_id: Option<String>,
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::InvalidAddress(_) => "invalid_address",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::TxRejected(_) => "tx_rejected",
+            ApiError::NotConfigured(_) => "not_configured",
+            ApiError::Upstream(_) => "upstream_error",
+            ApiError::Internal(_) => "internal_error",
+            ApiError::RateLimited { .. } => "rate_limited",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ApiError::BadRequest(m)
+            | ApiError::InvalidAddress(m)
+            | ApiError::Unauthorized(m)
+            | ApiError::NotFound(m)
+            | ApiError::TxRejected(m)
+            | ApiError::NotConfigured(m)
+            | ApiError::Upstream(m)
+            | ApiError::Internal(m)
+            | ApiError::RateLimited { message: m, .. } => m,
+        }
+    }
+
+    /// Envelope for this error, tagged with the id of the request being served
+    pub fn body(&self) -> ErrorBody {
+        ErrorBody {
+            error: self.message().to_string(),
+            code: self.code(),
+            request_id: request_id::current(),
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) | ApiError::InvalidAddress(_) | ApiError::TxRejected(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::NotConfigured(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::build(self.status_code());
+        if let ApiError::RateLimited { retry_after, .. } = self {
+            res.insert_header((RETRY_AFTER, retry_after.to_string()));
+        }
+        res.json(self.body())
+    }
+}
+
+// Malformed json bodies, query strings and paths answer with the envelope too
+pub fn json_error(err: actix_web::error::JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::BadRequest(format!("Invalid request body: {}", err)).into()
+}
+
+pub fn query_error(err: actix_web::error::QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::BadRequest(format!("Invalid query string: {}", err)).into()
+}
+
+pub fn path_error(err: actix_web::error::PathError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::BadRequest(format!("Invalid path: {}", err)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_and_code_mapping() {
+        let cases = [
+            (ApiError::BadRequest(String::new()), 400, "bad_request"),
+            (ApiError::InvalidAddress(String::new()), 400, "invalid_address"),
+            (ApiError::Unauthorized(String::new()), 401, "unauthorized"),
+            (ApiError::NotFound(String::new()), 404, "not_found"),
+            (ApiError::TxRejected(String::new()), 400, "tx_rejected"),
+            (ApiError::NotConfigured(String::new()), 503, "not_configured"),
+            (ApiError::Upstream(String::new()), 502, "upstream_error"),
+            (ApiError::Internal(String::new()), 500, "internal_error"),
+            (ApiError::RateLimited { message: String::new(), retry_after: 1 }, 429, "rate_limited"),
+        ];
+        for (error, status, code) in cases {
+            assert_eq!(error.status_code().as_u16(), status);
+            assert_eq!(error.code(), code);
+        }
+    }
+
+    #[test]
+    fn test_body_outside_a_request() {
+        let body = ApiError::NotFound("Block not seen by this explorer".to_string()).body();
+        assert_eq!(body.error, "Block not seen by this explorer");
+        assert_eq!(body.code, "not_found");
+        assert!(body.request_id.is_none());
+    }
+}
@@ -0,0 +1,198 @@
+//live chain events broadcast to websocket clients
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use tokio::sync::broadcast;
+
+use crate::models::{Block, EpochSummary, Proposal, Transaction, TxMetadata, Vote};
+use crate::stats::StatsSnapshot;
+
+// Events a slow client can fall behind before it starts missing them
+pub const EVENT_BUFFER: usize = 1024;
+
+// Past events kept for clients resuming a stream with Last-Event-ID
+pub const EVENT_HISTORY: usize = 1024;
+
+// Topics clients can subscribe to
+pub const TOPICS: [&str; 6] = ["blocks", "transactions", "epochs", "stats", "governance", "metadata"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum ChainEvent {
+    Block(Block),
+    Transaction(Transaction),
+    EpochBoundary {
+        epoch: u64,
+        slot: u64,
+        timestamp: u64,
+        previous: Option<EpochSummary>, //summary of the epoch that just ended
+    },
+    Stats(StatsSnapshot),
+    // a transaction submitted through /api/v1/transactions/submit made it into a block
+    TxConfirmed {
+        hash: String,
+        block_number: u64,
+        block_hash: Option<String>,
+        slot: u64,
+        submitted_at: u64,
+        confirmed_at: u64,
+    },
+    // a block dropped by a chain rollback, its transactions are no longer on chain
+    Rollback {
+        block_number: u64,
+        block_hash: String,
+        slot: u64,
+    },
+    Proposal(Proposal),
+    Vote(Vote),
+    // transaction metadata under one label, as /api/v1/metadata/{label} serves it
+    Metadata(TxMetadata),
+}
+
+impl ChainEvent {
+    /// The "type" tag the event is serialized with
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ChainEvent::Block(_) => "block",
+            ChainEvent::Transaction(_) => "transaction",
+            ChainEvent::EpochBoundary { .. } => "epoch_boundary",
+            ChainEvent::Stats(_) => "stats",
+            ChainEvent::TxConfirmed { .. } => "tx_confirmed",
+            ChainEvent::Rollback { .. } => "rollback",
+            ChainEvent::Proposal(_) => "proposal",
+            ChainEvent::Vote(_) => "vote",
+            ChainEvent::Metadata(_) => "metadata",
+        }
+    }
+
+    pub fn topic(&self) -> &'static str {
+        match self {
+            ChainEvent::Block(_) | ChainEvent::Rollback { .. } => "blocks",
+            ChainEvent::Transaction(_) | ChainEvent::TxConfirmed { .. } => "transactions",
+            ChainEvent::EpochBoundary { .. } => "epochs",
+            ChainEvent::Stats(_) => "stats",
+            ChainEvent::Proposal(_) | ChainEvent::Vote(_) => "governance",
+            ChainEvent::Metadata(_) => "metadata",
+        }
+    }
+}
+
+/// A chain event with the sequence number it was broadcast under, numbers start
+/// at 1 and restart with the process
+#[derive(Debug, Clone)]
+pub struct Sequenced {
+    pub id: u64,
+    pub event: ChainEvent,
+}
+
+/// Numbers events and keeps the most recent ones for replay
+#[derive(Debug)]
+pub struct EventLog {
+    next_id: u64,
+    recent: VecDeque<Sequenced>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self { next_id: 1, recent: VecDeque::with_capacity(EVENT_HISTORY) }
+    }
+
+    pub fn record(&mut self, event: ChainEvent) -> Sequenced {
+        let event = Sequenced { id: self.next_id, event };
+        self.next_id += 1;
+        if self.recent.len() == EVENT_HISTORY {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(event.clone());
+        event
+    }
+
+    /// Id of the newest recorded event, 0 before the first one
+    pub fn last_id(&self) -> u64 {
+        self.next_id - 1
+    }
+
+    /// Events after `last_id`, None when some of them are no longer kept or the
+    /// id was never handed out by this process
+    pub fn since(&self, last_id: u64) -> Option<Vec<Sequenced>> {
+        if last_id >= self.next_id {
+            return None;
+        }
+        let oldest = self.recent.front().map(|e| e.id).unwrap_or(self.next_id);
+        if last_id + 1 < oldest {
+            return None;
+        }
+        Some(self.recent.iter().filter(|e| e.id > last_id).cloned().collect())
+    }
+}
+
+pub fn channel() -> broadcast::Sender<Sequenced> {
+    broadcast::channel(EVENT_BUFFER).0
+}
+
+/// Server-sent event frame: the id to resume from, the event type and the same
+/// {"type", "data"} JSON the websocket sends
+pub fn sse_frame(event: &Sequenced) -> Option<String> {
+    let json = serde_json::to_string(&event.event).ok()?;
+    Some(format!("id: {}\nevent: {}\ndata: {}\n\n", event.id, event.event.kind(), json))
+}
+
+/// Frame telling a stream client that events after `last_id` were lost and it should refetch
+pub fn sse_resync_frame(last_id: u64) -> String {
+    format!("event: resync\ndata: {{\"type\":\"resync\",\"data\":{{\"last_event_id\":{}}}}}\n\n", last_id)
+}
+
+/// Known topics from a comma separated list, every topic when the list is absent
+pub fn parse_topics(list: Option<&str>) -> HashSet<&'static str> {
+    match list {
+        Some(list) => TOPICS
+            .iter()
+            .copied()
+            .filter(|topic| list.split(',').any(|t| t.trim() == *topic))
+            .collect(),
+        None => TOPICS.iter().copied().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_topics() {
+        assert_eq!(parse_topics(None).len(), TOPICS.len());
+        let topics = parse_topics(Some("stats, blocks,unknown"));
+        assert_eq!(topics.len(), 2);
+        assert!(topics.contains("stats") && topics.contains("blocks"));
+    }
+
+    fn rollback(slot: u64) -> ChainEvent {
+        ChainEvent::Rollback { block_number: slot, block_hash: "ab".repeat(32), slot }
+    }
+
+    #[test]
+    fn test_event_log_replay() {
+        let mut log = EventLog::new();
+        assert!(log.since(0).unwrap().is_empty());
+        assert!(log.since(1).is_none()); //not handed out yet
+
+        for slot in 0..EVENT_HISTORY as u64 + 10 {
+            log.record(rollback(slot));
+        }
+        let ids: Vec<u64> = log.since(EVENT_HISTORY as u64 + 7).unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![EVENT_HISTORY as u64 + 8, EVENT_HISTORY as u64 + 9, EVENT_HISTORY as u64 + 10]);
+        assert_eq!(log.since(10).unwrap().len(), EVENT_HISTORY); //11 is the oldest kept
+        assert!(log.since(9).is_none());
+    }
+
+    #[test]
+    fn test_sse_frame() {
+        let frame = sse_frame(&Sequenced { id: 42, event: rollback(7) }).unwrap();
+        let mut lines = frame.lines();
+        assert_eq!(lines.next(), Some("id: 42"));
+        assert_eq!(lines.next(), Some("event: rollback"));
+        let data: serde_json::Value = serde_json::from_str(lines.next().unwrap().strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(data["type"], "rollback");
+        assert_eq!(data["data"]["slot"], 7);
+        assert!(frame.ends_with("\n\n"));
+    }
+}
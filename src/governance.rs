@@ -0,0 +1,265 @@
+//Conway proposal and voting procedures from block cbor
+use pallas_codec::utils::Nullable;
+use pallas_primitives::conway::{GovAction, GovActionId, Vote as VoteKind, Voter};
+use pallas_traverse::{MultiEraBlock, MultiEraTx};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+use crate::certs::{self, Inclusion};
+use crate::models::{Proposal, Tally, Vote, VoteTally};
+use crate::pools;
+
+/// Proposal ids as `tx_hash#index`, the form used across the governance endpoints
+pub fn proposal_id(tx_hash: &str, index: u32) -> String {
+    format!("{}#{}", tx_hash, index)
+}
+
+/// Accepts `tx_hash#index` (percent-encoded in urls) or `tx_hash:index`
+pub fn parse_proposal_id(id: &str) -> Option<String> {
+    let (tx_hash, index) = id.trim().split_once(['#', ':'])?;
+    let tx_hash = tx_hash.to_lowercase();
+    if tx_hash.len() != 64 || !tx_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(proposal_id(&tx_hash, index.parse().ok()?))
+}
+
+/// CIP-105 drep id from a CIP-105 or CIP-129 (header byte 0x22 key / 0x23 script) bech32 id
+pub fn normalize_drep_id(id: &str) -> Option<String> {
+    let id = id.trim().to_lowercase();
+    let (hrp, data) = bech32::decode(&id).ok()?;
+    match (hrp.as_str(), data.len()) {
+        ("drep", 28) => certs::drep_id(false, &data),
+        ("drep_script", 28) => certs::drep_id(true, &data),
+        ("drep", 29) if data[0] == 0x22 => certs::drep_id(false, &data[1..]),
+        ("drep", 29) if data[0] == 0x23 => certs::drep_id(true, &data[1..]),
+        _ => None,
+    }
+}
+
+fn action_id(id: &GovActionId) -> String {
+    proposal_id(&id.transaction_id.to_string(), id.action_index)
+}
+
+fn previous(id: &Nullable<GovActionId>) -> Value {
+    match id {
+        Nullable::Some(id) => json!(action_id(id)),
+        _ => Value::Null,
+    }
+}
+
+fn script(hash: &Nullable<pallas_primitives::ScriptHash>) -> Value {
+    match hash {
+        Nullable::Some(hash) => json!(hash.to_string()),
+        _ => Value::Null,
+    }
+}
+
+fn action_json(action: &GovAction) -> (&'static str, Value) {
+    match action {
+        GovAction::ParameterChange(prev, update, guardrail) => (
+            "parameter_change",
+            json!({
+                "previous": previous(prev),
+                "update": serde_json::to_value(update.as_ref()).unwrap_or_default(),
+                "guardrail_script": script(guardrail),
+            }),
+        ),
+        GovAction::HardForkInitiation(prev, (major, minor)) => (
+            "hard_fork_initiation",
+            json!({ "previous": previous(prev), "protocol_version": { "major": major, "minor": minor } }),
+        ),
+        GovAction::TreasuryWithdrawals(withdrawals, guardrail) => (
+            "treasury_withdrawals",
+            json!({
+                "withdrawals": withdrawals
+                    .iter()
+                    .map(|(account, amount)| json!({
+                        "stake_address": certs::reward_address(&hex::encode(account.as_slice())),
+                        "amount": amount,
+                    }))
+                    .collect::<Vec<_>>(),
+                "guardrail_script": script(guardrail),
+            }),
+        ),
+        GovAction::NoConfidence(prev) => ("no_confidence", json!({ "previous": previous(prev) })),
+        GovAction::UpdateCommittee(prev, remove, add, threshold) => (
+            "update_committee",
+            json!({
+                "previous": previous(prev),
+                "remove": remove.iter().map(certs::credential_json).collect::<Vec<_>>(),
+                "add": add
+                    .iter()
+                    .map(|(credential, epoch)| json!({ "credential": certs::credential_json(credential), "expires_epoch": epoch }))
+                    .collect::<Vec<_>>(),
+                "threshold": { "numerator": threshold.numerator, "denominator": threshold.denominator },
+            }),
+        ),
+        GovAction::NewConstitution(prev, constitution) => (
+            "new_constitution",
+            json!({
+                "previous": previous(prev),
+                "anchor": certs::anchor_json(&Nullable::Some(constitution.anchor.clone())),
+                "guardrail_script": script(&constitution.guardrail_script),
+            }),
+        ),
+        GovAction::Information => ("info", json!({})),
+    }
+}
+
+fn voter(voter: &Voter) -> (&'static str, Option<String>) {
+    match voter {
+        Voter::DRepKey(hash) => ("drep", certs::drep_id(false, hash.as_slice())),
+        Voter::DRepScript(hash) => ("drep", certs::drep_id(true, hash.as_slice())),
+        Voter::StakePoolKey(hash) => ("spo", pools::pool_hash_to_bech32(&hash.to_string())),
+        Voter::ConstitutionalCommitteeKey(hash) | Voter::ConstitutionalCommitteeScript(hash) => {
+            ("cc", Some(hash.to_string()))
+        }
+    }
+}
+
+/// Proposals and votes of every Conway transaction in a block
+pub fn from_block(block: &MultiEraBlock, at: Inclusion, epoch: u64) -> (Vec<Proposal>, Vec<Vote>) {
+    let mut proposals = Vec::new();
+    let mut votes = Vec::new();
+    for tx in block.txs() {
+        let (tx_proposals, tx_votes) = from_tx(&tx, at, epoch);
+        proposals.extend(tx_proposals);
+        votes.extend(tx_votes);
+    }
+    (proposals, votes)
+}
+
+/// Proposals and votes of one transaction, only Conway ones carry any
+pub fn from_tx(tx: &MultiEraTx, at: Inclusion, epoch: u64) -> (Vec<Proposal>, Vec<Vote>) {
+    let mut proposals = Vec::new();
+    let mut votes = Vec::new();
+    let MultiEraTx::Conway(conway) = tx else {
+        return (proposals, votes);
+    };
+    let tx_hash = tx.hash().to_string();
+    let body = &conway.transaction_body;
+
+    for (index, procedure) in body.proposal_procedures.iter().flat_map(|p| p.iter()).enumerate() {
+        let (action, details) = action_json(&procedure.gov_action);
+        proposals.push(Proposal {
+            id: proposal_id(&tx_hash, index as u32),
+            tx_hash: tx_hash.clone(),
+            index: index as u32,
+            block_number: at.block_number,
+            slot: at.slot,
+            timestamp: at.timestamp,
+            epoch,
+            action: action.to_string(),
+            deposit: procedure.deposit,
+            return_address: certs::reward_address(&hex::encode(procedure.reward_account.as_slice())),
+            anchor: certs::anchor_json(&Nullable::Some(procedure.anchor.clone())),
+            details,
+        });
+    }
+
+    for (voter_key, ballots) in body.voting_procedures.iter().flat_map(|v| v.iter()) {
+        let (role, id) = voter(voter_key);
+        let Some(id) = id else {
+            continue;
+        };
+        for (action, procedure) in ballots.iter() {
+            votes.push(Vote {
+                tx_hash: tx_hash.clone(),
+                block_number: at.block_number,
+                slot: at.slot,
+                timestamp: at.timestamp,
+                proposal_id: action_id(action),
+                voter_role: role.to_string(),
+                voter: id.clone(),
+                vote: match procedure.vote {
+                    VoteKind::Yes => "yes",
+                    VoteKind::No => "no",
+                    VoteKind::Abstain => "abstain",
+                }
+                .to_string(),
+                anchor: certs::anchor_json(&procedure.anchor),
+            });
+        }
+    }
+    (proposals, votes)
+}
+
+/// Counts the latest vote of every voter, votes are given newest first
+pub fn tally(votes: &[Vote]) -> VoteTally {
+    let mut seen = HashSet::new();
+    let mut tally = VoteTally::default();
+    for vote in votes {
+        if !seen.insert((vote.voter_role.as_str(), vote.voter.as_str())) {
+            continue;
+        }
+        let role: &mut Tally = match vote.voter_role.as_str() {
+            "drep" => &mut tally.drep,
+            "spo" => &mut tally.spo,
+            _ => &mut tally.cc,
+        };
+        match vote.vote.as_str() {
+            "yes" => role.yes += 1,
+            "no" => role.no += 1,
+            _ => role.abstain += 1,
+        }
+    }
+    tally
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vote(role: &str, voter: &str, choice: &str) -> Vote {
+        Vote {
+            tx_hash: String::new(),
+            block_number: 0,
+            slot: 0,
+            timestamp: 0,
+            proposal_id: String::new(),
+            voter_role: role.to_string(),
+            voter: voter.to_string(),
+            vote: choice.to_string(),
+            anchor: Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_tally_counts_latest_vote() {
+        // newest first: drep1 changed its mind from no to yes
+        let votes = [
+            vote("drep", "drep1a", "yes"),
+            vote("drep", "drep1b", "abstain"),
+            vote("drep", "drep1a", "no"),
+            vote("spo", "pool1a", "no"),
+            vote("cc", "aa", "yes"),
+        ];
+        let tally = tally(&votes);
+        assert_eq!(tally.drep, Tally { yes: 1, no: 0, abstain: 1 });
+        assert_eq!(tally.spo, Tally { yes: 0, no: 1, abstain: 0 });
+        assert_eq!(tally.cc, Tally { yes: 1, no: 0, abstain: 0 });
+    }
+
+    #[test]
+    fn test_parse_proposal_id() {
+        let hash = "AB".repeat(32);
+        assert_eq!(parse_proposal_id(&format!("{}#0", hash)), Some(format!("{}#0", hash.to_lowercase())));
+        assert_eq!(parse_proposal_id(&format!("{}:3", hash)), Some(format!("{}#3", hash.to_lowercase())));
+        assert!(parse_proposal_id(&hash).is_none());
+        assert!(parse_proposal_id("abc#1").is_none());
+    }
+
+    #[test]
+    fn test_normalize_drep_id() {
+        let hash = [0x11u8; 28];
+        let cip105 = certs::drep_id(false, &hash).unwrap();
+        assert_eq!(normalize_drep_id(&cip105.to_uppercase()), Some(cip105.clone()));
+
+        let mut data = vec![0x22];
+        data.extend_from_slice(&hash);
+        let cip129 = bech32::encode::<bech32::Bech32>(bech32::Hrp::parse("drep").unwrap(), &data).unwrap();
+        assert_eq!(normalize_drep_id(&cip129), Some(cip105));
+        assert!(normalize_drep_id("pool1xyz").is_none());
+    }
+}
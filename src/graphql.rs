@@ -0,0 +1,417 @@
+//GraphQL schema over the explorer state, queries plus subscriptions fed by the chain event broadcast
+use async_graphql::{
+    ComplexObject, Context, EmptyMutation, ErrorExtensions, Object, Result, Schema, SimpleObject, Subscription,
+};
+use futures_util::future::ready;
+use futures_util::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::address::{self, AddressInfo};
+use crate::error::ApiError;
+use crate::events::{self, ChainEvent};
+use crate::models::{AssetMetadata, Block, Certificate, EpochSummary, Transaction, TxMetadata, Withdrawal};
+use crate::oura_stream::BlockChainState;
+use crate::pools::normalize_pool_id;
+use crate::request_id;
+
+pub type ExplorerSchema = Schema<QueryRoot, EmptyMutation, SubscriptionRoot>;
+
+// Deepest selection nesting a query may use
+static MAX_DEPTH: Lazy<usize> = Lazy::new(|| {
+    std::env::var("GRAPHQL_MAX_DEPTH").ok().and_then(|v| v.parse().ok()).unwrap_or(10)
+});
+// Highest cost a query may have, every field costs 1 and lists multiply by their limit
+static MAX_COMPLEXITY: Lazy<usize> = Lazy::new(|| {
+    std::env::var("GRAPHQL_MAX_COMPLEXITY").ok().and_then(|v| v.parse().ok()).unwrap_or(2000)
+});
+
+pub fn schema(state: Arc<BlockChainState>) -> ExplorerSchema {
+    schema_with_limits(state, *MAX_DEPTH, *MAX_COMPLEXITY)
+}
+
+pub fn schema_with_limits(state: Arc<BlockChainState>, depth: usize, complexity: usize) -> ExplorerSchema {
+    Schema::build(QueryRoot, EmptyMutation, SubscriptionRoot)
+        .data(state)
+        .limit_depth(depth)
+        .limit_complexity(complexity)
+        .finish()
+}
+
+// GraphQL errors carry the same code (and request id) as the REST error envelope in their extensions
+impl ErrorExtensions for ApiError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.message()).extend_with(|_, e| {
+            e.set("code", self.code());
+            if let Some(id) = request_id::current() {
+                e.set("request_id", id);
+            }
+        })
+    }
+}
+
+fn state<'a>(ctx: &Context<'a>) -> &'a Arc<BlockChainState> {
+    ctx.data_unchecked::<Arc<BlockChainState>>()
+}
+
+fn pool_id(id: &str) -> Result<String> {
+    normalize_pool_id(id)
+        .ok_or_else(|| ApiError::BadRequest("Invalid pool id, expected pool1... or a 56 character hex hash".to_string()).extend())
+}
+
+/// Stake pool, resolved lazily from what the explorer has seen of it
+pub struct Pool {
+    id: String,
+}
+
+#[derive(SimpleObject)]
+pub struct PoolEpochBlocks {
+    pub epoch: u64,
+    pub blocks: u64,
+}
+
+#[derive(SimpleObject)]
+pub struct PoolBlockCount {
+    pub pool: Pool,
+    pub blocks: u64,
+}
+
+#[derive(SimpleObject)]
+pub struct Rollback {
+    pub block_number: u64,
+    pub block_hash: String,
+    pub slot: u64,
+}
+
+#[Object]
+impl Pool {
+    /// Bech32 pool id
+    async fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Recent blocks produced by the pool, max 100
+    #[graphql(complexity = "limit.min(100) * child_complexity")]
+    async fn blocks(&self, ctx: &Context<'_>, #[graphql(default = 20)] limit: usize) -> Vec<Block> {
+        state(ctx).get_pool_blocks(&self.id, limit.min(100)).await
+    }
+
+    /// Blocks produced per epoch, newest epoch first
+    async fn epochs(&self, ctx: &Context<'_>) -> Vec<PoolEpochBlocks> {
+        state(ctx)
+            .get_pool_epoch_counts(&self.id)
+            .await
+            .into_iter()
+            .rev()
+            .map(|(epoch, blocks)| PoolEpochBlocks { epoch, blocks })
+            .collect()
+    }
+
+    /// Registration, update and retirement certificates, newest first
+    async fn registrations(&self, ctx: &Context<'_>) -> Vec<Certificate> {
+        state(ctx).get_pool_certificates(&self.id).await
+    }
+
+    async fn retiring_epoch(&self, ctx: &Context<'_>) -> Option<u64> {
+        let certificates = state(ctx).get_pool_certificates(&self.id).await;
+        certificates.first().filter(|c| c.kind == "pool_retirement").and_then(|c| c.epoch)
+    }
+}
+
+#[ComplexObject]
+impl Block {
+    /// Transactions of the block, while they are still in memory
+    async fn transactions(&self, ctx: &Context<'_>) -> Vec<Transaction> {
+        state(ctx).get_block_transactions(self.number).await
+    }
+
+    /// Producing pool, missing for byron blocks
+    async fn pool(&self) -> Option<Pool> {
+        self.pool_id.clone().map(|id| Pool { id })
+    }
+}
+
+#[ComplexObject]
+impl Transaction {
+    async fn block(&self, ctx: &Context<'_>) -> Option<Block> {
+        state(ctx).get_block_by_number(self.block_number).await
+    }
+
+    async fn metadata(&self, ctx: &Context<'_>) -> Vec<TxMetadata> {
+        state(ctx).get_tx_metadata(&self.hash).await
+    }
+}
+
+#[ComplexObject]
+impl EpochSummary {
+    /// Pools that produced blocks in the epoch, most productive first
+    async fn producers(&self, ctx: &Context<'_>) -> Vec<PoolBlockCount> {
+        state(ctx)
+            .get_epoch_pool_counts(self.epoch)
+            .await
+            .into_iter()
+            .map(|(id, blocks)| PoolBlockCount { pool: Pool { id }, blocks })
+            .collect()
+    }
+}
+
+#[ComplexObject]
+impl AddressInfo {
+    /// Certificates of the stake address the funds are delegated through, newest first
+    async fn certificates(&self, ctx: &Context<'_>) -> Vec<Certificate> {
+        match &self.stake_address {
+            Some(stake_address) => state(ctx).get_stake_certificates(stake_address).await,
+            None => Vec::new(),
+        }
+    }
+
+    /// Reward withdrawals of the stake address, newest first
+    async fn withdrawals(&self, ctx: &Context<'_>) -> Vec<Withdrawal> {
+        match &self.stake_address {
+            Some(stake_address) => state(ctx).get_withdrawals(stake_address).await,
+            None => Vec::new(),
+        }
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Height of the newest block
+    async fn tip(&self, ctx: &Context<'_>) -> u64 {
+        state(ctx).tip().await
+    }
+
+    /// Latest blocks, newest first, max 100
+    #[graphql(complexity = "limit.min(100) * child_complexity")]
+    async fn blocks(&self, ctx: &Context<'_>, #[graphql(default = 20)] limit: usize) -> Vec<Block> {
+        state(ctx).get_blocks(limit.min(100)).await
+    }
+
+    /// One recent block by hash, number or slot
+    async fn block(&self, ctx: &Context<'_>, hash: Option<String>, number: Option<u64>, slot: Option<u64>) -> Result<Option<Block>> {
+        let state = state(ctx);
+        match (hash, number, slot) {
+            (Some(hash), None, None) => Ok(state.get_block_by_hash(&hash.to_lowercase()).await),
+            (None, Some(number), None) => Ok(state.get_block_by_number(number).await),
+            (None, None, Some(slot)) => Ok(state.get_block_by_slot(slot).await),
+            _ => Err(ApiError::BadRequest("Pass exactly one of hash, number or slot".to_string()).extend()),
+        }
+    }
+
+    /// Latest transactions, newest first, max 200
+    #[graphql(complexity = "limit.min(200) * child_complexity")]
+    async fn transactions(&self, ctx: &Context<'_>, #[graphql(default = 50)] limit: usize) -> Vec<Transaction> {
+        state(ctx).get_transactions(limit.min(200)).await
+    }
+
+    async fn transaction(&self, ctx: &Context<'_>, hash: String) -> Option<Transaction> {
+        state(ctx).get_transaction(&hash.to_lowercase()).await
+    }
+
+    /// Decoded bech32, byron base58 or hex address
+    async fn address(&self, address: String) -> Result<AddressInfo> {
+        address::inspect(&address).map_err(|e| ApiError::InvalidAddress(e).extend())
+    }
+
+    /// Recently seen CIP-25 / CIP-68 token metadata, max 200
+    #[graphql(complexity = "limit.min(200) * child_complexity")]
+    async fn assets(&self, ctx: &Context<'_>, #[graphql(default = 50)] limit: usize) -> Vec<AssetMetadata> {
+        state(ctx).get_assets(limit.min(200)).await
+    }
+
+    /// Token metadata by unit or fingerprint
+    async fn asset(&self, ctx: &Context<'_>, id: String) -> Option<AssetMetadata> {
+        state(ctx).get_asset(&id).await
+    }
+
+    async fn policy_assets(&self, ctx: &Context<'_>, policy_id: String) -> Vec<AssetMetadata> {
+        state(ctx).get_policy_assets(&policy_id.to_lowercase()).await
+    }
+
+    /// Latest epoch summaries, max 100
+    #[graphql(complexity = "limit.min(100) * child_complexity")]
+    async fn epochs(&self, ctx: &Context<'_>, #[graphql(default = 10)] limit: usize) -> Vec<EpochSummary> {
+        state(ctx).get_epochs(limit.min(100)).await
+    }
+
+    async fn epoch(&self, ctx: &Context<'_>, number: u64) -> Option<EpochSummary> {
+        state(ctx).get_epoch(number).await
+    }
+
+    /// Pools that produced blocks in an epoch, defaults to the latest one
+    async fn pools(&self, ctx: &Context<'_>, epoch: Option<u64>) -> Vec<PoolBlockCount> {
+        let state = state(ctx);
+        let epoch = match epoch {
+            Some(epoch) => epoch,
+            None => match state.get_blocks(1).await.first() {
+                Some(block) => block.epoch,
+                None => return Vec::new(),
+            },
+        };
+        state
+            .get_epoch_pool_counts(epoch)
+            .await
+            .into_iter()
+            .map(|(id, blocks)| PoolBlockCount { pool: Pool { id }, blocks })
+            .collect()
+    }
+
+    /// A pool by pool1... id or hex hash, null when it was never seen
+    async fn pool(&self, ctx: &Context<'_>, id: String) -> Result<Option<Pool>> {
+        let id = pool_id(&id)?;
+        let state = state(ctx);
+        if state.has_pool(&id).await || !state.get_pool_certificates(&id).await.is_empty() {
+            Ok(Some(Pool { id }))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+// Chain events as they are broadcast, lagging subscribers skip what they missed
+fn chain_events(state: &BlockChainState) -> impl Stream<Item = ChainEvent> {
+    futures_util::stream::unfold(state.subscribe(), |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => return Some((event.event, events)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("GraphQL subscriber lagged, skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Blocks as they are ingested
+    async fn blocks(&self, ctx: &Context<'_>) -> impl Stream<Item = Block> {
+        chain_events(state(ctx)).filter_map(|event| {
+            ready(match event {
+                ChainEvent::Block(block) => Some(block),
+                _ => None,
+            })
+        })
+    }
+
+    /// Transactions as they are ingested
+    async fn transactions(&self, ctx: &Context<'_>) -> impl Stream<Item = Transaction> {
+        chain_events(state(ctx)).filter_map(|event| {
+            ready(match event {
+                ChainEvent::Transaction(tx) => Some(tx),
+                _ => None,
+            })
+        })
+    }
+
+    /// Blocks dropped by chain rollbacks
+    async fn rollbacks(&self, ctx: &Context<'_>) -> impl Stream<Item = Rollback> {
+        chain_events(state(ctx)).filter_map(|event| {
+            ready(match event {
+                ChainEvent::Rollback { block_number, block_hash, slot } => Some(Rollback { block_number, block_hash, slot }),
+                _ => None,
+            })
+        })
+    }
+
+    /// Transaction metadata as it is ingested, only under `label` when given
+    async fn metadata(&self, ctx: &Context<'_>, label: Option<u64>) -> impl Stream<Item = TxMetadata> {
+        chain_events(state(ctx)).filter_map(move |event| {
+            ready(match event {
+                ChainEvent::Metadata(entry) if label.is_none_or(|label| entry.label == label) => Some(entry),
+                _ => None,
+            })
+        })
+    }
+
+    /// Every chain event of the given topics as the {"type", "data"} JSON the websocket sends, all topics by default
+    async fn events(&self, ctx: &Context<'_>, topics: Option<Vec<String>>) -> impl Stream<Item = serde_json::Value> {
+        let topics = events::parse_topics(topics.map(|t| t.join(",")).as_deref());
+        chain_events(state(ctx)).filter_map(move |event| {
+            ready(match topics.contains(event.topic()) {
+                true => serde_json::to_value(&event).ok(),
+                false => None,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn state_with_block() -> Arc<BlockChainState> {
+        let state = Arc::new(BlockChainState::new());
+        let mut block = Block::new("ab".repeat(32), 7, 700, 3, 1000, 1, 500);
+        block.pool_id = Some("pool1abc".to_string());
+        state.add_block(block).await;
+        state.add_transactions(Transaction::new("cd".repeat(32), 7, 1000, 170000, 1, 2, 5000000)).await;
+        state
+    }
+
+    #[tokio::test]
+    async fn test_nested_block_query() {
+        let schema = schema_with_limits(state_with_block().await, 10, 1000);
+        let res = schema
+            .execute("{ blocks(limit: 5) { number pool { id } transactions { fee block { hash } } } }")
+            .await;
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let data = res.data.into_json().unwrap();
+        let block = &data["blocks"][0];
+        assert_eq!(block["number"], 7);
+        assert_eq!(block["pool"]["id"], "pool1abc");
+        assert_eq!(block["transactions"][0]["fee"], 170000);
+        assert_eq!(block["transactions"][0]["block"]["hash"], "ab".repeat(32));
+    }
+
+    #[tokio::test]
+    async fn test_depth_and_complexity_limits() {
+        let state = state_with_block().await;
+        let deep = "{ blocks { transactions { block { transactions { block { number } } } } } }";
+        let res = schema_with_limits(state.clone(), 4, 1000).execute(deep).await;
+        assert!(res.errors[0].message.contains("nested too deep"), "{:?}", res.errors);
+
+        // 100 blocks times their selection is over the budget, 2 blocks is not
+        let schema = schema_with_limits(state, 10, 100);
+        let res = schema.execute("{ blocks(limit: 100) { number hash slot } }").await;
+        assert!(res.errors[0].message.contains("too complex"), "{:?}", res.errors);
+        assert!(schema.execute("{ blocks(limit: 2) { number hash slot } }").await.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_error_codes() {
+        let schema = schema_with_limits(Arc::new(BlockChainState::new()), 10, 1000);
+        let res = schema.execute(r#"{ address(address: "not an address") { kind } }"#).await;
+        let error = serde_json::to_value(&res.errors[0]).unwrap();
+        assert_eq!(error["extensions"]["code"], "invalid_address");
+
+        let res = schema.execute(r#"{ pool(id: "nope") { id } }"#).await;
+        let error = serde_json::to_value(&res.errors[0]).unwrap();
+        assert_eq!(error["extensions"]["code"], "bad_request");
+    }
+
+    #[tokio::test]
+    async fn test_block_subscription() {
+        let state = Arc::new(BlockChainState::new());
+        let schema = schema_with_limits(state.clone(), 10, 1000);
+        let mut stream = schema.execute_stream("subscription { blocks { number } }");
+
+        // the subscription attaches to the broadcast on first poll, keep publishing until it does
+        let publisher = tokio::spawn(async move {
+            loop {
+                state.publish(ChainEvent::Block(Block::new("ef".repeat(32), 9, 900, 3, 1100, 0, 100)));
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        });
+        let res = tokio::time::timeout(Duration::from_secs(2), stream.next()).await.unwrap().unwrap();
+        publisher.abort();
+        assert_eq!(res.data.into_json().unwrap()["blocks"]["number"], 9);
+    }
+}
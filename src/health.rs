@@ -0,0 +1,155 @@
+//readiness of the explorer: chain sync, oura source, storage and blockfrost
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+
+use crate::blockfrost;
+use crate::oura_stream::BlockChainState;
+use crate::time::NETWORK_TIME;
+
+// Seconds the newest block may trail the wall clock before the explorer is not ready
+static MAX_LAG: Lazy<u64> = Lazy::new(|| {
+    std::env::var("READINESS_MAX_LAG").ok().and_then(|v| v.parse().ok()).unwrap_or(300)
+});
+// Blockfrost reachability is rechecked at most this often, probes run every few seconds
+const BLOCKFROST_CHECK_TTL: Duration = Duration::from_secs(30);
+const BLOCKFROST_TIMEOUT: Duration = Duration::from_secs(5);
+// A state lock not granted within this is reported as unavailable storage
+const STORAGE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// State of the oura child process feeding the explorer
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SourceStatus {
+    pub status: &'static str, //starting, running, stopped or failed
+    pub since: u64,
+    pub last_event_at: Option<u64>,
+    pub detail: Option<String>, //spawn error or exit status
+}
+
+impl SourceStatus {
+    pub fn new() -> Self {
+        Self { status: "starting", since: now(), last_event_at: None, detail: None }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StorageStatus {
+    pub status: &'static str, //ok or unavailable
+    pub blocks: usize,
+    pub transactions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BlockfrostStatus {
+    pub status: &'static str, //ok, unreachable or not_configured
+    pub latency_ms: Option<u64>,
+    pub checked_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Readiness {
+    pub status: &'static str, //ready, syncing (no block yet), stale or unavailable (storage)
+    pub ready: bool,
+    pub last_block_time: Option<u64>,
+    pub last_block_slot: Option<u64>,
+    pub seconds_behind_tip: Option<u64>,
+    pub max_lag_seconds: u64,
+    pub source: SourceStatus,
+    pub storage: StorageStatus,
+    pub blockfrost: BlockfrostStatus,
+}
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+/// Readiness verdict: blocks are flowing and storage answers. Source and blockfrost
+/// are reported but a dead source shows up as lag soon enough
+pub fn verdict(seconds_behind_tip: Option<u64>, max_lag: u64, storage_ok: bool) -> &'static str {
+    match seconds_behind_tip {
+        _ if !storage_ok => "unavailable",
+        None => "syncing",
+        Some(lag) if lag > max_lag => "stale",
+        Some(_) => "ready",
+    }
+}
+
+async fn storage(state: &BlockChainState) -> StorageStatus {
+    let counts = tokio::time::timeout(STORAGE_TIMEOUT, async {
+        let blocks = state.blocks.read().await.len();
+        let transactions = state.transactions.read().await.len();
+        (blocks, transactions)
+    })
+    .await;
+    match counts {
+        Ok((blocks, transactions)) => StorageStatus { status: "ok", blocks, transactions },
+        Err(_) => StorageStatus { status: "unavailable", blocks: 0, transactions: 0 },
+    }
+}
+
+static BLOCKFROST_CHECK: Lazy<RwLock<Option<(Instant, BlockfrostStatus)>>> = Lazy::new(|| RwLock::new(None));
+
+async fn blockfrost_status() -> BlockfrostStatus {
+    let Some(key) = blockfrost::api_key() else {
+        return BlockfrostStatus { status: "not_configured", latency_ms: None, checked_at: None, error: None };
+    };
+    if let Some((at, status)) = BLOCKFROST_CHECK.read().await.as_ref() {
+        if at.elapsed() < BLOCKFROST_CHECK_TTL {
+            return status.clone();
+        }
+    }
+
+    let started = Instant::now();
+    let result = match tokio::time::timeout(BLOCKFROST_TIMEOUT, blockfrost::get::<serde_json::Value>("/health", &key)).await {
+        Ok(Ok(body)) if body.get("is_healthy").and_then(|v| v.as_bool()) == Some(true) => Ok(()),
+        Ok(Ok(body)) => Err(format!("Blockfrost reports unhealthy: {}", body)),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err("Blockfrost health check timed out".to_string()),
+    };
+    let status = BlockfrostStatus {
+        status: if result.is_ok() { "ok" } else { "unreachable" },
+        latency_ms: Some(started.elapsed().as_millis() as u64),
+        checked_at: Some(now()),
+        error: result.err(),
+    };
+    *BLOCKFROST_CHECK.write().await = Some((Instant::now(), status.clone()));
+    status
+}
+
+pub async fn readiness(state: &BlockChainState) -> Readiness {
+    let tip = state.get_blocks(1).await.into_iter().next();
+    let seconds_behind_tip = tip
+        .as_ref()
+        .map(|block| now().saturating_sub(NETWORK_TIME.slot_to_time(block.slot)));
+    let storage = storage(state).await;
+    let status = verdict(seconds_behind_tip, *MAX_LAG, storage.status == "ok");
+
+    Readiness {
+        status,
+        ready: status == "ready",
+        last_block_time: tip.as_ref().map(|b| b.timestamp),
+        last_block_slot: tip.as_ref().map(|b| b.slot),
+        seconds_behind_tip,
+        max_lag_seconds: *MAX_LAG,
+        source: state.source.read().await.clone(),
+        storage,
+        blockfrost: blockfrost_status().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verdict() {
+        assert_eq!(verdict(None, 300, true), "syncing");
+        assert_eq!(verdict(Some(20), 300, true), "ready");
+        assert_eq!(verdict(Some(300), 300, true), "ready");
+        assert_eq!(verdict(Some(301), 300, true), "stale");
+        assert_eq!(verdict(Some(20), 300, false), "unavailable");
+    }
+}
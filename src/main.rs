@@ -1,62 +1,150 @@
 mod models;
+mod error;
+mod request_id;
+mod telemetry;
+mod metrics;
+mod ratelimit;
+mod health;
+mod shutdown;
+mod snapshot;
+mod versioning;
+mod cip;
+mod metadata;
+mod pools;
+mod network;
+mod time;
+mod events;
+mod stats;
+mod blockfrost;
+mod address;
+mod plutus;
+mod blueprint;
+mod certs;
+mod governance;
+mod tx_decoder;
+mod submit;
+mod search;
 mod oura_stream;
 mod api;
+mod graphql;
+mod openapi;
 mod websocket;
 mod auth;
 
-use actix_web::{middleware,web,App,HttpResponse,HttpServer};
+use actix_web::{middleware,web,App,HttpServer};
 use actix_cors::Cors;
 use std::sync::Arc;
 use oura_stream::{BlockChainState,start_oura};
 use auth::SessionStore;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    //logger
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
-    log::info!("Starting the backend");
+    //json logs and trace export
+    let telemetry = telemetry::init();
+    tracing::info!("Starting the backend");
+
+    //the network everything else is derived from, an unknown name stops the start
+    let network = network::configured().map_err(|e| {
+        tracing::error!("{}", e);
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, e)
+    })?;
+    tracing::info!("Following {} through {}", network.name, network.relay);
 
     //shared state
     let state = Arc::new(BlockChainState::new());
     let session_store=Arc::new(SessionStore::new());
+    //state saved by the last shutdown
+    if let Some(saved) = snapshot::load_sessions(&snapshot::STATE_DIR) {
+        tracing::info!("Restored {} sessions", saved.sessions.len());
+        session_store.restore(saved).await;
+    }
     //oura stream
-    start_oura(state.clone()).await;
+    start_oura(state.clone(), snapshot::load_chain_point(&snapshot::STATE_DIR)).await;
 
-    log::info!("Oura started");
+    tracing::info!("Oura started");
 
     //clean expired session
     let session_store_cleanup = session_store.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600));
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown::requested() => break,
+            }
             session_store_cleanup.cleanup_expired(604800).await; // 7 days
-            log::info!("Cleaned up expired sessions");
+            ratelimit::prune();
+            tracing::info!("Cleaned up expired sessions and idle rate limit keys");
         }
     });
 
 
     //server
-    log::info!("Starting server on 0.0.0.0:8000");
+    tracing::info!("Starting server on 0.0.0.0:8000");
+
+    let openapi = openapi::ApiDoc::openapi();
+    let graphql_schema = graphql::schema(state.clone());
+    let app_state = state.clone();
+    let app_sessions = session_store.clone();
 
-    HttpServer::new(move || {
-        let cors = Cors::default().allow_any_origin().allow_any_method().allow_any_header().supports_credentials();
+    let server = HttpServer::new(move || {
+        let state = app_state.clone();
+        let session_store = app_sessions.clone();
+        let cors = Cors::default().allow_any_origin().allow_any_method().allow_any_header().expose_headers([request_id::HEADER, "deprecation", "link", "retry-after"]).supports_credentials();
       
-        App::new().app_data(web::Data::new(state.clone())).app_data(web::Data::new(session_store.clone())).wrap(middleware::Logger::default()).wrap(cors)
+        App::new().app_data(web::Data::new(state.clone())).app_data(web::Data::new(session_store.clone()))
+        .app_data(web::Data::new(graphql_schema.clone()))
+        //malformed bodies, queries and paths answer with the api error envelope
+        .app_data(web::JsonConfig::default().error_handler(error::json_error))
+        .app_data(web::QueryConfig::default().error_handler(error::query_error))
+        .app_data(web::PathConfig::default().error_handler(error::path_error))
+        .wrap(middleware::from_fn(ratelimit::limit)).wrap(middleware::from_fn(versioning::deprecated_aliases)).wrap(middleware::from_fn(metrics::track_requests)).wrap(middleware::from_fn(telemetry::trace_requests)).wrap(middleware::from_fn(request_id::assign)).wrap(cors)
         .route("/ws", web::get().to(websocket::websocket_route))
         .service(api::blocks::get_blocks)
-        .service(api::blocks::get_latest_block).
+        .service(api::blocks::get_latest_block)
+        .service(api::blocks::get_block).
         service(api::transactions::get_transactions)
+        .service(api::assets::get_assets)
+        .service(api::assets::get_policy_assets)
+        .service(api::assets::get_asset)
+        .service(api::metadata::get_metadata_by_label)
+        .service(api::metadata::get_transaction_metadata)
+        .service(api::transactions::get_transaction_status)
+        .service(api::transactions::get_transaction)
+        .service(api::scripts::get_script)
+        .service(api::pools::get_pools)
+        .service(api::pools::get_pool_blocks)
+        .service(api::pools::get_pool_registrations)
+        .service(api::stake::get_stake)
+        .service(api::governance::get_proposals)
+        .service(api::governance::get_proposal)
+        .service(api::governance::get_drep)
+        .service(api::time::get_slot_time)
+        .service(api::time::get_posix_slot)
+        .service(api::epochs::get_epochs)
+        .service(api::epochs::get_epoch)
+        .service(api::stats::get_stats)
+        .service(api::search::get_search)
+        .service(api::addresses::get_address_info)
+        .service(api::tools::decode_tx)
+        .service(api::tools::decode_datum)
+        .service(api::submit::submit_tx)
+        .service(api::stream::get_stream)
+        .service(api::graphql::graphql)
+        .service(api::graphql::graphiql)
+        .service(api::graphql::graphql_ws)
 
         .service(auth::routes::get_challenge)
         .service(auth::routes::login)
-        .service(auth::routes::referesh_token)
+        .service(auth::routes::refresh_access_token)
         //auth routes
         
        
         
         .service(
-            web::scope("/api/auth")
+            web::scope("/api/v1/auth")
                 .wrap(auth::middleware::AuthMiddleWare {
                 session_store: session_store.clone(),
                 })
@@ -67,7 +155,7 @@ async fn main() -> std::io::Result<()> {
         )
         //user routes
          .service(
-                web::scope("/api/user")
+                web::scope("/api/v1/user")
                     .wrap(auth::middleware::AuthMiddleWare {
                         session_store: session_store.clone(),
                     })
@@ -75,14 +163,22 @@ async fn main() -> std::io::Result<()> {
                     .service(api::user_transactions::get_user_balance)
                     .service(api::user_transactions::get_user_wallets)
             )
-        .route("/health", web::get().to(|| async {
-            HttpResponse::Ok().json(serde_json::json!({
-                "status":"healthy",
-                "service":"cardano-backend"
-            }))
-        }))
+        .service(api::health::health)
+        .service(api::health::live)
+        .service(api::health::ready)
+        .service(api::metrics::get_metrics)
+        //api docs
+        .service(SwaggerUi::new("/api/v1/docs/{_:.*}").url("/api/v1/openapi.json", openapi.clone()))
     })
-    .bind(("0.0.0.0",8000))?.run().await
+    .bind(("0.0.0.0",8000))?
+    //signals are handled by shutdown::run, which closes live connections before stopping
+    .disable_signals()
+    .shutdown_timeout(shutdown::DEADLINE.as_secs())
+    .run();
+
+    let result = shutdown::run(server, state, session_store).await;
+    telemetry.shutdown();
+    result
 
 
 }
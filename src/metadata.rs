@@ -0,0 +1,265 @@
+//transaction metadata rendering, same schemas as cardano-cli
+use pallas_codec::minicbor::{data::Type, decode, Decoder};
+use pallas_codec::utils::{Bytes, Int, KeyValuePairs, Nullable};
+use pallas_primitives::Metadatum;
+use pallas_traverse::{MultiEraBlock, MultiEraTx};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetadataSchema {
+    Detailed,
+    NoSchema,
+}
+
+impl MetadataSchema {
+    pub fn from_query(value: Option<&String>) -> Self {
+        match value.map(|s| s.as_str()) {
+            Some("detailed") | Some("detailed_schema") => MetadataSchema::Detailed,
+            _ => MetadataSchema::NoSchema,
+        }
+    }
+}
+
+pub fn int_to_json(value: i128) -> Value {
+    if let Ok(v) = i64::try_from(value) {
+        json!(v)
+    } else if let Ok(v) = u64::try_from(value) {
+        json!(v)
+    } else {
+        json!(value.to_string())
+    }
+}
+
+/// Detailed schema: every value tagged with its type ({"int":..}, {"string":..}, {"bytes":..}, ..)
+pub fn to_detailed_json(datum: &Metadatum) -> Value {
+    match datum {
+        Metadatum::Int(i) => json!({ "int": int_to_json(i128::from(*i)) }),
+        Metadatum::Bytes(b) => json!({ "bytes": hex::encode(b.as_slice()) }),
+        Metadatum::Text(t) => json!({ "string": t }),
+        Metadatum::Array(items) => json!({ "list": items.iter().map(to_detailed_json).collect::<Vec<_>>() }),
+        Metadatum::Map(entries) => json!({
+            "map": entries
+                .iter()
+                .map(|(k, v)| json!({ "k": to_detailed_json(k), "v": to_detailed_json(v) }))
+                .collect::<Vec<_>>()
+        }),
+    }
+}
+
+/// No schema: plain JSON, bytes as "0x..." strings and map keys stringified
+pub fn detailed_to_no_schema(value: &Value) -> Value {
+    if let Some(int) = value.get("int") {
+        return int.clone();
+    }
+    if let Some(text) = value.get("string") {
+        return text.clone();
+    }
+    if let Some(bytes) = value.get("bytes").and_then(|b| b.as_str()) {
+        return json!(format!("0x{}", bytes));
+    }
+    if let Some(items) = value.get("list").and_then(|l| l.as_array()) {
+        return Value::Array(items.iter().map(detailed_to_no_schema).collect());
+    }
+    if let Some(entries) = value.get("map").and_then(|m| m.as_array()) {
+        let mut object = serde_json::Map::new();
+        for entry in entries {
+            let key = match entry.get("k").map(detailed_to_no_schema) {
+                Some(Value::String(s)) => s,
+                Some(other) => other.to_string(),
+                None => continue,
+            };
+            object.insert(key, entry.get("v").map(detailed_to_no_schema).unwrap_or(Value::Null));
+        }
+        return Value::Object(object);
+    }
+    Value::Null
+}
+
+pub fn render(detailed: &Value, schema: MetadataSchema) -> Value {
+    match schema {
+        MetadataSchema::Detailed => detailed.clone(),
+        MetadataSchema::NoSchema => detailed_to_no_schema(detailed),
+    }
+}
+
+// Plain JSON back into a metadatum. Used for oura's JSON rendition where the
+// original types are already lost (bytes arrive as hex text), so it is never re-encoded as cbor
+fn from_plain_json(value: &Value) -> Option<Metadatum> {
+    match value {
+        Value::Number(n) => {
+            let int = n.as_i64().map(Int::from).or_else(|| {
+                n.as_u64().and_then(|v| Int::try_from(v as i128).ok())
+            })?;
+            Some(Metadatum::Int(int))
+        }
+        Value::String(s) => Some(Metadatum::Text(s.clone())),
+        Value::Array(items) => Some(Metadatum::Array(items.iter().filter_map(from_plain_json).collect())),
+        Value::Object(entries) => {
+            let pairs: Vec<(Metadatum, Metadatum)> = entries
+                .iter()
+                .filter_map(|(k, v)| Some((Metadatum::Text(k.clone()), from_plain_json(v)?)))
+                .collect();
+            Some(Metadatum::Map(KeyValuePairs::from(pairs)))
+        }
+        Value::Bool(_) | Value::Null => None,
+    }
+}
+
+/// Metadatum from an oura metadata record ({"label": "674", "map_json": {..}})
+pub fn from_rendition(entry: &Value) -> Option<(u64, Metadatum)> {
+    let label = entry.get("label")?.as_str()?.parse::<u64>().ok()?;
+
+    let datum = if let Some(v) = entry.get("map_json").or_else(|| entry.get("array_json")) {
+        from_plain_json(v)?
+    } else if let Some(v) = entry.get("int_scalar") {
+        match v {
+            Value::String(s) => Metadatum::Int(Int::try_from(s.parse::<i128>().ok()?).ok()?),
+            other => from_plain_json(other)?,
+        }
+    } else if let Some(v) = entry.get("text_scalar").and_then(|v| v.as_str()) {
+        Metadatum::Text(v.to_string())
+    } else if let Some(v) = entry.get("bytes_hex").and_then(|v| v.as_str()) {
+        Metadatum::Bytes(Bytes::from(hex::decode(v).ok()?))
+    } else {
+        return None;
+    };
+    Some((label, datum))
+}
+
+// (label, metadatum, on-chain cbor hex of it) of one transaction
+pub type LabelledMetadata = Vec<(u64, Metadatum, Option<String>)>;
+
+// Auxiliary data bytes of a transaction exactly as they are in the block
+fn aux_data_cbor<'a>(tx: &'a MultiEraTx) -> Option<&'a [u8]> {
+    let aux = match tx {
+        MultiEraTx::AlonzoCompatible(x, _) => &x.auxiliary_data,
+        MultiEraTx::Babbage(x) => &x.auxiliary_data,
+        MultiEraTx::Conway(x) => &x.auxiliary_data,
+        _ => return None,
+    };
+    match aux {
+        Nullable::Some(aux) => Some(aux.raw_cbor()),
+        _ => None,
+    }
+}
+
+// Entries of a definite or indefinite map, calling `entry` with the decoder at each key
+fn each_entry<'b>(
+    d: &mut Decoder<'b>,
+    mut entry: impl FnMut(&mut Decoder<'b>) -> Result<(), decode::Error>,
+) -> Result<(), decode::Error> {
+    match d.map()? {
+        Some(len) => (0..len).try_for_each(|_| entry(d)),
+        None => {
+            while d.datatype()? != Type::Break {
+                entry(d)?;
+            }
+            d.skip()
+        }
+    }
+}
+
+/// Bytes of the metadatum under each label, sliced out of the auxiliary data: a bare metadata
+/// map (shelley), [metadata, scripts] (allegra, mary) or #6.259({0: metadata, ..}) (alonzo on)
+pub fn labelled_cbor(aux: &[u8]) -> Result<Vec<(u64, &[u8])>, decode::Error> {
+    let mut d = Decoder::new(aux);
+    match d.datatype()? {
+        Type::Array | Type::ArrayIndef => {
+            d.array()?;
+        }
+        Type::Tag => {
+            d.tag()?;
+            let mut metadata_at = None;
+            each_entry(&mut d, |d| {
+                if d.u64()? == 0 {
+                    metadata_at = Some(d.position());
+                }
+                d.skip()
+            })?;
+            match metadata_at {
+                Some(position) => d.set_position(position),
+                None => return Ok(Vec::new()),
+            }
+        }
+        _ => {}
+    }
+
+    let mut entries = Vec::new();
+    each_entry(&mut d, |d| {
+        let label = d.u64()?;
+        let start = d.position();
+        d.skip()?;
+        entries.push((label, &aux[start..d.position()]));
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// Metadata of a transaction with the cbor of each label as it was encoded, None where slicing failed
+pub fn tx_metadata(tx: &MultiEraTx) -> LabelledMetadata {
+    let raw = aux_data_cbor(tx).and_then(|aux| labelled_cbor(aux).ok()).unwrap_or_default();
+    tx.metadata()
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|(label, datum)| {
+            let cbor = raw.iter().find(|(l, _)| *l == label).map(|(_, bytes)| hex::encode(bytes));
+            (label, datum.clone(), cbor)
+        })
+        .collect()
+}
+
+/// Metadata of every transaction in a block, keyed by tx hash
+pub fn from_block(block: &MultiEraBlock) -> Vec<(String, LabelledMetadata)> {
+    block
+        .txs()
+        .iter()
+        .map(|tx| (tx.hash().to_string(), tx_metadata(tx)))
+        .filter(|(_, entries)| !entries.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detailed_and_no_schema() {
+        let datum = Metadatum::Map(KeyValuePairs::from(vec![
+            (Metadatum::Text("msg".to_string()), Metadatum::Array(vec![Metadatum::Text("hello".to_string())])),
+            (Metadatum::Int(Int::from(1)), Metadatum::Bytes(Bytes::from(vec![0xca, 0xfe]))),
+        ]));
+
+        let detailed = to_detailed_json(&datum);
+        assert_eq!(detailed["map"][0]["k"], json!({"string": "msg"}));
+        assert_eq!(detailed["map"][1]["v"], json!({"bytes": "cafe"}));
+
+        let plain = detailed_to_no_schema(&detailed);
+        assert_eq!(plain, json!({"msg": ["hello"], "1": "0xcafe"}));
+    }
+
+    #[test]
+    fn test_cbor_roundtrip() {
+        let (label, datum) = from_rendition(&json!({"label": "674", "map_json": {"msg": ["hi"]}})).unwrap();
+        assert_eq!(label, 674);
+
+        let cbor = pallas_codec::minicbor::to_vec(&datum).unwrap();
+        assert_eq!(hex::encode(&cbor), "a1636d736781626869");
+        let decoded: Metadatum = pallas_codec::minicbor::decode(&cbor).unwrap();
+        assert_eq!(to_detailed_json(&decoded), to_detailed_json(&datum));
+    }
+
+    #[test]
+    fn test_labelled_cbor() {
+        // {674: {"msg": ["hi"]}, 1: 5}, the 5 encoded in two bytes where one would do
+        let shelley = hex::decode("a21902a2a1636d73678162686901190005").unwrap();
+        let entries = labelled_cbor(&shelley).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], (674, hex::decode("a1636d736781626869").unwrap().as_slice()));
+        assert_eq!(entries[1], (1, [0x19, 0x00, 0x05].as_slice()));
+
+        // #6.259({0: {674: "hi"}, 1: []})
+        let post_alonzo = hex::decode("d90103a200a11902a262686901 80".replace(' ', "")).unwrap();
+        assert_eq!(labelled_cbor(&post_alonzo).unwrap(), vec![(674, [0x62, 0x68, 0x69].as_slice())]);
+        assert!(labelled_cbor(&hex::decode("d90103a10180").unwrap()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+//prometheus metrics: ingestion, live connections, http and blockfrost
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    Error,
+};
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, Gauge, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::time::Instant;
+
+use crate::auth::SessionStore;
+use crate::oura_stream::BlockChainState;
+use crate::time::NETWORK_TIME;
+
+// Latency buckets in seconds, from cached lookups to slow upstream calls
+const LATENCY_BUCKETS: [f64; 12] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+pub struct Metrics {
+    registry: Registry,
+    pub events_processed: IntCounterVec,
+    pub events_failed: IntCounterVec,
+    pub rollbacks: IntCounter,
+    pub live_connections: IntGaugeVec,
+    pub live_messages: IntCounterVec,
+    pub http_requests: IntCounterVec,
+    pub http_duration: HistogramVec,
+    pub blockfrost_requests: IntCounterVec,
+    pub blockfrost_errors: IntCounterVec,
+    pub blockfrost_duration: HistogramVec,
+    pub rate_limited: IntCounterVec,
+    pub live_rejected: IntCounterVec,
+    // set when scraped
+    tip_slot: IntGauge,
+    ingestion_lag: Gauge,
+    sessions: IntGauge,
+    revoked_tokens: IntGauge,
+}
+
+fn counter_vec(name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    IntCounterVec::new(Opts::new(name, help), labels).expect("valid counter")
+}
+
+fn histogram_vec(name: &str, help: &str, labels: &[&str]) -> HistogramVec {
+    HistogramVec::new(HistogramOpts::new(name, help).buckets(LATENCY_BUCKETS.to_vec()), labels).expect("valid histogram")
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let metrics = Self {
+            registry: Registry::new_custom(Some("explorer".to_string()), None).expect("valid registry"),
+            events_processed: counter_vec("ingest_events_total", "Oura events processed, by record type", &["type"]),
+            events_failed: counter_vec("ingest_events_failed_total", "Oura events or block parts that could not be read, by type", &["type"]),
+            rollbacks: IntCounter::new("rollbacks_total", "Blocks dropped by chain rollbacks").expect("valid counter"),
+            live_connections: IntGaugeVec::new(Opts::new("live_connections", "Open live event connections, by transport"), &["transport"])
+                .expect("valid gauge"),
+            live_messages: counter_vec("live_messages_sent_total", "Messages sent to live event clients, by transport", &["transport"]),
+            http_requests: counter_vec("http_requests_total", "HTTP requests, by method, route pattern and status", &["method", "route", "status"]),
+            http_duration: histogram_vec("http_request_duration_seconds", "HTTP request latency, by method and route pattern", &["method", "route"]),
+            blockfrost_requests: counter_vec("blockfrost_requests_total", "Blockfrost calls, by endpoint", &["endpoint"]),
+            blockfrost_errors: counter_vec("blockfrost_errors_total", "Failed Blockfrost calls (transport errors and non 2xx other than 404), by endpoint", &["endpoint"]),
+            blockfrost_duration: histogram_vec("blockfrost_request_duration_seconds", "Blockfrost call latency, by endpoint", &["endpoint"]),
+            rate_limited: counter_vec("rate_limited_total", "Requests refused with 429, by budget and key kind (ip or address)", &["budget", "key"]),
+            live_rejected: counter_vec("live_connections_rejected_total", "Live connections refused by the per IP cap, by transport", &["transport"]),
+            tip_slot: IntGauge::new("tip_slot", "Slot of the newest ingested block").expect("valid gauge"),
+            ingestion_lag: Gauge::new("ingestion_lag_seconds", "Wall clock time minus the time of the newest ingested block's slot").expect("valid gauge"),
+            sessions: IntGauge::new("sessions", "Wallet sessions in the session store").expect("valid gauge"),
+            revoked_tokens: IntGauge::new("revoked_tokens", "Size of the revoked token set").expect("valid gauge"),
+        };
+
+        let collectors: Vec<Box<dyn prometheus::core::Collector>> = vec![
+            Box::new(metrics.events_processed.clone()),
+            Box::new(metrics.events_failed.clone()),
+            Box::new(metrics.rollbacks.clone()),
+            Box::new(metrics.live_connections.clone()),
+            Box::new(metrics.live_messages.clone()),
+            Box::new(metrics.http_requests.clone()),
+            Box::new(metrics.http_duration.clone()),
+            Box::new(metrics.blockfrost_requests.clone()),
+            Box::new(metrics.blockfrost_errors.clone()),
+            Box::new(metrics.blockfrost_duration.clone()),
+            Box::new(metrics.rate_limited.clone()),
+            Box::new(metrics.live_rejected.clone()),
+            Box::new(metrics.tip_slot.clone()),
+            Box::new(metrics.ingestion_lag.clone()),
+            Box::new(metrics.sessions.clone()),
+            Box::new(metrics.revoked_tokens.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).expect("metric registered once");
+        }
+        metrics
+    }
+
+    /// Text exposition of every metric, with the gauges read from the state refreshed first
+    pub async fn render(&self, state: &BlockChainState, sessions: &SessionStore) -> Result<String, String> {
+        if let Some(tip) = state.get_blocks(1).await.first() {
+            self.tip_slot.set(tip.slot as i64);
+            let now = chrono::Utc::now().timestamp() as f64;
+            self.ingestion_lag.set(now - NETWORK_TIME.slot_to_time(tip.slot) as f64);
+        }
+        self.sessions.set(sessions.session_count().await as i64);
+        self.revoked_tokens.set(sessions.revoked_count().await as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| format!("Failed to encode metrics: {}", e))?;
+        String::from_utf8(buffer).map_err(|e| format!("Failed to encode metrics: {}", e))
+    }
+}
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+/// Counts an open live connection (ws, graphql_ws, sse) until dropped
+pub struct LiveConnection {
+    transport: &'static str,
+}
+
+impl LiveConnection {
+    pub fn open(transport: &'static str) -> Self {
+        METRICS.live_connections.with_label_values(&[transport]).inc();
+        Self { transport }
+    }
+
+    pub fn sent(&self) {
+        METRICS.live_messages.with_label_values(&[self.transport]).inc();
+    }
+}
+
+impl Drop for LiveConnection {
+    fn drop(&mut self) {
+        METRICS.live_connections.with_label_values(&[self.transport]).dec();
+    }
+}
+
+/// Blockfrost path with ids, hashes and addresses replaced by `{}`, so the label stays low cardinality
+pub fn blockfrost_endpoint(path: &str) -> String {
+    let path = path.split('?').next().unwrap_or(path);
+    path.split('/')
+        .map(|segment| {
+            let word = segment.len() <= 24 && segment.chars().all(|c| c.is_ascii_lowercase() || c == '_' || c == '-');
+            if word { segment } else { "{}" }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Records a Blockfrost call, `ok` is false for transport errors and unexpected statuses
+pub fn blockfrost_call(path: &str, started: Instant, ok: bool) {
+    let endpoint = blockfrost_endpoint(path);
+    METRICS.blockfrost_requests.with_label_values(&[&endpoint]).inc();
+    METRICS.blockfrost_duration.with_label_values(&[&endpoint]).observe(started.elapsed().as_secs_f64());
+    if !ok {
+        METRICS.blockfrost_errors.with_label_values(&[&endpoint]).inc();
+    }
+}
+
+/// Counts requests and their latency by the route pattern that served them
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let res = next.call(req).await;
+
+    let (route, status) = match &res {
+        Ok(res) => (res.request().match_pattern(), res.status()),
+        Err(e) => (None, e.as_response_error().status_code()),
+    };
+    // unmatched paths share a label instead of adding one per probe
+    let route = route.unwrap_or_else(|| "unmatched".to_string());
+    METRICS.http_requests.with_label_values(&[&method, &route, status.as_str()]).inc();
+    METRICS.http_duration.with_label_values(&[&method, &route]).observe(started.elapsed().as_secs_f64());
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blockfrost_endpoint() {
+        assert_eq!(blockfrost_endpoint("/addresses/addr_test1qz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgs68faae/transactions"), "/addresses/{}/transactions");
+        assert_eq!(blockfrost_endpoint(&format!("/txs/{}", "ab".repeat(32))), "/txs/{}");
+        assert_eq!(blockfrost_endpoint("/assets/policy/abc123?page=2"), "/assets/policy/{}");
+        assert_eq!(blockfrost_endpoint("/pools/pool1abc/metadata"), "/pools/{}/metadata");
+    }
+
+    #[tokio::test]
+    async fn test_render() {
+        let _ws = LiveConnection::open("ws");
+        METRICS.rollbacks.inc();
+        let text = METRICS.render(&BlockChainState::new(), &SessionStore::new()).await.unwrap();
+        assert!(text.contains("explorer_rollbacks_total"));
+        assert!(text.contains("explorer_live_connections{transport=\"ws\"}"));
+        assert!(text.contains("explorer_sessions 0"));
+    }
+}
@@ -0,0 +1,40 @@
+//native asset metadata (CIP-25 / CIP-68)
+use serde::{Deserialize,Serialize};
+use utoipa::ToSchema;
+use async_graphql::{Enum, SimpleObject};
+
+#[derive(Debug,Clone,Copy,Serialize,Deserialize,PartialEq,Eq,ToSchema,Enum)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataStandard {
+    Cip25,
+    Cip68,
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize,ToSchema,SimpleObject)]
+pub struct AssetFile {
+    pub name: Option<String>,
+    pub media_type: Option<String>,
+    pub src: String,
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize,ToSchema,SimpleObject)]
+pub struct AssetMetadata {
+    pub unit: String, //policy id + asset name hex
+    pub policy_id: String,
+    pub asset_name: String, //hex
+    pub asset_name_ascii: Option<String>,
+    pub fingerprint: String,
+    pub standard: MetadataStandard,
+    pub version: u64,
+    pub label: Option<u16>, //CIP-67 label, only for CIP-68 tokens
+    pub name: Option<String>,
+    pub image: Option<String>,
+    pub media_type: Option<String>,
+    pub description: Option<String>,
+    pub ticker: Option<String>,
+    pub decimals: Option<u64>,
+    pub files: Vec<AssetFile>,
+    pub raw: serde_json::Value,
+    pub tx_hash: String,
+    pub slot: u64,
+}
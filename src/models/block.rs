@@ -1,8 +1,10 @@
-
 //block struct
  use serde::{Deserialize,Serialize};
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
 
-#[derive(Debug,Clone,Serialize,Deserialize)] //traits for struct
+#[derive(Debug,Clone,Serialize,Deserialize,ToSchema,SimpleObject)] //traits for struct
+#[graphql(complex)]
 pub struct Block {
     pub hash: String,
     pub number: u64,
@@ -11,12 +13,30 @@ pub struct Block {
     pub timestamp: u64,
     pub tx_count: u32,
     pub size: u64,
+    //block producer info, missing for byron blocks
+    pub previous_hash: Option<String>,
+    pub issuer_vkey: Option<String>,
+    pub pool_id: Option<String>, //bech32 pool1...
+    pub vrf_vkey: Option<String>,
+    pub vrf_output: Option<String>,
+    pub protocol_version: Option<ProtocolVersion>,
+    pub op_cert_counter: Option<u64>,
+    #[serde(default)]
+    pub confirmations: u64, //blocks on top of this one, filled in against the current tip
+}
+
+#[derive(Debug,Clone,Copy,Serialize,Deserialize,PartialEq,ToSchema,SimpleObject)]
+pub struct ProtocolVersion {
+    pub major: u64,
+    pub minor: u64,
 }
 
 //constructor
 impl Block { //methods
     pub fn new(hash:String,number:u64,slot:u64,epoch:u64,
         timestamp: u64,tx_count:u32,size: u64)-> Self{
-            Self { hash, number, slot, epoch, timestamp, tx_count, size }
+            Self { hash, number, slot, epoch, timestamp, tx_count, size,
+                previous_hash: None, issuer_vkey: None, pool_id: None, vrf_vkey: None,
+                vrf_output: None, protocol_version: None, op_cert_counter: None, confirmations: 0 }
     }
 }
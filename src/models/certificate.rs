@@ -0,0 +1,30 @@
+//staking, pool and governance certificates and reward withdrawals
+use serde::{Deserialize,Serialize};
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
+
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,ToSchema,SimpleObject)]
+pub struct Certificate {
+    pub tx_hash: String,
+    pub cert_index: u32, //position in the transaction body
+    pub block_number: u64,
+    pub slot: u64,
+    pub timestamp: u64,
+    pub kind: String, //stake_registration, stake_delegation, pool_registration, vote_delegation, drep_registration, ...
+    pub stake_address: Option<String>,
+    pub pool_id: Option<String>,
+    pub drep: Option<String>, //drep id, or drep_always_abstain / drep_always_no_confidence
+    pub deposit: Option<u64>, //deposit paid, or refunded on deregistration
+    pub epoch: Option<u64>, //pool retirement epoch
+    pub details: serde_json::Value, //pool parameters, anchors, committee credentials
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,ToSchema,SimpleObject)]
+pub struct Withdrawal {
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub slot: u64,
+    pub timestamp: u64,
+    pub stake_address: String,
+    pub amount: u64,
+}
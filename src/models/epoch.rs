@@ -0,0 +1,33 @@
+//per epoch aggregates
+use serde::{Deserialize,Serialize};
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
+use std::collections::HashSet;
+
+#[derive(Debug,Clone,Serialize,Deserialize,ToSchema,SimpleObject)]
+#[graphql(complex)]
+pub struct EpochSummary {
+    pub epoch: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub blocks: u64,
+    pub transactions: u64,
+    pub fees: u64,
+    pub output_volume: u64,
+    pub first_block: Option<String>, //hash
+    pub first_block_time: Option<u64>,
+    pub last_block: Option<String>,
+    pub last_block_time: Option<u64>,
+    pub active_pools: usize,
+    #[serde(skip)]
+    #[graphql(skip)]
+    pub pools: HashSet<String>,
+}
+
+impl EpochSummary {
+    pub fn new(epoch: u64, start_time: u64, end_time: u64) -> Self {
+        Self { epoch, start_time, end_time, blocks: 0, transactions: 0, fees: 0, output_volume: 0,
+            first_block: None, first_block_time: None, last_block: None, last_block_time: None,
+            active_pools: 0, pools: HashSet::new() }
+    }
+}
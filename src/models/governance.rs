@@ -0,0 +1,47 @@
+//Conway governance proposals and votes
+use serde::{Deserialize,Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,ToSchema)]
+pub struct Proposal {
+    pub id: String, //tx_hash#index
+    pub tx_hash: String,
+    pub index: u32,
+    pub block_number: u64,
+    pub slot: u64,
+    pub timestamp: u64,
+    pub epoch: u64,
+    pub action: String, //parameter_change, hard_fork_initiation, treasury_withdrawals, no_confidence, update_committee, new_constitution or info
+    pub deposit: u64,
+    pub return_address: Option<String>,
+    pub anchor: serde_json::Value, //url and data_hash of the rationale
+    pub details: serde_json::Value, //action specific fields, previous action id
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,ToSchema)]
+pub struct Vote {
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub slot: u64,
+    pub timestamp: u64,
+    pub proposal_id: String,
+    pub voter_role: String, //drep, spo or cc
+    pub voter: String, //drep id, pool id or committee hot credential hash
+    pub vote: String, //yes, no or abstain
+    pub anchor: serde_json::Value,
+}
+
+#[derive(Debug,Clone,Default,Serialize,Deserialize,PartialEq,ToSchema)]
+pub struct Tally {
+    pub yes: u64,
+    pub no: u64,
+    pub abstain: u64,
+}
+
+//latest vote of every voter, per role
+#[derive(Debug,Clone,Default,Serialize,Deserialize,PartialEq,ToSchema)]
+pub struct VoteTally {
+    pub drep: Tally,
+    pub spo: Tally,
+    pub cc: Tally,
+}
@@ -0,0 +1,15 @@
+//transaction metadata (auxiliary data) per label
+use serde::{Deserialize,Serialize};
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
+
+#[derive(Debug,Clone,Serialize,Deserialize,ToSchema,SimpleObject)]
+pub struct TxMetadata {
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub slot: u64,
+    pub timestamp: u64,
+    pub label: u64,
+    pub cbor: Option<String>, //on-chain hex of the metadatum under this label, none when only oura's JSON rendition was seen
+    pub json: serde_json::Value, //detailed schema rendering
+}
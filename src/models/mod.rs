@@ -1,5 +1,19 @@
 pub mod block;
 pub mod transaction;
+pub mod asset;
+pub mod metadata;
+pub mod epoch;
+pub mod pending;
+pub mod script;
+pub mod certificate;
+pub mod governance;
 
-pub use block::Block;
-pub use transaction::Transaction;
\ No newline at end of file
+pub use block::{Block, ProtocolVersion};
+pub use transaction::{RolledBackTx, Transaction};
+pub use asset::{AssetFile, AssetMetadata, MetadataStandard};
+pub use metadata::TxMetadata;
+pub use epoch::EpochSummary;
+pub use pending::PendingTx;
+pub use certificate::{Certificate, Withdrawal};
+pub use governance::{Proposal, Tally, Vote, VoteTally};
+pub use script::{CollateralOutput, Datum, InputRef, Redeemer, ScriptSummary, ScriptWitness, TxScriptData};
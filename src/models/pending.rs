@@ -0,0 +1,12 @@
+//transactions submitted through the explorer, waiting for a block
+use serde::{Deserialize,Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug,Clone,Serialize,Deserialize,ToSchema)]
+pub struct PendingTx {
+    pub hash: String,
+    pub era: String,
+    pub size: u64,
+    pub submitted_at: u64,
+    pub via: String, //node or submit_api
+}
@@ -0,0 +1,87 @@
+//smart contract execution data of a transaction and per script usage
+use serde::{Deserialize,Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,ToSchema)]
+pub struct ScriptWitness {
+    pub hash: String,
+    pub kind: String, //native or plutus
+    pub size: Option<u64>, //plutus script bytes
+    pub json: Option<serde_json::Value>, //native script in cardano-cli format
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,ToSchema)]
+pub struct Redeemer {
+    pub tag: String, //spend, mint, cert or reward
+    pub index: u32,
+    pub data: serde_json::Value,
+    pub ex_units_mem: u64,
+    pub ex_units_steps: u64,
+    pub script_hash: Option<String>, //when the transaction alone tells which script it unlocks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typed: Option<serde_json::Value>, //rendered from the script's CIP-57 blueprint
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,ToSchema)]
+pub struct Datum {
+    pub hash: String,
+    pub source: String, //inline (in an output), witness, or hash (referenced only)
+    pub output_index: Option<u32>,
+    pub data: Option<serde_json::Value>,
+    #[serde(default)]
+    pub script_hash: Option<String>, //script address the output locks funds at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typed: Option<serde_json::Value>,
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,ToSchema)]
+pub struct InputRef {
+    pub tx_hash: String,
+    pub index: u64,
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,ToSchema)]
+pub struct CollateralOutput {
+    pub address: String,
+    pub lovelace: u64,
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize,PartialEq,ToSchema)]
+pub struct TxScriptData {
+    pub tx_hash: String,
+    pub scripts: Vec<ScriptWitness>,
+    pub redeemers: Vec<Redeemer>,
+    pub datums: Vec<Datum>,
+    pub collateral_inputs: Vec<InputRef>,
+    pub collateral_output: Option<CollateralOutput>,
+}
+
+impl TxScriptData {
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty() && self.redeemers.is_empty() && self.datums.is_empty()
+            && self.collateral_inputs.is_empty() && self.collateral_output.is_none()
+    }
+}
+
+//usage of one script across the transactions seen
+#[derive(Debug,Clone,Serialize,Deserialize,ToSchema)]
+pub struct ScriptSummary {
+    pub hash: String,
+    pub kind: String,
+    pub size: Option<u64>,
+    pub transactions: u64,
+    pub redeemers: u64,
+    pub failed: u64, //transactions that failed phase-2 validation
+    pub ex_units_mem: u64,
+    pub ex_units_steps: u64,
+    pub first_seen_slot: u64,
+    pub last_seen_slot: u64,
+    pub recent_transactions: Vec<String>, //newest first
+}
+
+impl ScriptSummary {
+    pub fn new(hash: String, kind: String, slot: u64) -> Self {
+        Self { hash, kind, size: None, transactions: 0, redeemers: 0, failed: 0, ex_units_mem: 0,
+            ex_units_steps: 0, first_seen_slot: slot, last_seen_slot: slot, recent_transactions: Vec::new() }
+    }
+}
@@ -1,7 +1,10 @@
 
 //transaction struct
 use serde::{Deserialize,Serialize};
-#[derive(Debug,Clone,Serialize,Deserialize)]
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
+#[derive(Debug,Clone,Serialize,Deserialize,ToSchema,SimpleObject)]
+#[graphql(complex)]
 pub struct Transaction {
     pub hash: String,
     pub block_number: u64,
@@ -10,11 +13,27 @@ pub struct Transaction {
     pub input_count: u32,
     pub output_count: u32,
     pub total_output: u64,
+    #[serde(default)]
+    pub confirmations: u64, //blocks on top of the including block
+    #[serde(default = "valid")]
+    pub is_valid: bool, //false when phase-2 (script) validation failed and only collateral was taken
+}
+
+fn valid() -> bool {
+    true
 }
 
 impl Transaction {
     pub fn new(hash: String,block_number: u64,timestamp:u64,fee:u64,input_count:u32,
                output_count: u32,total_output:u64 ) -> Self {
-                Self { hash, block_number, timestamp, fee, input_count, output_count, total_output }
+                Self { hash, block_number, timestamp, fee, input_count, output_count, total_output, confirmations: 0, is_valid: true }
                }
-}
\ No newline at end of file
+}
+
+//transaction whose block was rolled back
+#[derive(Debug,Clone,Serialize,Deserialize,ToSchema)]
+pub struct RolledBackTx {
+    pub hash: String,
+    pub block_number: u64,
+    pub rolled_back_at: u64,
+}
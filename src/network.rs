@@ -0,0 +1,87 @@
+//the cardano network the explorer follows, picked once from CARDANO_NETWORK
+use once_cell::sync::Lazy;
+
+use crate::time::NetworkTime;
+
+/// Everything that differs between networks: where oura connects, which chain it decodes,
+/// the Blockfrost project and the era history
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    pub name: &'static str,
+    pub relay: &'static str,
+    pub oura_chain: &'static str, //oura's well known chain, sets the magic and era times
+    pub blockfrost_url: &'static str,
+    pub magic: u64,
+}
+
+pub const MAINNET: Network = Network {
+    name: "mainnet",
+    relay: "backbone.cardano.iog.io:3001",
+    oura_chain: "mainnet",
+    blockfrost_url: "https://cardano-mainnet.blockfrost.io/api/v0",
+    magic: pallas_network::miniprotocols::MAINNET_MAGIC,
+};
+
+pub const PREPROD: Network = Network {
+    name: "preprod",
+    relay: "preprod-node.world.dev.cardano.org:30000",
+    oura_chain: "preprod",
+    blockfrost_url: "https://cardano-preprod.blockfrost.io/api/v0",
+    magic: pallas_network::miniprotocols::PRE_PRODUCTION_MAGIC,
+};
+
+pub const PREVIEW: Network = Network {
+    name: "preview",
+    relay: "preview-node.world.dev.cardano.org:30002",
+    oura_chain: "preview",
+    blockfrost_url: "https://cardano-preview.blockfrost.io/api/v0",
+    magic: pallas_network::miniprotocols::PREVIEW_MAGIC,
+};
+
+impl Network {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "mainnet" => Ok(MAINNET),
+            "preprod" | "pre-prod" => Ok(PREPROD),
+            "preview" => Ok(PREVIEW),
+            _ => Err(format!("Unknown CARDANO_NETWORK {}, expected mainnet, preprod or preview", name)),
+        }
+    }
+
+    /// Preset era history, before any genesis file overrides
+    pub fn time(&self) -> NetworkTime {
+        match self.name {
+            "mainnet" => NetworkTime::mainnet(),
+            "preview" => NetworkTime::preview(),
+            _ => NetworkTime::preprod(),
+        }
+    }
+}
+
+/// CARDANO_NETWORK, preprod when unset
+pub fn configured() -> Result<Network, String> {
+    dotenv::dotenv().ok();
+    match std::env::var("CARDANO_NETWORK") {
+        Ok(name) => Network::from_name(&name),
+        Err(_) => Ok(PREPROD),
+    }
+}
+
+// main checks `configured` before anything reads this, so the panic cannot happen at runtime
+pub static NETWORK: Lazy<Network> = Lazy::new(|| configured().unwrap_or_else(|e| panic!("{}", e)));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_from_name() {
+        let preprod = Network::from_name("pre-prod").unwrap();
+        assert_eq!(preprod, PREPROD);
+        assert_eq!(preprod.time().network, "preprod");
+        assert_eq!(Network::from_name("mainnet").unwrap().time().shelley_start_epoch, 208);
+        assert_eq!(Network::from_name("preview").unwrap().oura_chain, "preview");
+        assert!(Network::from_name("sanchonet").is_err());
+        assert!(Network::from_name("").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//OpenAPI document generated from the handler annotations and model schemas
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::error::ErrorBody;
+use crate::{api, auth, websocket};
+
+/// Served at /api/v1/openapi.json, browsable at /api/v1/docs/
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Cardano PreProd Live Explorer API",
+        description = "Blocks, transactions, assets, pools, governance and wallet endpoints of the explorer. Errors share the `ErrorBody` envelope.",
+    ),
+    paths(
+        api::blocks::get_blocks,
+        api::blocks::get_latest_block,
+        api::blocks::get_block,
+        api::transactions::get_transactions,
+        api::transactions::get_transaction,
+        api::transactions::get_transaction_status,
+        api::assets::get_assets,
+        api::assets::get_policy_assets,
+        api::assets::get_asset,
+        api::metadata::get_metadata_by_label,
+        api::metadata::get_transaction_metadata,
+        api::scripts::get_script,
+        api::pools::get_pools,
+        api::pools::get_pool_blocks,
+        api::pools::get_pool_registrations,
+        api::stake::get_stake,
+        api::governance::get_proposals,
+        api::governance::get_proposal,
+        api::governance::get_drep,
+        api::time::get_slot_time,
+        api::time::get_posix_slot,
+        api::epochs::get_epochs,
+        api::epochs::get_epoch,
+        api::stats::get_stats,
+        api::search::get_search,
+        api::addresses::get_address_info,
+        api::tools::decode_tx,
+        api::tools::decode_datum,
+        api::submit::submit_tx,
+        api::health::health,
+        api::health::live,
+        api::health::ready,
+        api::metrics::get_metrics,
+        api::graphql::graphql,
+        api::graphql::graphiql,
+        api::graphql::graphql_ws,
+        api::user_transactions::get_user_transactions,
+        api::user_transactions::get_user_balance,
+        api::user_transactions::get_user_wallets,
+        auth::routes::get_challenge,
+        auth::routes::login,
+        auth::routes::refresh_access_token,
+        auth::routes::logout,
+        auth::routes::get_current_user,
+        auth::routes::add_wallet,
+        websocket::websocket_route,
+        api::stream::get_stream,
+    ),
+    components(schemas(ErrorBody)),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "blocks"), (name = "transactions"), (name = "assets"), (name = "metadata"),
+        (name = "scripts"), (name = "pools"), (name = "stake"), (name = "governance"),
+        (name = "time"), (name = "epochs"), (name = "stats"), (name = "search"),
+        (name = "addresses"), (name = "tools"), (name = "health"),
+        (name = "graphql", description = "GraphQL over the same data, with subscriptions to live chain events"),
+        (name = "auth", description = "Wallet signature login and JWT sessions"),
+        (name = "user", description = "Wallet data of the signed in user"),
+        (name = "live", description = "Chain events pushed over WebSocket or server-sent events"),
+    ),
+)]
+pub struct ApiDoc;
+
+// JWT access tokens from /api/v1/auth/login, sent as Authorization: Bearer <token>
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // Handlers passed to .service(..) and paths passed to .route(..) in main.rs
+    fn registered_routes() -> (Vec<String>, Vec<String>) {
+        let main = include_str!("main.rs");
+        let services = main
+            .split(".service(")
+            .skip(1)
+            .map(|rest| rest.trim_start().split(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':')).next().unwrap_or(""))
+            .filter(|name| name.starts_with("api::") || name.starts_with("auth::routes::"))
+            .map(|name| name.rsplit("::").next().unwrap_or(name).to_string())
+            .collect();
+        let routes = main
+            .split(".route(\"")
+            .skip(1)
+            .filter_map(|rest| rest.split('"').next())
+            .map(String::from)
+            .collect();
+        (services, routes)
+    }
+
+    #[test]
+    fn test_every_route_is_documented() {
+        let doc = ApiDoc::openapi();
+        let operations: HashSet<String> = doc
+            .paths
+            .paths
+            .values()
+            .flat_map(|item| {
+                [&item.get, &item.post, &item.put, &item.delete, &item.patch]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|op| op.operation_id.clone())
+            })
+            .collect();
+
+        let (services, routes) = registered_routes();
+        assert!(services.len() > 30, "found only {} services in main.rs", services.len());
+        for service in services {
+            assert!(operations.contains(&service), "{} is registered in main.rs but missing from ApiDoc", service);
+        }
+        for route in routes {
+            assert!(doc.paths.paths.contains_key(&route), "{} is registered in main.rs but missing from ApiDoc", route);
+        }
+    }
+
+    #[test]
+    fn test_scoped_paths_and_security() {
+        let doc = ApiDoc::openapi();
+        for path in ["/api/v1/auth/me", "/api/v1/auth/logout", "/api/v1/user/balance", "/api/v1/blocks/{id}", "/api/v1/transactions/submit"] {
+            assert!(doc.paths.paths.contains_key(path), "{} missing", path);
+        }
+        let me = doc.paths.paths["/api/v1/auth/me"].get.as_ref().unwrap();
+        assert!(me.security.is_some());
+        assert!(doc.components.unwrap().security_schemes.contains_key("bearer"));
+    }
+}
@@ -1,15 +1,70 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use crate::models::{Block, Transaction};
+use std::sync::Mutex;
+use tokio::sync::{broadcast, RwLock};
+use pallas_traverse::MultiEraBlock;
+use tracing::Instrument;
+use crate::{certs, cip, events, governance, metadata, plutus, pools, shutdown, snapshot};
+use crate::health::SourceStatus;
+use crate::metrics::METRICS;
+use crate::network::NETWORK;
+use crate::snapshot::ChainPoint;
+use crate::stats::{NetworkStats, StatsSnapshot, MAX_BLOCK_BODY_SIZE};
+use crate::events::{ChainEvent, EventLog, Sequenced};
+use crate::models::{
+    AssetMetadata, Block, Certificate, EpochSummary, PendingTx, Proposal, RolledBackTx, ScriptSummary, Transaction, TxMetadata, TxScriptData,
+    Vote, Withdrawal,
+};
+use crate::time::NETWORK_TIME;
 
-// Constants for Cardano preprod network
-const CARDANO_RELAY: &str = "preprod-node.world.dev.cardano.org:30000";
-const CARDANO_MAGIC: &str = "pre-prod"; // Preprod magic number
+// Daemon config written to STATE_DIR on start, unless OURA_CONFIG names one to use instead
+const OURA_CONFIG_FILE: &str = "oura.toml";
+
+// Token metadata kept in memory
+const MAX_ASSETS: usize = 10000;
+// Replaced versions of a token's metadata kept to restore on rollback
+const ASSET_VERSIONS: usize = 5;
+// Metadata entries (tx, label) kept in memory
+const MAX_METADATA: usize = 5000;
+// Epoch summaries kept in memory
+const MAX_EPOCHS: usize = 500;
+// Submitted transactions not seen in a block after this long are dropped
+const PENDING_TIMEOUT: u64 = 7200;
+// Rolled back transactions remembered for status lookups
+const MAX_ROLLED_BACK: usize = 1000;
+// Scripts with usage summaries kept in memory
+const MAX_SCRIPTS: usize = 5000;
+// Recent transactions listed per script
+const SCRIPT_RECENT_TXS: usize = 20;
+// Certificates and withdrawals kept in memory
+const MAX_CERTIFICATES: usize = 10000;
+const MAX_WITHDRAWALS: usize = 10000;
+// Governance proposals and votes kept in memory
+const MAX_PROPOSALS: usize = 1000;
+const MAX_VOTES: usize = 20000;
 
 // Common state
 pub struct BlockChainState {
     pub blocks: Arc<RwLock<Vec<Block>>>,
     pub transactions: Arc<RwLock<Vec<Transaction>>>,
+    pub assets: Arc<RwLock<HashMap<String, AssetMetadata>>>, //keyed by unit
+    pub asset_versions: Arc<RwLock<HashMap<String, Vec<AssetMetadata>>>>, //keyed by unit, metadata replaced by newer transactions, oldest first
+    pub metadata: Arc<RwLock<Vec<TxMetadata>>>,
+    pub pool_blocks: Arc<RwLock<HashMap<String, BTreeMap<u64, u64>>>>, //pool id -> epoch -> blocks
+    pub epochs: Arc<RwLock<BTreeMap<u64, EpochSummary>>>,
+    pub events: broadcast::Sender<Sequenced>,
+    pub event_log: Mutex<EventLog>, //numbers events and keeps recent ones for stream resumption
+    pub source: Arc<RwLock<SourceStatus>>, //oura child process
+    pub stats: Arc<RwLock<NetworkStats>>,
+    pub pending: Arc<RwLock<HashMap<String, PendingTx>>>, //keyed by tx hash
+    pub rolled_back: Arc<RwLock<HashMap<String, RolledBackTx>>>, //keyed by tx hash
+    pub script_data: Arc<RwLock<HashMap<String, TxScriptData>>>, //keyed by tx hash, for transactions still in memory
+    pub scripts: Arc<RwLock<HashMap<String, ScriptSummary>>>, //keyed by script hash
+    pub invalid_txs: Arc<RwLock<HashMap<String, u64>>>, //phase-2 failures from block cbor by block number, until their tx record arrives
+    pub certificates: Arc<RwLock<Vec<Certificate>>>, //newest first
+    pub withdrawals: Arc<RwLock<Vec<Withdrawal>>>, //newest first
+    pub proposals: Arc<RwLock<Vec<Proposal>>>, //newest first
+    pub votes: Arc<RwLock<Vec<Vote>>>, //newest first
 }
 
 impl BlockChainState {
@@ -17,6 +72,24 @@ impl BlockChainState {
         Self {
             blocks: Arc::new(RwLock::new(Vec::new())),
             transactions: Arc::new(RwLock::new(Vec::new())),
+            assets: Arc::new(RwLock::new(HashMap::new())),
+            asset_versions: Arc::new(RwLock::new(HashMap::new())),
+            metadata: Arc::new(RwLock::new(Vec::new())),
+            pool_blocks: Arc::new(RwLock::new(HashMap::new())),
+            epochs: Arc::new(RwLock::new(BTreeMap::new())),
+            events: events::channel(),
+            event_log: Mutex::new(EventLog::new()),
+            source: Arc::new(RwLock::new(SourceStatus::new())),
+            stats: Arc::new(RwLock::new(NetworkStats::new())),
+            pending: Arc::new(RwLock::new(HashMap::new())),
+            rolled_back: Arc::new(RwLock::new(HashMap::new())),
+            script_data: Arc::new(RwLock::new(HashMap::new())),
+            scripts: Arc::new(RwLock::new(HashMap::new())),
+            invalid_txs: Arc::new(RwLock::new(HashMap::new())),
+            certificates: Arc::new(RwLock::new(Vec::new())),
+            withdrawals: Arc::new(RwLock::new(Vec::new())),
+            proposals: Arc::new(RwLock::new(Vec::new())),
+            votes: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
@@ -31,45 +104,858 @@ impl BlockChainState {
     }
 
     pub async fn add_transactions(&self, tx: Transaction) {
+        // re-applied after a rollback
+        self.rolled_back.write().await.remove(&tx.hash);
         let mut transactions = self.transactions.write().await;
         transactions.insert(0, tx);
 
         // Only keep 500
         if transactions.len() > 500 {
-            transactions.truncate(500);
+            let dropped = transactions.split_off(500);
+            let mut script_data = self.script_data.write().await;
+            for tx in dropped {
+                script_data.remove(&tx.hash);
+            }
+        }
+    }
+
+    pub async fn mark_invalid(&self, hashes: Vec<String>, block_number: u64) {
+        let mut invalid = self.invalid_txs.write().await;
+        invalid.extend(hashes.into_iter().map(|hash| (hash, block_number)));
+    }
+
+    /// Whether block cbor reported the transaction as failing phase-2 validation
+    pub async fn take_invalid(&self, hash: &str) -> bool {
+        let mut invalid = self.invalid_txs.write().await;
+        invalid.remove(hash).is_some()
+    }
+
+    /// Stores a transaction's script data and counts it against every script involved
+    pub async fn add_script_data(&self, data: TxScriptData, slot: u64, is_valid: bool) {
+        {
+            let mut scripts = self.scripts.write().await;
+            for hash in involved_scripts(&data) {
+                let witness = data.scripts.iter().find(|s| s.hash == hash);
+                let kind = witness.map(|s| s.kind.clone()).unwrap_or_else(|| "plutus".to_string());
+                let summary = scripts
+                    .entry(hash.to_string())
+                    .or_insert_with(|| ScriptSummary::new(hash.to_string(), kind, slot));
+                if let Some(size) = witness.and_then(|s| s.size) {
+                    summary.size = Some(size);
+                }
+                summary.transactions += 1;
+                if !is_valid {
+                    summary.failed += 1;
+                }
+                summary.last_seen_slot = slot;
+                summary.recent_transactions.insert(0, data.tx_hash.clone());
+                summary.recent_transactions.truncate(SCRIPT_RECENT_TXS);
+
+                for redeemer in data.redeemers.iter().filter(|r| r.script_hash.as_deref() == Some(hash)) {
+                    summary.redeemers += 1;
+                    summary.ex_units_mem += redeemer.ex_units_mem;
+                    summary.ex_units_steps += redeemer.ex_units_steps;
+                }
+            }
+
+            // Forget the scripts seen longest ago once full
+            if scripts.len() > MAX_SCRIPTS {
+                let mut by_slot: Vec<(u64, String)> = scripts.values().map(|s| (s.last_seen_slot, s.hash.clone())).collect();
+                by_slot.sort();
+                for (_, hash) in by_slot.into_iter().take(scripts.len() - MAX_SCRIPTS) {
+                    scripts.remove(&hash);
+                }
+            }
+        }
+
+        let mut script_data = self.script_data.write().await;
+        script_data.insert(data.tx_hash.clone(), data);
+    }
+
+    /// Takes the usage of rolled back transactions, with their validity, out of the script summaries
+    async fn unrecord_script_data(&self, removed: &[(TxScriptData, bool)]) {
+        let mut scripts = self.scripts.write().await;
+        for (data, is_valid) in removed {
+            for hash in involved_scripts(data) {
+                let Some(summary) = scripts.get_mut(hash) else {
+                    continue;
+                };
+                summary.transactions = summary.transactions.saturating_sub(1);
+                if !is_valid {
+                    summary.failed = summary.failed.saturating_sub(1);
+                }
+                summary.recent_transactions.retain(|tx| *tx != data.tx_hash);
+                for redeemer in data.redeemers.iter().filter(|r| r.script_hash.as_deref() == Some(hash)) {
+                    summary.redeemers = summary.redeemers.saturating_sub(1);
+                    summary.ex_units_mem = summary.ex_units_mem.saturating_sub(redeemer.ex_units_mem);
+                    summary.ex_units_steps = summary.ex_units_steps.saturating_sub(redeemer.ex_units_steps);
+                }
+                if summary.transactions == 0 {
+                    scripts.remove(hash);
+                }
+            }
+        }
+    }
+
+    /// Script credentials of the outputs a transaction spends and of its certificates, as far as
+    /// the transactions that made the outputs and the certificates are still in memory
+    pub async fn known_credentials(&self, tx_obj: &serde_json::Value, tx_hash: &str) -> plutus::KnownCredentials {
+        let mut known = plutus::KnownCredentials::default();
+        {
+            let script_data = self.script_data.read().await;
+            for input in tx_obj.get("inputs").and_then(|i| i.as_array()).into_iter().flatten() {
+                let (Some(spent_tx), Some(index)) = (input.get("tx_id").and_then(|v| v.as_str()), input.get("index").and_then(|v| v.as_u64()))
+                else {
+                    continue;
+                };
+                // outputs locked at a script address carry a datum, or a hash of one, before Plutus V3
+                let script = script_data
+                    .get(spent_tx)
+                    .and_then(|data| data.datums.iter().find(|d| d.output_index == Some(index as u32)))
+                    .and_then(|datum| datum.script_hash.clone());
+                if let Some(script) = script {
+                    known.spent.insert((spent_tx.to_string(), index), script);
+                }
+            }
+        }
+        let certificates = self.certificates.read().await;
+        for cert in certificates.iter().filter(|c| c.tx_hash == tx_hash) {
+            if let Some(script) = certs::script_credential(cert) {
+                known.certs.insert(cert.cert_index, script);
+            }
+        }
+        known
+    }
+
+    pub async fn get_script_data(&self, tx_hash: &str) -> Option<TxScriptData> {
+        let script_data = self.script_data.read().await;
+        script_data.get(tx_hash).cloned()
+    }
+
+    pub async fn get_script(&self, hash: &str) -> Option<ScriptSummary> {
+        let scripts = self.scripts.read().await;
+        scripts.get(hash).cloned()
+    }
+
+    /// Skips certificates already stored, block cbor and oura certificate events overlap
+    pub async fn add_certificates(&self, new: Vec<Certificate>) {
+        let mut certificates = self.certificates.write().await;
+        for cert in new {
+            if certificates.iter().any(|c| c.tx_hash == cert.tx_hash && c.cert_index == cert.cert_index) {
+                continue;
+            }
+            certificates.insert(0, cert);
+        }
+        certificates.truncate(MAX_CERTIFICATES);
+    }
+
+    pub async fn add_withdrawals(&self, new: Vec<Withdrawal>) {
+        let mut withdrawals = self.withdrawals.write().await;
+        for withdrawal in new {
+            if withdrawals.iter().any(|w| w.tx_hash == withdrawal.tx_hash && w.stake_address == withdrawal.stake_address) {
+                continue;
+            }
+            withdrawals.insert(0, withdrawal);
+        }
+        withdrawals.truncate(MAX_WITHDRAWALS);
+    }
+
+    pub async fn get_stake_certificates(&self, stake_address: &str) -> Vec<Certificate> {
+        let certificates = self.certificates.read().await;
+        certificates
+            .iter()
+            .filter(|c| c.stake_address.as_deref() == Some(stake_address))
+            .cloned()
+            .collect()
+    }
+
+    pub async fn get_withdrawals(&self, stake_address: &str) -> Vec<Withdrawal> {
+        let withdrawals = self.withdrawals.read().await;
+        withdrawals.iter().filter(|w| w.stake_address == stake_address).cloned().collect()
+    }
+
+    pub async fn add_proposals(&self, new: Vec<Proposal>) {
+        let mut proposals = self.proposals.write().await;
+        for proposal in new {
+            if !proposals.iter().any(|p| p.id == proposal.id) {
+                proposals.insert(0, proposal);
+            }
+        }
+        proposals.truncate(MAX_PROPOSALS);
+    }
+
+    pub async fn add_votes(&self, new: Vec<Vote>) {
+        let mut votes = self.votes.write().await;
+        for vote in new {
+            if votes.iter().any(|v| v.tx_hash == vote.tx_hash && v.voter == vote.voter && v.proposal_id == vote.proposal_id) {
+                continue;
+            }
+            votes.insert(0, vote);
         }
+        votes.truncate(MAX_VOTES);
+    }
+
+    pub async fn get_proposals(&self, action: Option<&str>, limit: usize) -> Vec<Proposal> {
+        let proposals = self.proposals.read().await;
+        proposals
+            .iter()
+            .filter(|p| action.is_none_or(|a| p.action == a))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub async fn get_proposal(&self, id: &str) -> Option<Proposal> {
+        let proposals = self.proposals.read().await;
+        proposals.iter().find(|p| p.id == id).cloned()
+    }
+
+    pub async fn get_proposal_votes(&self, proposal_id: &str) -> Vec<Vote> {
+        let votes = self.votes.read().await;
+        votes.iter().filter(|v| v.proposal_id == proposal_id).cloned().collect()
+    }
+
+    pub async fn get_voter_votes(&self, voter: &str) -> Vec<Vote> {
+        let votes = self.votes.read().await;
+        votes.iter().filter(|v| v.voter == voter).cloned().collect()
+    }
+
+    /// DRep registration, update and retirement certificates
+    pub async fn get_drep_certificates(&self, drep: &str) -> Vec<Certificate> {
+        let certificates = self.certificates.read().await;
+        certificates
+            .iter()
+            .filter(|c| c.kind.starts_with("drep_") && c.drep.as_deref() == Some(drep))
+            .cloned()
+            .collect()
+    }
+
+    /// Stake addresses whose latest vote delegation is to this drep
+    pub async fn get_drep_delegators(&self, drep: &str) -> Vec<String> {
+        let certificates = self.certificates.read().await;
+        let mut seen = HashSet::new();
+        certificates
+            .iter()
+            .filter(|c| !c.kind.starts_with("drep_"))
+            .filter_map(|c| Some((c.stake_address.as_deref()?, c.drep.as_deref()?)))
+            .filter(|(stake, _)| seen.insert(*stake))
+            .filter(|(_, target)| *target == drep)
+            .map(|(stake, _)| stake.to_string())
+            .collect()
+    }
+
+    /// Registrations, updates and retirements of a pool
+    pub async fn get_pool_certificates(&self, pool_id: &str) -> Vec<Certificate> {
+        let certificates = self.certificates.read().await;
+        certificates
+            .iter()
+            .filter(|c| c.pool_id.as_deref() == Some(pool_id))
+            .filter(|c| c.kind == "pool_registration" || c.kind == "pool_retirement")
+            .cloned()
+            .collect()
+    }
+
+    pub async fn set_source(&self, status: &'static str, detail: Option<String>) {
+        let mut source = self.source.write().await;
+        source.status = status;
+        source.since = chrono::Utc::now().timestamp() as u64;
+        source.detail = detail;
+    }
+
+    /// Height of the newest block, confirmations are counted against it
+    pub async fn tip(&self) -> u64 {
+        let blocks = self.blocks.read().await;
+        blocks.first().map(|b| b.number).unwrap_or(0)
     }
 
     pub async fn get_blocks(&self, limit: usize) -> Vec<Block> {
         let blocks = self.blocks.read().await;
-        blocks.iter().take(limit).cloned().collect()
+        let tip = blocks.first().map(|b| b.number).unwrap_or(0);
+        blocks.iter().take(limit).map(|b| block_confirmations(b, tip)).collect()
     }
 
     pub async fn get_transactions(&self, limit: usize) -> Vec<Transaction> {
+        let tip = self.tip().await;
         let transactions = self.transactions.read().await;
-        transactions.iter().take(limit).cloned().collect()
+        transactions.iter().take(limit).map(|t| tx_confirmations(t, tip)).collect()
+    }
+
+    pub async fn get_block_by_hash(&self, hash: &str) -> Option<Block> {
+        let blocks = self.blocks.read().await;
+        let tip = blocks.first().map(|b| b.number).unwrap_or(0);
+        blocks.iter().find(|b| b.hash == hash).map(|b| block_confirmations(b, tip))
+    }
+
+    pub async fn get_block_by_number(&self, number: u64) -> Option<Block> {
+        let blocks = self.blocks.read().await;
+        let tip = blocks.first().map(|b| b.number).unwrap_or(0);
+        blocks.iter().find(|b| b.number == number).map(|b| block_confirmations(b, tip))
+    }
+
+    pub async fn get_block_by_slot(&self, slot: u64) -> Option<Block> {
+        let blocks = self.blocks.read().await;
+        let tip = blocks.first().map(|b| b.number).unwrap_or(0);
+        blocks.iter().find(|b| b.slot == slot).map(|b| block_confirmations(b, tip))
+    }
+
+    pub async fn get_transaction(&self, hash: &str) -> Option<Transaction> {
+        let tip = self.tip().await;
+        let transactions = self.transactions.read().await;
+        transactions.iter().find(|t| t.hash == hash).map(|t| tx_confirmations(t, tip))
+    }
+
+    pub async fn get_block_transactions(&self, number: u64) -> Vec<Transaction> {
+        let tip = self.tip().await;
+        let transactions = self.transactions.read().await;
+        transactions.iter().filter(|t| t.block_number == number).map(|t| tx_confirmations(t, tip)).collect()
+    }
+
+    /// Undo of a single block, its transactions are marked rolled back
+    pub async fn rollback_block(&self, hash: &str) -> Vec<Block> {
+        self.rollback_blocks(|b| b.hash == hash).await
+    }
+
+    /// Reset to a point: every block after the slot is rolled back
+    pub async fn rollback_to_slot(&self, slot: u64) -> Vec<Block> {
+        self.rollback_blocks(|b| b.slot > slot).await
+    }
+
+    async fn rollback_blocks(&self, remove: impl Fn(&Block) -> bool) -> Vec<Block> {
+        let removed: Vec<Block> = {
+            let mut blocks = self.blocks.write().await;
+            let (removed, kept) = blocks.drain(..).partition(|b| remove(b));
+            *blocks = kept;
+            removed
+        };
+
+        let numbers: Vec<u64> = removed.iter().map(|b| b.number).collect();
+        let mut transactions = self.transactions.write().await;
+        let mut rolled_back = self.rolled_back.write().await;
+        let mut script_data = self.script_data.write().await;
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut removed_txs = Vec::new();
+        let mut removed_hashes = Vec::new();
+        let mut removed_script_data = Vec::new();
+        transactions.retain(|tx| {
+            let Some(block) = removed.iter().find(|b| b.number == tx.block_number) else {
+                return true;
+            };
+            removed_txs.push((block.epoch, tx.fee, tx.total_output));
+            removed_hashes.push(tx.hash.clone());
+            if let Some(data) = script_data.remove(&tx.hash) {
+                removed_script_data.push((data, tx.is_valid));
+            }
+            rolled_back.insert(
+                tx.hash.clone(),
+                RolledBackTx { hash: tx.hash.clone(), block_number: tx.block_number, rolled_back_at: now },
+            );
+            false
+        });
+        prune_rolled_back(&mut rolled_back);
+        self.invalid_txs.write().await.retain(|_, block_number| !numbers.contains(block_number));
+        // asset metadata carries the slot of its block, its transaction may have left the list already
+        let slots: Vec<u64> = removed.iter().map(|b| b.slot).collect();
+        self.unrecord_assets(|a| slots.contains(&a.slot) || removed_hashes.contains(&a.tx_hash)).await;
+        self.unrecord_script_data(&removed_script_data).await;
+        self.metadata
+            .write()
+            .await
+            .retain(|m| !numbers.contains(&m.block_number) && !removed_hashes.contains(&m.tx_hash));
+        self.certificates.write().await.retain(|c| !numbers.contains(&c.block_number));
+        self.withdrawals.write().await.retain(|w| !numbers.contains(&w.block_number));
+        self.proposals.write().await.retain(|p| !numbers.contains(&p.block_number));
+        self.votes.write().await.retain(|v| !numbers.contains(&v.block_number));
+        self.unrecord_pool_blocks(&removed).await;
+        self.unrecord_epoch_blocks(&removed, &removed_txs).await;
+        self.stats.write().await.remove_blocks(&numbers);
+        removed
+    }
+
+    /// Undo of a single transaction record
+    pub async fn rollback_transaction(&self, hash: &str) {
+        self.metadata.write().await.retain(|m| m.tx_hash != hash);
+        self.unrecord_assets(|a| a.tx_hash == hash).await;
+        let mut transactions = self.transactions.write().await;
+        let Some(index) = transactions.iter().position(|t| t.hash == hash) else {
+            return;
+        };
+        let tx = transactions.remove(index);
+        if let Some(data) = self.script_data.write().await.remove(&tx.hash) {
+            self.unrecord_script_data(&[(data, tx.is_valid)]).await;
+        }
+        self.stats.write().await.remove_transaction(tx.block_number, tx.fee);
+        let epoch = self.blocks.read().await.iter().find(|b| b.number == tx.block_number).map(|b| b.epoch);
+        if let Some(epoch) = epoch {
+            self.unrecord_epoch_blocks(&[], &[(epoch, tx.fee, tx.total_output)]).await;
+        }
+
+        let mut rolled_back = self.rolled_back.write().await;
+        rolled_back.insert(
+            tx.hash.clone(),
+            RolledBackTx { hash: tx.hash, block_number: tx.block_number, rolled_back_at: chrono::Utc::now().timestamp() as u64 },
+        );
+        prune_rolled_back(&mut rolled_back);
+    }
+
+    pub async fn get_rolled_back(&self, hash: &str) -> Option<RolledBackTx> {
+        let rolled_back = self.rolled_back.read().await;
+        rolled_back.get(hash).cloned()
+    }
+
+    pub async fn get_pending(&self, hash: &str) -> Option<PendingTx> {
+        let pending = self.pending.read().await;
+        pending.get(hash).cloned()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Sequenced> {
+        self.events.subscribe()
+    }
+
+    pub fn publish(&self, event: ChainEvent) {
+        // numbered and sent under the lock so subscribers see ids in order
+        let mut log = self.event_log.lock().unwrap_or_else(|e| e.into_inner());
+        let event = log.record(event);
+        // Err only means nobody is listening right now
+        let _ = self.events.send(event);
+    }
+
+    /// Receiver of future events with the id of the last event published before it
+    pub fn subscribe_after(&self) -> (broadcast::Receiver<Sequenced>, u64) {
+        let log = self.event_log.lock().unwrap_or_else(|e| e.into_inner());
+        (self.events.subscribe(), log.last_id())
+    }
+
+    pub fn latest_event_id(&self) -> u64 {
+        let log = self.event_log.lock().unwrap_or_else(|e| e.into_inner());
+        log.last_id()
+    }
+
+    /// Published events after `last_id`, None when they can not all be replayed
+    pub fn events_since(&self, last_id: u64) -> Option<Vec<Sequenced>> {
+        let log = self.event_log.lock().unwrap_or_else(|e| e.into_inner());
+        log.since(last_id)
+    }
+
+    /// Adds the block to its epoch summary, true when it opens a newer epoch than seen so far
+    pub async fn record_epoch_block(&self, block: &Block) -> bool {
+        let mut epochs = self.epochs.write().await;
+        let new_epoch = epochs.keys().next_back().is_some_and(|latest| block.epoch > *latest);
+
+        let summary = epochs.entry(block.epoch).or_insert_with(|| {
+            let first_slot = NETWORK_TIME.epoch_first_slot(block.epoch);
+            EpochSummary::new(
+                block.epoch,
+                NETWORK_TIME.slot_to_time(first_slot),
+                NETWORK_TIME.slot_to_time(first_slot.saturating_add(NETWORK_TIME.epoch_length(block.epoch))),
+            )
+        });
+        summary.blocks += 1;
+        if summary.first_block.is_none() {
+            summary.first_block = Some(block.hash.clone());
+            summary.first_block_time = Some(block.timestamp);
+        }
+        summary.last_block = Some(block.hash.clone());
+        summary.last_block_time = Some(block.timestamp);
+        if let Some(pool_id) = &block.pool_id {
+            summary.pools.insert(pool_id.clone());
+            summary.active_pools = summary.pools.len();
+        }
+
+        if epochs.len() > MAX_EPOCHS {
+            epochs.pop_first();
+        }
+        new_epoch
+    }
+
+    pub async fn record_epoch_transaction(&self, epoch: u64, fee: u64, total_output: u64) {
+        let mut epochs = self.epochs.write().await;
+        if let Some(summary) = epochs.get_mut(&epoch) {
+            summary.transactions += 1;
+            summary.fees += fee;
+            summary.output_volume += total_output;
+        }
+    }
+
+    /// Takes rolled back blocks and their `(epoch, fee, total_output)` transactions out of the
+    /// summaries, run after the blocks left the block list and the pool counts were reverted
+    async fn unrecord_epoch_blocks(&self, removed: &[Block], removed_txs: &[(u64, u64, u64)]) {
+        let blocks = self.blocks.read().await;
+        let pool_blocks = self.pool_blocks.read().await;
+        let mut epochs = self.epochs.write().await;
+        for (epoch, fee, total_output) in removed_txs {
+            if let Some(summary) = epochs.get_mut(epoch) {
+                summary.transactions = summary.transactions.saturating_sub(1);
+                summary.fees = summary.fees.saturating_sub(*fee);
+                summary.output_volume = summary.output_volume.saturating_sub(*total_output);
+            }
+        }
+        for block in removed {
+            let Some(summary) = epochs.get_mut(&block.epoch) else {
+                continue;
+            };
+            summary.blocks = summary.blocks.saturating_sub(1);
+            if summary.blocks == 0 {
+                epochs.remove(&block.epoch);
+                continue;
+            }
+            // replaced from the blocks still held, older ones may have left the bounded list
+            if summary.first_block.as_ref() == Some(&block.hash) {
+                let first = blocks.iter().filter(|b| b.epoch == block.epoch).min_by_key(|b| b.number);
+                summary.first_block = first.map(|b| b.hash.clone());
+                summary.first_block_time = first.map(|b| b.timestamp);
+            }
+            if summary.last_block.as_ref() == Some(&block.hash) {
+                let last = blocks.iter().filter(|b| b.epoch == block.epoch).max_by_key(|b| b.number);
+                summary.last_block = last.map(|b| b.hash.clone());
+                summary.last_block_time = last.map(|b| b.timestamp);
+            }
+            summary.pools.retain(|pool| pool_blocks.get(pool).is_some_and(|counts| counts.contains_key(&block.epoch)));
+            summary.active_pools = summary.pools.len();
+        }
+    }
+
+    pub async fn get_epochs(&self, limit: usize) -> Vec<EpochSummary> {
+        let epochs = self.epochs.read().await;
+        epochs.values().rev().take(limit).cloned().collect()
+    }
+
+    pub async fn get_epoch(&self, epoch: u64) -> Option<EpochSummary> {
+        let epochs = self.epochs.read().await;
+        epochs.get(&epoch).cloned()
+    }
+
+    pub async fn record_block_stats(&self, block: &Block) {
+        let mut stats = self.stats.write().await;
+        stats.record_block(block.number, block.timestamp, block.size, block.tx_count);
+    }
+
+    pub async fn record_tx_stats(&self, block_number: u64, timestamp: u64, fee: u64) {
+        let mut stats = self.stats.write().await;
+        stats.record_transaction(block_number, timestamp, fee);
+    }
+
+    pub async fn get_stats(&self) -> StatsSnapshot {
+        let stats = self.stats.read().await;
+        stats.snapshot(*MAX_BLOCK_BODY_SIZE)
+    }
+
+    pub async fn record_pool_block(&self, pool_id: String, epoch: u64) {
+        let mut pool_blocks = self.pool_blocks.write().await;
+        *pool_blocks.entry(pool_id).or_default().entry(epoch).or_insert(0) += 1;
+    }
+
+    // Rolled back blocks no longer count for their producer, pools left without blocks are forgotten
+    async fn unrecord_pool_blocks(&self, removed: &[Block]) {
+        let mut pool_blocks = self.pool_blocks.write().await;
+        for block in removed {
+            let Some(pool_id) = &block.pool_id else {
+                continue;
+            };
+            let Some(epochs) = pool_blocks.get_mut(pool_id) else {
+                continue;
+            };
+            if let Some(count) = epochs.get_mut(&block.epoch) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    epochs.remove(&block.epoch);
+                }
+            }
+            if epochs.is_empty() {
+                pool_blocks.remove(pool_id);
+            }
+        }
+    }
+
+    pub async fn get_pool_blocks(&self, pool_id: &str, limit: usize) -> Vec<Block> {
+        let blocks = self.blocks.read().await;
+        let tip = blocks.first().map(|b| b.number).unwrap_or(0);
+        blocks
+            .iter()
+            .filter(|b| b.pool_id.as_deref() == Some(pool_id))
+            .take(limit)
+            .map(|b| block_confirmations(b, tip))
+            .collect()
+    }
+
+    pub async fn get_pool_epoch_counts(&self, pool_id: &str) -> BTreeMap<u64, u64> {
+        let pool_blocks = self.pool_blocks.read().await;
+        pool_blocks.get(pool_id).cloned().unwrap_or_default()
+    }
+
+    pub async fn has_pool(&self, pool_id: &str) -> bool {
+        let pool_blocks = self.pool_blocks.read().await;
+        pool_blocks.contains_key(pool_id)
+    }
+
+    /// Blocks produced per pool in one epoch, most productive first
+    pub async fn get_epoch_pool_counts(&self, epoch: u64) -> Vec<(String, u64)> {
+        let pool_blocks = self.pool_blocks.read().await;
+        let mut counts: Vec<(String, u64)> = pool_blocks
+            .iter()
+            .filter_map(|(pool, epochs)| Some((pool.clone(), *epochs.get(&epoch)?)))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    pub async fn add_pending(&self, tx: PendingTx) {
+        let mut pending = self.pending.write().await;
+        let cutoff = tx.submitted_at.saturating_sub(PENDING_TIMEOUT);
+        pending.retain(|_, p| p.submitted_at >= cutoff);
+        pending.insert(tx.hash.clone(), tx);
+    }
+
+    /// Removes a submitted transaction once it shows up in a block
+    pub async fn take_pending(&self, hash: &str) -> Option<PendingTx> {
+        let mut pending = self.pending.write().await;
+        pending.remove(hash)
+    }
+
+    /// Stores the metadata, keeping the version it replaces in case its transaction rolls back
+    pub async fn add_asset(&self, asset: AssetMetadata) {
+        let mut assets = self.assets.write().await;
+        let mut versions = self.asset_versions.write().await;
+        if let Some(replaced) = assets.insert(asset.unit.clone(), asset) {
+            // the same transaction applied again replaces nothing
+            if assets.get(&replaced.unit).is_some_and(|a| a.tx_hash != replaced.tx_hash) {
+                let previous = versions.entry(replaced.unit.clone()).or_default();
+                previous.push(replaced);
+                if previous.len() > ASSET_VERSIONS {
+                    previous.remove(0);
+                }
+            }
+        }
+
+        // Drop the oldest metadata once full
+        if assets.len() > MAX_ASSETS {
+            if let Some(oldest) = assets
+                .values()
+                .min_by_key(|a| a.slot)
+                .map(|a| a.unit.clone())
+            {
+                assets.remove(&oldest);
+                versions.remove(&oldest);
+            }
+        }
+    }
+
+    // Removes token metadata set by rolled back transactions, restoring the version it replaced
+    async fn unrecord_assets(&self, rolled_back: impl Fn(&AssetMetadata) -> bool) {
+        let mut assets = self.assets.write().await;
+        let mut versions = self.asset_versions.write().await;
+        let units: Vec<String> = assets.values().filter(|a| rolled_back(a)).map(|a| a.unit.clone()).collect();
+        for unit in units {
+            let previous = versions.remove(&unit).unwrap_or_default();
+            let mut kept: Vec<AssetMetadata> = previous.into_iter().filter(|a| !rolled_back(a)).collect();
+            match kept.pop() {
+                Some(restored) => {
+                    assets.insert(unit.clone(), restored);
+                    if !kept.is_empty() {
+                        versions.insert(unit, kept);
+                    }
+                }
+                None => {
+                    assets.remove(&unit);
+                }
+            }
+        }
+    }
+
+    /// Look up by unit or fingerprint. CIP-68 user tokens resolve to their reference datum
+    pub async fn get_asset(&self, id: &str) -> Option<AssetMetadata> {
+        let id = id.to_lowercase();
+        let assets = self.assets.read().await;
+
+        if id.starts_with("asset1") {
+            return assets.values().find(|a| a.fingerprint == id).cloned();
+        }
+        if let Some(asset) = assets.get(&id) {
+            return Some(asset.clone());
+        }
+        let reference = cip::reference_unit(&id)?;
+        assets.get(&reference).map(|asset| {
+            let mut asset = asset.clone();
+            asset.unit = id.clone();
+            asset.asset_name = id[56..].to_string();
+            asset.label = cip::cip67_label(&asset.asset_name);
+            asset.fingerprint = cip::fingerprint(&asset.policy_id, &asset.asset_name).unwrap_or_default();
+            asset
+        })
+    }
+
+    pub async fn get_assets(&self, limit: usize) -> Vec<AssetMetadata> {
+        let assets = self.assets.read().await;
+        let mut recent: Vec<AssetMetadata> = assets.values().cloned().collect();
+        recent.sort_by_key(|a| std::cmp::Reverse(a.slot));
+        recent.truncate(limit);
+        recent
+    }
+
+    /// Stores the entries and publishes each one on the metadata topic
+    pub async fn add_metadata(&self, entries: Vec<TxMetadata>) {
+        {
+            let mut metadata = self.metadata.write().await;
+            for entry in &entries {
+                metadata.insert(0, entry.clone());
+            }
+
+            if metadata.len() > MAX_METADATA {
+                metadata.truncate(MAX_METADATA);
+            }
+        }
+        for entry in entries {
+            self.publish(ChainEvent::Metadata(entry));
+        }
+    }
+
+    pub async fn has_tx_metadata(&self, tx_hash: &str) -> bool {
+        let metadata = self.metadata.read().await;
+        metadata.iter().any(|m| m.tx_hash == tx_hash)
+    }
+
+    pub async fn get_metadata_by_label(&self, label: u64, limit: usize) -> Vec<TxMetadata> {
+        let metadata = self.metadata.read().await;
+        metadata.iter().filter(|m| m.label == label).take(limit).cloned().collect()
+    }
+
+    pub async fn get_tx_metadata(&self, tx_hash: &str) -> Vec<TxMetadata> {
+        let metadata = self.metadata.read().await;
+        let mut entries: Vec<TxMetadata> = metadata.iter().filter(|m| m.tx_hash == tx_hash).cloned().collect();
+        entries.sort_by_key(|m| m.label);
+        entries
+    }
+
+    pub async fn get_policy_assets(&self, policy_id: &str) -> Vec<AssetMetadata> {
+        let policy_id = policy_id.to_lowercase();
+        let assets = self.assets.read().await;
+        let mut found: Vec<AssetMetadata> = assets
+            .values()
+            .filter(|a| a.policy_id == policy_id)
+            .cloned()
+            .collect();
+        found.sort_by(|a, b| a.asset_name.cmp(&b.asset_name));
+        found
+    }
+}
+
+fn block_confirmations(block: &Block, tip: u64) -> Block {
+    let mut block = block.clone();
+    block.confirmations = tip.saturating_sub(block.number);
+    block
+}
+
+fn tx_confirmations(tx: &Transaction, tip: u64) -> Transaction {
+    let mut tx = tx.clone();
+    tx.confirmations = tip.saturating_sub(tx.block_number);
+    tx
+}
+
+// Forget the oldest rollbacks once full
+// Scripts a transaction counts against: its witnesses and the scripts its redeemers run
+fn involved_scripts(data: &TxScriptData) -> Vec<&str> {
+    let mut involved: Vec<&str> = data.scripts.iter().map(|s| s.hash.as_str()).collect();
+    involved.extend(data.redeemers.iter().filter_map(|r| r.script_hash.as_deref()));
+    involved.sort();
+    involved.dedup();
+    involved
+}
+
+fn prune_rolled_back(rolled_back: &mut HashMap<String, RolledBackTx>) {
+    while rolled_back.len() > MAX_ROLLED_BACK {
+        let oldest = rolled_back.values().min_by_key(|r| r.rolled_back_at).map(|r| r.hash.clone());
+        match oldest {
+            Some(hash) => rolled_back.remove(&hash),
+            None => break,
+        };
+    }
+}
+
+/// Oura daemon config: node-to-node from the relay, legacy v1 records with transaction details
+/// and block cbor as JSON lines on stdout, starting at `since` or the tip. Block cbor is the only
+/// source of Conway certificates, governance procedures, phase-2 validity and raw metadata
+pub fn oura_config(since: Option<&ChainPoint>) -> String {
+    let intersect = match since {
+        Some(point) => format!("type = \"Point\"\nvalue = [{}, \"{}\"]", point.slot, point.hash),
+        None => "type = \"Tip\"".to_string(),
+    };
+    format!(
+        r#"[source]
+type = "N2N"
+peers = ["{relay}"]
+
+[chain]
+type = "{chain}"
+
+[intersect]
+{intersect}
+
+[[filters]]
+type = "LegacyV1"
+include_transaction_details = true
+include_block_cbor = true
+
+[sink]
+type = "Stdout"
+"#,
+        relay = NETWORK.relay,
+        chain = NETWORK.oura_chain,
+        intersect = intersect,
+    )
+}
+
+// Config path for the daemon, the generated one is rewritten on every start
+fn oura_config_path(since: Option<&ChainPoint>) -> Result<std::path::PathBuf, String> {
+    if let Ok(path) = std::env::var("OURA_CONFIG") {
+        return Ok(path.into());
     }
+    let dir = snapshot::STATE_DIR.as_path();
+    let path = dir.join(OURA_CONFIG_FILE);
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    std::fs::write(&path, oura_config(since)).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
 }
 
 // Spawn oura as subprocess and parse stdout
-pub async fn start_oura(state: Arc<BlockChainState>) {
+pub async fn start_oura(state: Arc<BlockChainState>, since: Option<ChainPoint>) {
     use std::io::{BufRead, BufReader};
     use std::process::{Command, Stdio};
 
     tokio::spawn(async move {
-        log::info!("Starting oura dump stream");
-
-        let mut child = Command::new("oura")
-            .arg("dump")
-            .arg(CARDANO_RELAY)
-            .arg("--bearer")
-            .arg("tcp")
-            .arg("--magic")
-            .arg(CARDANO_MAGIC)
+        let config = match oura_config_path(since.as_ref()) {
+            Ok(path) => path,
+            Err(e) => {
+                tracing::error!("Failed to start Oura: {}", e);
+                state.set_source("failed", Some(e)).await;
+                return;
+            }
+        };
+        let mut command = Command::new("oura");
+        command.arg("daemon").arg("--config").arg(&config);
+        // resume from the block saved at the last shutdown, otherwise oura starts at the tip
+        match &since {
+            Some(point) => tracing::info!(
+                "Starting oura daemon from #{} at slot {} ({})",
+                point.block_number,
+                point.slot,
+                config.display()
+            ),
+            None => tracing::info!("Starting oura daemon ({})", config.display()),
+        }
+
+        let spawned = command
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .spawn()
-            .expect("Failed to start Oura");
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                tracing::error!("Failed to start Oura: {}", e);
+                state.set_source("failed", Some(format!("Failed to start oura: {}", e))).await;
+                return;
+            }
+        };
+        shutdown::set_source_pid(child.id());
+        state.set_source("running", None).await;
 
         let stdout = child.stdout.take().expect("Failed to capture stdout");
         let stderr = child.stderr.take().expect("Failed to capture stderr");
@@ -77,10 +963,8 @@ pub async fn start_oura(state: Arc<BlockChainState>) {
         // Log stderr in separate task
         tokio::task::spawn_blocking(move || {
             let reader = BufReader::new(stderr);
-            for line in reader.lines() {
-                if let Ok(line_str) = line {
-                    log::error!("OURA STDERR: {}", line_str);
-                }
+            for line_str in reader.lines().map_while(Result::ok) {
+                tracing::error!("OURA STDERR: {}", line_str);
             }
         });
 
@@ -90,26 +974,138 @@ pub async fn start_oura(state: Arc<BlockChainState>) {
             for line in reader.lines() {
                 match line {
                     Ok(line_str) => {
-                        log::debug!("OURA: {}", &line_str[..line_str.len().min(100)]);
-                        if let Ok(event) = serde_json::from_str::<serde_json::Value>(&line_str) {
-                            let state_clone = state.clone();
-                            tokio::runtime::Handle::current().block_on(async move {
-                                process_event(event, state_clone).await;
-                            });
+                        tracing::debug!("OURA: {}", &line_str[..line_str.len().min(100)]);
+                        match serde_json::from_str::<serde_json::Value>(&line_str) {
+                            Ok(event) => {
+                                let state_clone = state.clone();
+                                let span = ingest_span(&event);
+                                tokio::runtime::Handle::current().block_on(
+                                    async move {
+                                        state_clone.source.write().await.last_event_at = Some(chrono::Utc::now().timestamp() as u64);
+                                        process_event(event, state_clone).await;
+                                    }
+                                    .instrument(span),
+                                );
+                            }
+                            Err(_) => METRICS.events_failed.with_label_values(&["invalid_json"]).inc(),
                         }
                     }
-                    Err(e) => log::error!("Error reading Oura output: {}", e),
+                    Err(e) => tracing::error!("Error reading Oura output: {}", e),
                 }
             }
-            log::warn!("Oura stream ended");
+            let detail = match child.wait() {
+                Ok(status) if shutdown::is_requested() => {
+                    tracing::info!("Oura stopped for shutdown ({})", status);
+                    format!("stopped for shutdown, {}", status)
+                }
+                Ok(status) => {
+                    tracing::warn!("Oura stream ended, oura exited with {}", status);
+                    format!("oura exited with {}", status)
+                }
+                Err(e) => {
+                    tracing::error!("Failed to wait for Oura: {}", e);
+                    format!("failed to wait for oura: {}", e)
+                }
+            };
+            shutdown::clear_source_pid();
+            tokio::runtime::Handle::current().block_on(state.set_source("stopped", Some(detail)));
         });
     });
 }
 
+// Undo carries the record being rolled back, reset only the point to roll back to
+async fn process_rollback(event: &serde_json::Value, state: &BlockChainState) {
+    let removed = if event.get("event").and_then(|v| v.as_str()) == Some("reset") {
+        // "origin" points have no slot, nothing followed is older than that
+        let Some(slot) = event.get("point").and_then(|p| p.get("slot")).and_then(|v| v.as_u64()) else {
+            return;
+        };
+        state.rollback_to_slot(slot).await
+    } else {
+        let Some(record) = event.get("record") else {
+            return;
+        };
+        if let Some(tx_hash) = record.get("transaction").and_then(|t| t.get("hash")).and_then(|v| v.as_str()) {
+            state.rollback_transaction(tx_hash).await;
+            return;
+        }
+        let Some(block) = record.get("block") else {
+            return;
+        };
+        let hash = block
+            .get("hash")
+            .or_else(|| event.get("point").and_then(|p| p.get("hash")))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        state.rollback_block(hash).await
+    };
+
+    for block in removed {
+        METRICS.rollbacks.inc();
+        tracing::warn!("Rolled back block #{} ({})", block.number, &block.hash[..16.min(block.hash.len())]);
+        state.publish(ChainEvent::Rollback {
+            block_number: block.number,
+            block_hash: block.hash,
+            slot: block.slot,
+        });
+    }
+}
+
+// Span of an oura event: one per block, transactions and other records of it only at debug level
+fn ingest_span(event: &serde_json::Value) -> tracing::Span {
+    let point = event.get("point");
+    let slot = point.and_then(|p| p.get("slot")).and_then(|v| v.as_u64());
+    let record = event.get("record");
+    match record.and_then(|r| r.get("block")) {
+        Some(block) => tracing::info_span!(
+            "ingest_block",
+            block.number = block.get("number").and_then(|v| v.as_u64()),
+            block.slot = slot,
+            block.hash = point.and_then(|p| p.get("hash")).and_then(|v| v.as_str()),
+            block.tx_count = block.get("tx_count").and_then(|v| v.as_u64()),
+        ),
+        None => tracing::debug_span!(
+            "ingest_event",
+            event.r#type = event_type(event),
+            block.number = record.and_then(|r| r.get("context")).and_then(|c| c.get("block_number")).and_then(|v| v.as_u64()),
+            block.slot = slot,
+        ),
+    }
+}
+
+// Metrics label of an oura event: rollback, or the record it applies (block, transaction, ...)
+fn event_type(event: &serde_json::Value) -> &str {
+    match event.get("event").and_then(|v| v.as_str()) {
+        Some("undo") | Some("reset") => "rollback",
+        Some("apply") => event
+            .get("record")
+            .and_then(|r| r.as_object())
+            .and_then(|r| r.keys().find(|k| *k != "context"))
+            .map(|k| k.as_str())
+            .unwrap_or("unknown"),
+        _ => "unknown",
+    }
+}
+
+// Bytes of the block record's cbor_hex, a malformed one is counted and skipped
+fn block_cbor(block_obj: &serde_json::Value, block_hash: &str) -> Option<Vec<u8>> {
+    let cbor_hex = block_obj.get("cbor_hex").and_then(|v| v.as_str())?;
+    match hex::decode(cbor_hex) {
+        Ok(cbor) => Some(cbor),
+        Err(e) => {
+            METRICS.events_failed.with_label_values(&["block"]).inc();
+            tracing::warn!("Invalid cbor hex in block {}: {}", block_hash, e);
+            None
+        }
+    }
+}
+
 async fn process_event(event: serde_json::Value, state: Arc<BlockChainState>) {
-    // Only process "apply" events
-    if event.get("event").and_then(|v| v.as_str()) != Some("apply") {
-        return;
+    METRICS.events_processed.with_label_values(&[event_type(&event)]).inc();
+    match event.get("event").and_then(|v| v.as_str()) {
+        Some("apply") => {}
+        Some("undo") | Some("reset") => return process_rollback(&event, &state).await,
+        _ => return,
     }
 
     let point = match event.get("point") {
@@ -142,15 +1138,16 @@ async fn process_event(event: serde_json::Value, state: Arc<BlockChainState>) {
         .and_then(|v| v.as_u64())
         .unwrap_or(slot);
     
+    // Era aware fallbacks from the network genesis parameters
     let epoch = context
         .and_then(|c| c.get("epoch"))
         .and_then(|v| v.as_u64())
-        .unwrap_or(slot / 432000);
+        .unwrap_or_else(|| NETWORK_TIME.slot_to_epoch(slot).0);
     
     let timestamp = context
         .and_then(|c| c.get("timestamp"))
         .and_then(|v| v.as_u64())
-        .unwrap_or(1654041600 + slot);
+        .unwrap_or_else(|| NETWORK_TIME.slot_to_time(slot));
 
     // Check if this is a Block event
     if let Some(block_obj) = record.get("block") {
@@ -174,7 +1171,7 @@ async fn process_event(event: serde_json::Value, state: Arc<BlockChainState>) {
             .and_then(|v| v.as_u64())
             .unwrap_or(epoch);
 
-        let block = Block::new(
+        let mut block = Block::new(
             block_hash.clone(),
             block_number,
             slot,
@@ -184,7 +1181,37 @@ async fn process_event(event: serde_json::Value, state: Arc<BlockChainState>) {
             body_size,
         );
 
-        log::info!(
+        // Block producer, byron blocks come with empty keys
+        let non_empty = |key: &str| {
+            block_obj
+                .get(key)
+                .and_then(|v| v.as_str())
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_string())
+        };
+        block.previous_hash = non_empty("previous_hash");
+        block.issuer_vkey = non_empty("issuer_vkey");
+        block.vrf_vkey = non_empty("vrf_vkey");
+        block.pool_id = block.issuer_vkey.as_deref().and_then(pools::pool_id_from_vkey);
+
+        // Raw block CBOR (include_block_cbor), decoded once for the header and every part below
+        let cbor = block_cbor(block_obj, &block_hash);
+        let decoded = cbor.as_deref().and_then(|cbor| match MultiEraBlock::decode(cbor) {
+            Ok(decoded) => Some(decoded),
+            Err(e) => {
+                METRICS.events_failed.with_label_values(&["block"]).inc();
+                tracing::warn!("Could not decode block {}: {}", block_hash, e);
+                None
+            }
+        });
+        if let Some(decoded) = &decoded {
+            let info = pools::header_info(decoded);
+            block.vrf_output = info.vrf_output;
+            block.protocol_version = info.protocol_version;
+            block.op_cert_counter = info.op_cert_counter;
+        }
+
+        tracing::info!(
             "New block: {} (number: {}, epoch: {}, slot: {}, txs: {}, size: {} bytes)",
             &block.hash[..16.min(block.hash.len())],
             block_number,
@@ -193,7 +1220,62 @@ async fn process_event(event: serde_json::Value, state: Arc<BlockChainState>) {
             tx_count,
             body_size
         );
+        if let Some(pool_id) = block.pool_id.clone() {
+            state.record_pool_block(pool_id, epoch).await;
+        }
+        if state.record_epoch_block(&block).await {
+            tracing::info!("Epoch {} started at slot {}", epoch, slot);
+            state.publish(ChainEvent::EpochBoundary {
+                epoch,
+                slot,
+                timestamp,
+                previous: match epoch.checked_sub(1) {
+                    Some(previous) => state.get_epoch(previous).await,
+                    None => None,
+                },
+            });
+        }
+        state.record_block_stats(&block).await;
+        state.publish(ChainEvent::Block(block.clone()));
+        state.publish(ChainEvent::Stats(state.get_stats().await));
         state.add_block(block).await;
+
+        if let Some(decoded) = &decoded {
+            // metadata from the cbor keeps its types and bytes intact
+            for (tx_hash, entries) in metadata::from_block(decoded) {
+                let records = entries
+                    .into_iter()
+                    .map(|(label, datum, cbor)| TxMetadata {
+                        tx_hash: tx_hash.clone(),
+                        block_number,
+                        slot,
+                        timestamp,
+                        label,
+                        cbor,
+                        json: metadata::to_detailed_json(&datum),
+                    })
+                    .collect();
+                state.add_metadata(records).await;
+            }
+            let invalid = plutus::invalid_txs(decoded);
+            if !invalid.is_empty() {
+                state.mark_invalid(invalid, block_number).await;
+            }
+            let at = certs::Inclusion { block_number, slot, timestamp };
+            let (certificates, withdrawals) = certs::from_block(decoded, at);
+            state.add_certificates(certificates).await;
+            state.add_withdrawals(withdrawals).await;
+            let (proposals, votes) = governance::from_block(decoded, at, epoch);
+            for proposal in &proposals {
+                tracing::info!("Governance proposal {} ({})", proposal.id, proposal.action);
+                state.publish(ChainEvent::Proposal(proposal.clone()));
+            }
+            for vote in &votes {
+                state.publish(ChainEvent::Vote(vote.clone()));
+            }
+            state.add_proposals(proposals).await;
+            state.add_votes(votes).await;
+        }
         return;
     }
 
@@ -225,7 +1307,7 @@ async fn process_event(event: serde_json::Value, state: Arc<BlockChainState>) {
             .and_then(|v| v.as_u64())
             .unwrap_or(0);
 
-        let tx = Transaction::new(
+        let mut tx = Transaction::new(
             tx_hash.clone(),
             block_number,
             timestamp,
@@ -234,8 +1316,11 @@ async fn process_event(event: serde_json::Value, state: Arc<BlockChainState>) {
             output_count,
             total_output,
         );
+        // oura v1 records carry no validity flag, block cbor does
+        let flagged_invalid = state.take_invalid(&tx_hash).await;
+        tx.is_valid = tx_obj.get("is_valid").and_then(|v| v.as_bool()).unwrap_or(true) && !flagged_invalid;
 
-        log::info!(
+        tracing::info!(
             "New transaction: {} (block: {}, fee: {}, in: {}, out: {})",
             &tx.hash[..16.min(tx.hash.len())],
             block_number,
@@ -243,6 +1328,757 @@ async fn process_event(event: serde_json::Value, state: Arc<BlockChainState>) {
             input_count,
             output_count
         );
+        process_tx_metadata(tx_obj, &tx_hash, block_number, slot, timestamp, &state).await;
+        process_token_metadata(tx_obj, &tx_hash, slot, &state).await;
+        let withdrawals = certs::withdrawals_from_record(tx_obj, &tx_hash, certs::Inclusion { block_number, slot, timestamp });
+        if !withdrawals.is_empty() {
+            state.add_withdrawals(withdrawals).await;
+        }
+        let known = state.known_credentials(tx_obj, &tx_hash).await;
+        let script_data = plutus::script_data_from_record(tx_obj, &tx_hash, &known);
+        if !script_data.is_empty() {
+            state.add_script_data(script_data, slot, tx.is_valid).await;
+        }
+        state.record_epoch_transaction(epoch, fee, total_output).await;
+        state.record_tx_stats(block_number, timestamp, fee).await;
+        state.publish(ChainEvent::Transaction(tx.clone()));
+        if let Some(pending) = state.take_pending(&tx_hash).await {
+            tracing::info!("Submitted transaction {} confirmed in block {}", tx_hash, block_number);
+            state.publish(ChainEvent::TxConfirmed {
+                hash: tx_hash.clone(),
+                block_number,
+                block_hash: context.and_then(|c| c.get("block_hash")).and_then(|v| v.as_str()).map(String::from),
+                slot,
+                submitted_at: pending.submitted_at,
+                confirmed_at: timestamp,
+            });
+        }
         state.add_transactions(tx).await;
+        return;
+    }
+
+    // Certificate events, pre-Conway only. Blocks with cbor already carried them
+    if certs::OURA_CERT_RECORDS.iter().any(|kind| record.get(*kind).is_some()) {
+        let tx_hash = context.and_then(|c| c.get("tx_hash")).and_then(|v| v.as_str()).unwrap_or("");
+        let cert_index = context
+            .and_then(|c| c.get("certificate_idx"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as u32;
+        let at = certs::Inclusion { block_number, slot, timestamp };
+        if let Some(cert) = certs::from_oura_record(record, tx_hash, cert_index, at) {
+            tracing::info!("Certificate {} in {}", cert.kind, tx_hash);
+            state.add_certificates(vec![cert]).await;
+        }
+        return;
+    }
+
+    // Standalone CIP-25 records (oura cip25 filter)
+    if let Some(asset_obj) = record.get("cip25_asset") {
+        let tx_hash = context
+            .and_then(|c| c.get("tx_hash"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let version = asset_obj
+            .get("version")
+            .and_then(|v| v.as_str())
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(|v| v as u64)
+            .unwrap_or(1);
+        let policy = asset_obj.get("policy").and_then(|v| v.as_str()).unwrap_or("");
+        let asset = asset_obj.get("asset").and_then(|v| v.as_str()).unwrap_or("");
+
+        if let Some(raw) = asset_obj.get("raw_json") {
+            if let Some(metadata) = cip::parse_cip25_asset(policy, asset, version, raw, tx_hash, slot) {
+                state.add_asset(metadata).await;
+            }
+        }
+    }
+}
+
+// Auxiliary data from the transaction record, unless the block CBOR already gave it to us.
+// Only its JSON is kept, the raw cbor comes from blocks
+async fn process_tx_metadata(
+    tx_obj: &serde_json::Value,
+    tx_hash: &str,
+    block_number: u64,
+    slot: u64,
+    timestamp: u64,
+    state: &Arc<BlockChainState>,
+) {
+    let entries = match tx_obj.get("metadata").and_then(|m| m.as_array()) {
+        Some(e) if !e.is_empty() => e,
+        _ => return,
+    };
+    if state.has_tx_metadata(tx_hash).await {
+        return;
+    }
+
+    let records: Vec<TxMetadata> = entries
+        .iter()
+        .filter_map(metadata::from_rendition)
+        .map(|(label, datum)| TxMetadata {
+            tx_hash: tx_hash.to_string(),
+            block_number,
+            slot,
+            timestamp,
+            label,
+            // the rendition lost bytes and key types, re-encoding it would not match the chain
+            cbor: None,
+            json: metadata::to_detailed_json(&datum),
+        })
+        .collect();
+
+    tracing::debug!("Stored {} metadata labels for {}", records.len(), tx_hash);
+    state.add_metadata(records).await;
+}
+
+// CIP-25 metadata (label 721) and CIP-68 reference datums carried by a transaction
+async fn process_token_metadata(
+    tx_obj: &serde_json::Value,
+    tx_hash: &str,
+    slot: u64,
+    state: &Arc<BlockChainState>,
+) {
+    if let Some(metadata) = tx_obj.get("metadata").and_then(|m| m.as_array()) {
+        for entry in metadata {
+            if entry.get("label").and_then(|l| l.as_str()) != Some(cip::CIP25_LABEL) {
+                continue;
+            }
+            if let Some(content) = entry.get("map_json") {
+                for asset in cip::parse_cip25(content, tx_hash, slot) {
+                    tracing::info!("CIP-25 metadata for {}", asset.unit);
+                    state.add_asset(asset).await;
+                }
+            }
+        }
+    }
+
+    let outputs = match tx_obj.get("outputs").and_then(|o| o.as_array()) {
+        Some(o) => o,
+        None => return,
+    };
+    let witness_datums = tx_obj.get("plutus_data").and_then(|d| d.as_array());
+    let reference_prefix = cip::cip67_prefix(cip::CIP68_REFERENCE_LABEL);
+
+    for output in outputs {
+        let assets = match output.get("assets").and_then(|a| a.as_array()) {
+            Some(a) => a,
+            None => continue,
+        };
+        for asset in assets {
+            let asset_name = asset.get("asset").and_then(|v| v.as_str()).unwrap_or("");
+            if !asset_name.starts_with(&reference_prefix) {
+                continue;
+            }
+            let policy = asset.get("policy").and_then(|v| v.as_str()).unwrap_or("");
+
+            // inline datum first, otherwise a datum hash resolved against the witness set
+            let datum = output
+                .get("inline_datum")
+                .and_then(|d| d.get("plutus_data"))
+                .or_else(|| {
+                    let datum_hash = output.get("datum_hash")?.as_str()?;
+                    witness_datums?
+                        .iter()
+                        .find(|d| d.get("datum_hash").and_then(|h| h.as_str()) == Some(datum_hash))?
+                        .get("plutus_data")
+                });
+
+            let unit = format!("{}{}", policy, asset_name);
+            match datum.and_then(|d| cip::parse_cip68_datum(&unit, d, tx_hash, slot)) {
+                Some(metadata) => {
+                    tracing::info!("CIP-68 metadata for {}", unit);
+                    state.add_asset(metadata).await;
+                }
+                None => tracing::debug!("No CIP-68 datum decoded for {}", unit),
+            }
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pallas_codec::minicbor::{encode::Error, Encoder};
+    use std::convert::Infallible;
+
+    const DREP_KEY_HASH: [u8; 28] = [0x33; 28];
+
+    fn block(hash: &str, epoch: u64, pool: &str) -> Block {
+        let mut block = Block::new(hash.to_string(), 1, 1, epoch, 1, 0, 0);
+        block.pool_id = Some(pool.to_string());
+        block
+    }
+
+    #[tokio::test]
+    async fn test_epoch_summary_and_boundary() {
+        let state = BlockChainState::new();
+
+        assert!(!state.record_epoch_block(&block("a", 10, "pool1a")).await);
+        assert!(!state.record_epoch_block(&block("b", 10, "pool1b")).await);
+        state.record_epoch_transaction(10, 200, 5000).await;
+        assert!(state.record_epoch_block(&block("c", 11, "pool1a")).await);
+
+        let summary = state.get_epoch(10).await.unwrap();
+        assert_eq!(summary.blocks, 2);
+        assert_eq!(summary.transactions, 1);
+        assert_eq!(summary.fees, 200);
+        assert_eq!(summary.active_pools, 2);
+        assert_eq!(summary.first_block.as_deref(), Some("a"));
+        assert_eq!(summary.last_block.as_deref(), Some("b"));
+        assert_eq!(state.get_epochs(10).await[0].epoch, 11);
+    }
+
+    #[tokio::test]
+    async fn test_pending_confirmation() {
+        let state = Arc::new(BlockChainState::new());
+        let mut events = state.subscribe();
+        state
+            .add_pending(PendingTx {
+                hash: "ab".repeat(32),
+                era: "Conway".to_string(),
+                size: 300,
+                submitted_at: 1000,
+                via: "submit_api".to_string(),
+            })
+            .await;
+
+        let event = serde_json::json!({
+            "event": "apply",
+            "point": { "slot": 500, "hash": "cd".repeat(32) },
+            "record": {
+                "context": { "block_number": 7, "block_hash": "cd".repeat(32), "timestamp": 1020 },
+                "transaction": { "hash": "ab".repeat(32), "fee": 170000 }
+            }
+        });
+        process_event(event, state.clone()).await;
+
+        assert!(state.take_pending(&"ab".repeat(32)).await.is_none());
+        assert!(matches!(events.recv().await.unwrap().event, ChainEvent::Transaction(_)));
+        match events.recv().await.unwrap().event {
+            ChainEvent::TxConfirmed { block_number, submitted_at, confirmed_at, .. } => {
+                assert_eq!((block_number, submitted_at, confirmed_at), (7, 1000, 1020));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_confirmations_and_rollback() {
+        let state = Arc::new(BlockChainState::new());
+        for number in 1..=3 {
+            state.add_block(Block::new(format!("b{}", number), number, number * 10, 1, 0, 0, 0)).await;
+            state.add_transactions(Transaction::new(format!("t{}", number), number, 0, 0, 1, 1, 0)).await;
+        }
+        assert_eq!(state.get_block_by_number(1).await.unwrap().confirmations, 2);
+        assert_eq!(state.get_transaction("t3").await.unwrap().confirmations, 0);
+
+        // undo of the tip block drops it and its transaction
+        let undo = serde_json::json!({
+            "event": "undo",
+            "point": { "slot": 30, "hash": "b3" },
+            "record": { "context": { "block_number": 3 }, "block": { "tx_count": 1 } }
+        });
+        process_event(undo, state.clone()).await;
+        assert!(state.get_block_by_hash("b3").await.is_none());
+        assert_eq!(state.get_rolled_back("t3").await.unwrap().block_number, 3);
+        assert_eq!(state.get_transaction("t1").await.unwrap().confirmations, 1);
+
+        // reset to slot 10 keeps block 1 only
+        let reset = serde_json::json!({ "event": "reset", "point": { "slot": 10, "hash": "b1" } });
+        process_event(reset, state.clone()).await;
+        assert_eq!(state.tip().await, 1);
+        assert!(state.get_transaction("t2").await.is_none());
+        assert!(state.get_rolled_back("t2").await.is_some());
+
+        // a rolled back transaction landing again is on chain once more
+        state.add_transactions(Transaction::new("t2".to_string(), 1, 0, 0, 1, 1, 0)).await;
+        assert!(state.get_rolled_back("t2").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_script_usage() {
+        let state = Arc::new(BlockChainState::new());
+        // t0 locks an output at the script's address, t1 and t2 spend it with a reference script
+        let script = "c37b1b5dc0669f1d3c61a6fddb2e8fde96be87b881c60bce8e8d542f";
+        let lock = serde_json::json!({
+            "event": "apply",
+            "point": { "slot": 50, "hash": "b" },
+            "record": {
+                "context": { "block_number": 1 },
+                "transaction": {
+                    "hash": "t0",
+                    "outputs": [{
+                        "address": "addr_test1wrphkx6acpnf78fuvxn0mkew3l0fd058hzquvz7w36x4gtcl6szpr",
+                        "amount": 2000000,
+                        "datum_hash": "ff".repeat(32)
+                    }]
+                }
+            }
+        });
+        process_event(lock, state.clone()).await;
+        state.mark_invalid(vec!["t2".to_string()], 1).await;
+
+        for (hash, slot) in [("t1", 100), ("t2", 200)] {
+            let event = serde_json::json!({
+                "event": "apply",
+                "point": { "slot": slot, "hash": "b" },
+                "record": {
+                    "context": { "block_number": 1 },
+                    "transaction": {
+                        "hash": hash,
+                        "inputs": [{ "tx_id": "t0", "index": 0 }],
+                        "plutus_redeemers": [{ "purpose": "spend", "input_idx": 0, "ex_units_mem": 10, "ex_units_steps": 20, "plutus_data": {} }],
+                        "collateral_inputs": [{ "tx_id": "11".repeat(32), "index": 0 }]
+                    }
+                }
+            });
+            process_event(event, state.clone()).await;
+        }
+
+        assert!(state.get_transaction("t1").await.unwrap().is_valid);
+        assert!(!state.get_transaction("t2").await.unwrap().is_valid);
+        assert_eq!(state.get_script_data("t1").await.unwrap().collateral_inputs.len(), 1);
+
+        let summary = state.get_script(script).await.unwrap();
+        assert_eq!((summary.transactions, summary.redeemers, summary.failed), (2, 2, 1));
+        assert_eq!((summary.ex_units_mem, summary.ex_units_steps), (20, 40));
+        assert_eq!((summary.first_seen_slot, summary.last_seen_slot), (100, 200));
+        assert_eq!(summary.recent_transactions, ["t2", "t1"]);
+
+        // undo of the failed transaction takes its usage back out
+        let undo = serde_json::json!({ "event": "undo", "point": { "slot": 200, "hash": "b" }, "record": { "transaction": { "hash": "t2" } } });
+        process_event(undo, state.clone()).await;
+        let summary = state.get_script(script).await.unwrap();
+        assert_eq!((summary.transactions, summary.redeemers, summary.failed), (1, 1, 0));
+        assert_eq!((summary.ex_units_mem, summary.ex_units_steps), (10, 20));
+        assert_eq!(summary.recent_transactions, ["t1"]);
+    }
+
+    // Conway transaction body spending one input, with a vote by a key drep on `gov_action`
+    // and an info action proposal
+    fn conway_tx_body(gov_action: [u8; 32]) -> Result<Vec<u8>, Error<Infallible>> {
+        let mut e = Encoder::new(Vec::new());
+        e.map(5)?;
+        e.u8(0)?.array(1)?.array(2)?.bytes(&[0x11; 32])?.u8(0)?;
+        e.u8(1)?.array(1)?.array(2)?.bytes(&[0x60; 29])?.u64(5_000_000)?;
+        e.u8(2)?.u64(180_000)?;
+        e.u8(19)?.map(1)?.array(2)?.u8(2)?.bytes(&DREP_KEY_HASH)?;
+        e.map(1)?.array(2)?.bytes(&gov_action)?.u8(0)?.array(2)?.u8(1)?.null()?;
+        e.u8(20)?.array(1)?.array(4)?.u64(100_000_000_000)?.bytes(&[0xe0; 29])?;
+        e.array(1)?.u8(6)?.array(2)?.str("https://example.com/info.json")?.bytes(&[0x22; 32])?;
+        Ok(e.into_writer())
+    }
+
+    // Conway transaction body registering a key drep, delegating a stake key's vote to it and
+    // authorizing a committee hot key
+    fn conway_cert_tx_body() -> Result<Vec<u8>, Error<Infallible>> {
+        let mut e = Encoder::new(Vec::new());
+        e.map(4)?;
+        e.u8(0)?.array(1)?.array(2)?.bytes(&[0x12; 32])?.u8(0)?;
+        e.u8(1)?.array(1)?.array(2)?.bytes(&[0x60; 29])?.u64(5_000_000)?;
+        e.u8(2)?.u64(180_000)?;
+        e.u8(4)?.array(3)?;
+        e.array(4)?.u8(16)?.array(2)?.u8(0)?.bytes(&DREP_KEY_HASH)?.u64(500_000_000)?.null()?;
+        e.array(3)?.u8(9)?.array(2)?.u8(0)?.bytes(&[0x77; 28])?.array(2)?.u8(0)?.bytes(&DREP_KEY_HASH)?;
+        e.array(3)?.u8(14)?.array(2)?.u8(0)?.bytes(&[0x88; 28])?.array(2)?.u8(0)?.bytes(&[0x99; 28])?;
+        Ok(e.into_writer())
+    }
+
+    // Block cbor as oura's include_block_cbor carries it: [era, block], 7 for Conway
+    fn conway_block(bodies: &[Vec<u8>], aux: &[(u32, &[u8])], invalid: &[u32]) -> Result<String, Error<Infallible>> {
+        let mut e = Encoder::new(Vec::new());
+        e.array(2)?.u8(7)?.array(5)?;
+        e.array(2)?.array(10)?;
+        e.u64(42)?.u64(1000)?.null()?.bytes(&[1; 32])?.bytes(&[2; 32])?;
+        e.array(2)?.bytes(&[3; 32])?.bytes(&[4; 80])?;
+        e.u64(0)?.bytes(&[5; 32])?;
+        e.array(4)?.bytes(&[6; 32])?.u64(7)?.u64(8)?.bytes(&[9; 64])?;
+        e.array(2)?.u64(10)?.u64(2)?;
+        e.bytes(&[0; 448])?;
+        e.array(bodies.len() as u64)?;
+        for body in bodies {
+            e.writer_mut().extend_from_slice(body);
+        }
+        e.array(bodies.len() as u64)?;
+        for _ in bodies {
+            e.map(0)?;
+        }
+        e.map(aux.len() as u64)?;
+        for (index, data) in aux {
+            e.u32(*index)?;
+            e.writer_mut().extend_from_slice(data);
+        }
+        e.array(invalid.len() as u64)?;
+        for index in invalid {
+            e.u32(*index)?;
+        }
+        Ok(hex::encode(e.into_writer()))
+    }
+
+    fn tx_hash(body: &[u8]) -> String {
+        pallas_crypto::hash::Hasher::<256>::hash(body).to_string()
+    }
+
+    fn block_event(cbor_hex: &str, tx_count: usize) -> serde_json::Value {
+        serde_json::json!({
+            "event": "apply",
+            "point": { "slot": 1000, "hash": "cd".repeat(32) },
+            "record": {
+                "context": { "block_number": 42, "slot": 1000, "timestamp": 1_700_000_000 },
+                "block": {
+                    "era": "Conway",
+                    "body_size": 512,
+                    "tx_count": tx_count,
+                    "number": 42,
+                    "slot": 1000,
+                    "epoch": 150,
+                    "hash": "cd".repeat(32),
+                    "previous_hash": "ef".repeat(32),
+                    "issuer_vkey": hex::encode([1; 32]),
+                    "vrf_vkey": hex::encode([2; 32]),
+                    "cbor_hex": cbor_hex
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_oura_config() {
+        let config = oura_config(None);
+        assert!(config.contains("include_block_cbor = true"));
+        assert!(config.contains("type = \"Tip\""));
+
+        let point = ChainPoint { slot: 42, hash: "ab".repeat(32), block_number: 7 };
+        assert!(oura_config(Some(&point)).contains(&format!("value = [42, \"{}\"]", "ab".repeat(32))));
+    }
+
+    #[tokio::test]
+    async fn test_governance_from_block_event() {
+        let state = Arc::new(BlockChainState::new());
+        let mut events = state.subscribe();
+        let body = conway_tx_body([0x44; 32]).unwrap();
+        let hash = tx_hash(&body);
+
+        process_event(block_event(&conway_block(&[body], &[], &[]).unwrap(), 1), state.clone()).await;
+
+        let proposals = state.get_proposals(None, 10).await;
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].id, format!("{}#0", hash));
+        assert_eq!((proposals[0].action.as_str(), proposals[0].epoch, proposals[0].block_number), ("info", 150, 42));
+
+        let votes = state.get_proposal_votes(&format!("{}#0", "44".repeat(32))).await;
+        assert_eq!(votes.len(), 1);
+        assert_eq!((votes[0].voter_role.as_str(), votes[0].vote.as_str()), ("drep", "yes"));
+        assert_eq!(Some(votes[0].voter.clone()), certs::drep_id(false, &DREP_KEY_HASH));
+
+        let mut published = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            published.push(event.event);
+        }
+        assert!(published.iter().any(|e| matches!(e, ChainEvent::Proposal(p) if p.tx_hash == hash)));
+        assert!(published.iter().any(|e| matches!(e, ChainEvent::Vote(_))));
+    }
+
+    #[tokio::test]
+    async fn test_governance_replayed_block() {
+        let state = Arc::new(BlockChainState::new());
+        let block = conway_block(&[conway_tx_body([0x44; 32]).unwrap()], &[], &[]).unwrap();
+
+        // oura replays the block after a restart from the saved chain point
+        process_event(block_event(&block, 1), state.clone()).await;
+        process_event(block_event(&block, 1), state.clone()).await;
+
+        assert_eq!(state.get_proposals(None, 10).await.len(), 1);
+        assert_eq!(state.get_proposal_votes(&format!("{}#0", "44".repeat(32))).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_header_from_block_event() {
+        let state = Arc::new(BlockChainState::new());
+        process_event(block_event(&conway_block(&[], &[], &[]).unwrap(), 0), state.clone()).await;
+
+        let block = state.get_block_by_number(42).await.unwrap();
+        assert_eq!(block.vrf_output, Some(hex::encode([3; 32])));
+        let version = block.protocol_version.unwrap();
+        assert_eq!((version.major, version.minor), (10, 2));
+        assert_eq!(block.op_cert_counter, Some(7));
+        assert_eq!(block.pool_id, pools::pool_id_from_vkey(&hex::encode([1; 32])));
+        assert_eq!(state.get_pool_epoch_counts(block.pool_id.as_deref().unwrap()).await.get(&150), Some(&1));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_metadata_cbor_from_block_event() {
+        let state = Arc::new(BlockChainState::new());
+        let mut events = state.subscribe();
+        let body = conway_tx_body([0x44; 32]).unwrap();
+        let hash = tx_hash(&body);
+        // {674: {"msg": ["hi"]}, 1: h'cafe'}
+        let aux = hex::decode("a21902a2a1636d73678162686901 42cafe".replace(' ', "")).unwrap();
+        process_event(block_event(&conway_block(&[body], &[(0, &aux)], &[]).unwrap(), 1), state.clone()).await;
+
+        // oura's rendition of the same metadata arrives with the transaction record
+        let tx = serde_json::json!({
+            "event": "apply",
+            "point": { "slot": 1000, "hash": "cd".repeat(32) },
+            "record": {
+                "context": { "block_number": 42 },
+                "transaction": {
+                    "hash": hash,
+                    "metadata": [
+                        { "label": "674", "map_json": { "msg": ["hi"] } },
+                        { "label": "1", "bytes_hex": "cafe" }
+                    ]
+                }
+            }
+        });
+        process_event(tx, state.clone()).await;
+
+        let entries = state.get_tx_metadata(&hash).await;
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].label, entries[0].cbor.as_deref()), (1, Some("42cafe")));
+        assert_eq!((entries[1].label, entries[1].cbor.as_deref()), (674, Some("a1636d736781626869")));
+        assert_eq!(entries[0].json, serde_json::json!({ "bytes": "cafe" }));
+
+        // published once, from the block, not again for the transaction record
+        let mut published = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let ChainEvent::Metadata(entry) = event.event {
+                published.push((entry.label, entry.tx_hash));
+            }
+        }
+        published.sort();
+        assert_eq!(published, vec![(1, hash.clone()), (674, hash.clone())]);
+
+        // without block cbor only the JSON is known
+        let tx = serde_json::json!({
+            "event": "apply",
+            "point": { "slot": 1001, "hash": "ef".repeat(32) },
+            "record": {
+                "context": { "block_number": 43 },
+                "transaction": { "hash": "t2", "metadata": [{ "label": "674", "map_json": { "msg": ["hi"] } }] }
+            }
+        });
+        process_event(tx, state.clone()).await;
+        let entries = state.get_tx_metadata("t2").await;
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].cbor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_tx_from_block_event() {
+        let state = Arc::new(BlockChainState::new());
+        let valid = conway_tx_body([0x44; 32]).unwrap();
+        let failed = conway_tx_body([0x55; 32]).unwrap();
+        let (valid_hash, failed_hash) = (tx_hash(&valid), tx_hash(&failed));
+        let cbor = conway_block(&[valid, failed], &[], &[1]).unwrap();
+        process_event(block_event(&cbor, 2), state.clone()).await;
+
+        // the transaction records follow their block, without a validity flag of their own
+        for hash in [&valid_hash, &failed_hash] {
+            let tx = serde_json::json!({
+                "event": "apply",
+                "point": { "slot": 1000, "hash": "cd".repeat(32) },
+                "record": { "context": { "block_number": 42 }, "transaction": { "hash": hash, "fee": 180000 } }
+            });
+            process_event(tx, state.clone()).await;
+        }
+        assert!(state.get_transaction(&valid_hash).await.unwrap().is_valid);
+        assert!(!state.get_transaction(&failed_hash).await.unwrap().is_valid);
+        assert!(state.invalid_txs.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_conway_certificates_from_block_event() {
+        let state = Arc::new(BlockChainState::new());
+        let body = conway_cert_tx_body().unwrap();
+        let hash = tx_hash(&body);
+        process_event(block_event(&conway_block(&[body], &[], &[]).unwrap(), 1), state.clone()).await;
+
+        let drep = certs::drep_id(false, &DREP_KEY_HASH).unwrap();
+        let registrations = state.get_drep_certificates(&drep).await;
+        assert_eq!(registrations.len(), 1);
+        assert_eq!((registrations[0].kind.as_str(), registrations[0].deposit), ("drep_registration", Some(500_000_000)));
+        assert_eq!((registrations[0].tx_hash.as_str(), registrations[0].block_number), (hash.as_str(), 42));
+
+        let delegators = state.get_drep_delegators(&drep).await;
+        assert_eq!(delegators.len(), 1);
+        assert!(delegators[0].starts_with("stake_test1"));
+
+        let certificates = state.certificates.read().await;
+        let kinds: Vec<&str> = certificates.iter().map(|c| c.kind.as_str()).collect();
+        assert_eq!(kinds, ["committee_hot_auth", "vote_delegation", "drep_registration"]);
+    }
+
+    // Block record without cbor, `event` is apply or undo
+    fn block_record(event: &str, number: u64, epoch: u64, issuer: u8) -> serde_json::Value {
+        serde_json::json!({
+            "event": event,
+            "point": { "slot": number * 10, "hash": format!("b{}", number) },
+            "record": {
+                "context": { "block_number": number, "timestamp": 1000 + number },
+                "block": { "number": number, "epoch": epoch, "tx_count": 1, "body_size": 100, "issuer_vkey": hex::encode([issuer; 32]) }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_rollback_pool_blocks() {
+        let state = Arc::new(BlockChainState::new());
+        for (number, issuer) in [(1, 1), (2, 2), (3, 2)] {
+            process_event(block_record("apply", number, 10, issuer), state.clone()).await;
+        }
+        let (pool_a, pool_b) = (
+            pools::pool_id_from_vkey(&hex::encode([1; 32])).unwrap(),
+            pools::pool_id_from_vkey(&hex::encode([2; 32])).unwrap(),
+        );
+        assert_eq!(state.get_pool_epoch_counts(&pool_b).await.get(&10), Some(&2));
+
+        process_event(block_record("undo", 3, 10, 2), state.clone()).await;
+        assert_eq!(state.get_pool_epoch_counts(&pool_b).await.get(&10), Some(&1));
+        assert_eq!(state.get_pool_blocks(&pool_b, 10).await.len(), 1);
+
+        // reset to block 1: pool b produced nothing that is still on chain
+        process_event(serde_json::json!({ "event": "reset", "point": { "slot": 10, "hash": "b1" } }), state.clone()).await;
+        assert!(!state.has_pool(&pool_b).await);
+        assert!(state.get_pool_blocks(&pool_b, 10).await.is_empty());
+        assert_eq!(state.get_epoch_pool_counts(10).await, vec![(pool_a, 1)]);
+    }
+
+    fn tx_record(block: u64, hash: &str, fee: u64, total_output: u64) -> serde_json::Value {
+        serde_json::json!({
+            "event": "apply",
+            "point": { "slot": block * 10, "hash": format!("b{}", block) },
+            "record": {
+                "context": { "block_number": block, "epoch": 10, "timestamp": 1000 + block, "tx_hash": hash },
+                "transaction": { "hash": hash, "fee": fee, "total_output": total_output, "input_count": 1, "output_count": 1 }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_rollback_epoch_summary() {
+        let state = Arc::new(BlockChainState::new());
+        for (number, issuer, fee, output) in [(1, 1, 100, 1000), (2, 2, 200, 2000), (3, 2, 300, 3000)] {
+            process_event(block_record("apply", number, 10, issuer), state.clone()).await;
+            process_event(tx_record(number, &format!("t{}", number), fee, output), state.clone()).await;
+        }
+        process_event(block_record("apply", 4, 11, 1), state.clone()).await;
+        let summary = state.get_epoch(10).await.unwrap();
+        assert_eq!((summary.blocks, summary.transactions, summary.fees, summary.output_volume), (3, 3, 600, 6000));
+
+        // reset to block 2: block 3 and its transaction leave epoch 10, epoch 11 is gone
+        process_event(serde_json::json!({ "event": "reset", "point": { "slot": 20, "hash": "b2" } }), state.clone()).await;
+        let summary = state.get_epoch(10).await.unwrap();
+        assert_eq!((summary.blocks, summary.transactions, summary.fees, summary.output_volume), (2, 2, 300, 3000));
+        assert_eq!(summary.first_block.as_deref(), Some("b1"));
+        assert_eq!((summary.last_block.as_deref(), summary.last_block_time), (Some("b2"), Some(1002)));
+        assert_eq!(summary.active_pools, 2);
+        assert!(state.get_epoch(11).await.is_none());
+
+        // undo of block 2 also drops its pool from the epoch
+        process_event(block_record("undo", 2, 10, 2), state.clone()).await;
+        let summary = state.get_epoch(10).await.unwrap();
+        assert_eq!((summary.blocks, summary.transactions, summary.fees), (1, 1, 100));
+        assert_eq!(summary.active_pools, 1);
+        assert_eq!(summary.last_block.as_deref(), Some("b1"));
+    }
+
+    #[tokio::test]
+    async fn test_rollback_stats() {
+        let state = Arc::new(BlockChainState::new());
+        for (number, fee) in [(1, 100), (2, 200), (3, 300)] {
+            process_event(block_record("apply", number, 10, 1), state.clone()).await;
+            process_event(tx_record(number, &format!("t{}", number), fee, 1000), state.clone()).await;
+        }
+        let day = &state.get_stats().await.windows[2];
+        assert_eq!((day.blocks, day.transactions, day.fees.max), (3, 3, 300));
+
+        process_event(serde_json::json!({ "event": "reset", "point": { "slot": 20, "hash": "b2" } }), state.clone()).await;
+        let stats = state.get_stats().await;
+        assert_eq!(stats.as_of, 1002);
+        assert_eq!((stats.windows[2].blocks, stats.windows[2].transactions, stats.windows[2].fees.max), (2, 2, 200));
+
+        let mut undo = tx_record(2, "t2", 200, 1000);
+        undo["event"] = serde_json::json!("undo");
+        process_event(undo, state.clone()).await;
+        let day = &state.get_stats().await.windows[2];
+        assert_eq!((day.blocks, day.transactions, day.fees.max), (2, 1, 100));
+    }
+
+    #[tokio::test]
+    async fn test_rollback_metadata() {
+        let state = Arc::new(BlockChainState::new());
+        for number in [1, 2] {
+            process_event(block_record("apply", number, 10, 1), state.clone()).await;
+            let mut tx = tx_record(number, &format!("t{}", number), 100, 1000);
+            tx["record"]["transaction"]["metadata"] = serde_json::json!([{ "label": "674", "map_json": { "msg": [number.to_string()] } }]);
+            process_event(tx, state.clone()).await;
+        }
+        assert_eq!(state.get_metadata_by_label(674, 10).await.len(), 2);
+
+        process_event(block_record("undo", 2, 10, 1), state.clone()).await;
+        let entries = state.get_metadata_by_label(674, 10).await;
+        assert_eq!(entries.iter().map(|m| m.tx_hash.as_str()).collect::<Vec<_>>(), vec!["t1"]);
+        assert!(!state.has_tx_metadata("t2").await);
+
+        // undo of the transaction record alone
+        let mut undo = tx_record(1, "t1", 100, 1000);
+        undo["event"] = serde_json::json!("undo");
+        process_event(undo, state.clone()).await;
+        assert!(state.get_metadata_by_label(674, 10).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rollback_script_usage() {
+        let state = Arc::new(BlockChainState::new());
+        let script = "aa".repeat(28);
+        process_event(block_record("apply", 1, 10, 1), state.clone()).await;
+        let mut tx = tx_record(1, "t1", 100, 1000);
+        tx["record"]["transaction"]["plutus_witnesses"] = serde_json::json!([{ "script_hash": script, "script_hex": "4e4d" }]);
+        process_event(tx, state.clone()).await;
+        // block 2 failed a script, its transaction record never arrived before the reset
+        process_event(block_record("apply", 2, 10, 1), state.clone()).await;
+        state.mark_invalid(vec!["t2".to_string()], 2).await;
+        assert_eq!(state.get_script(&script).await.unwrap().transactions, 1);
+
+        process_event(serde_json::json!({ "event": "reset", "point": { "slot": 0, "hash": "b0" } }), state.clone()).await;
+        assert!(state.get_script(&script).await.is_none());
+        assert!(state.get_script_data("t1").await.is_none());
+        assert!(state.invalid_txs.read().await.is_empty());
+    }
+
+    fn cip25_tx(block: u64, hash: &str, name: &str) -> serde_json::Value {
+        let mut tx = tx_record(block, hash, 100, 1000);
+        tx["record"]["transaction"]["metadata"] = serde_json::json!([{
+            "label": cip::CIP25_LABEL,
+            "map_json": { "aa".repeat(28): { "NFT1": { "name": name, "image": "ipfs://a" } } }
+        }]);
+        tx
+    }
+
+    #[tokio::test]
+    async fn test_rollback_assets() {
+        let state = Arc::new(BlockChainState::new());
+        let unit = format!("{}{}", "aa".repeat(28), hex::encode("NFT1"));
+        for (number, name) in [(1, "first"), (2, "second"), (3, "third")] {
+            process_event(block_record("apply", number, 10, 1), state.clone()).await;
+            process_event(cip25_tx(number, &format!("t{}", number), name), state.clone()).await;
+        }
+        // replaying a transaction after a restart keeps what it replaced
+        process_event(cip25_tx(3, "t3", "third"), state.clone()).await;
+        assert_eq!(state.get_asset(&unit).await.unwrap().name.as_deref(), Some("third"));
+
+        process_event(block_record("undo", 3, 10, 1), state.clone()).await;
+        let asset = state.get_asset(&unit).await.unwrap();
+        assert_eq!((asset.name.as_deref(), asset.tx_hash.as_str()), (Some("second"), "t2"));
+
+        let mut undo = cip25_tx(2, "t2", "second");
+        undo["event"] = serde_json::json!("undo");
+        process_event(undo, state.clone()).await;
+        assert_eq!(state.get_asset(&unit).await.unwrap().name.as_deref(), Some("first"));
+
+        process_event(serde_json::json!({ "event": "reset", "point": { "slot": 0, "hash": "b0" } }), state.clone()).await;
+        assert!(state.get_asset(&unit).await.is_none());
+        assert!(state.asset_versions.read().await.is_empty());
+    }
+}
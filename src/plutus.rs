@@ -0,0 +1,399 @@
+//Plutus data and script rendering
+use pallas_primitives::alonzo::NativeScript;
+use pallas_primitives::{BigInt, PlutusData};
+use pallas_traverse::MultiEraBlock;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::address;
+use crate::metadata::int_to_json;
+use crate::models::{CollateralOutput, Datum, InputRef, Redeemer, ScriptWitness, TxScriptData};
+
+/// Constructor index from the CBOR tag (121-127, 1280-1400, or 102 with an explicit index)
+pub fn constructor_index(tag: u64, any_constructor: Option<u64>) -> u64 {
+    match tag {
+        121..=127 => tag - 121,
+        1280..=1400 => tag - 1280 + 7,
+        _ => any_constructor.unwrap_or(tag),
+    }
+}
+
+// Unsigned big endian bytes as a decimal string
+fn bytes_to_decimal(bytes: &[u8]) -> String {
+    let mut digits: Vec<u8> = vec![0]; //little endian base 10
+    for byte in bytes {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            let value = *digit as u32 * 256 + carry;
+            *digit = (value % 10) as u8;
+            carry = value / 10;
+        }
+        while carry > 0 {
+            digits.push((carry % 10) as u8);
+            carry /= 10;
+        }
+    }
+    while digits.len() > 1 && digits.last() == Some(&0) {
+        digits.pop();
+    }
+    digits.iter().rev().map(|d| (b'0' + d) as char).collect()
+}
+
+fn big_int_to_json(int: &BigInt) -> Value {
+    match int {
+        BigInt::Int(i) => int_to_json(i128::from(*i)),
+        BigInt::BigUInt(bytes) => json!(bytes_to_decimal(bytes)),
+        BigInt::BigNInt(bytes) => {
+            // encoded as n for the value -1 - n
+            let mut n = bytes.to_vec();
+            let mut i = n.len();
+            loop {
+                if i == 0 {
+                    n.insert(0, 1);
+                    break;
+                }
+                i -= 1;
+                if n[i] == 0xff {
+                    n[i] = 0;
+                } else {
+                    n[i] += 1;
+                    break;
+                }
+            }
+            json!(format!("-{}", bytes_to_decimal(&n)))
+        }
+    }
+}
+
+/// Detailed schema, same shape as cardano-cli and oura ({"constructor","fields"}, {"map"}, {"list"}, {"int"}, {"bytes"})
+pub fn data_to_json(data: &PlutusData) -> Value {
+    match data {
+        PlutusData::Constr(constr) => json!({
+            "constructor": constructor_index(constr.tag, constr.any_constructor),
+            "fields": constr.fields.iter().map(data_to_json).collect::<Vec<_>>(),
+        }),
+        PlutusData::Map(entries) => json!({
+            "map": entries
+                .iter()
+                .map(|(k, v)| json!({ "k": data_to_json(k), "v": data_to_json(v) }))
+                .collect::<Vec<_>>()
+        }),
+        PlutusData::Array(items) => json!({ "list": items.iter().map(data_to_json).collect::<Vec<_>>() }),
+        PlutusData::BigInt(int) => json!({ "int": big_int_to_json(int) }),
+        PlutusData::BoundedBytes(bytes) => json!({ "bytes": hex::encode(bytes.as_slice()) }),
+    }
+}
+
+/// Native script in the cardano-cli JSON format
+pub fn native_script_to_json(script: &NativeScript) -> Value {
+    match script {
+        NativeScript::ScriptPubkey(hash) => json!({ "type": "sig", "keyHash": hash.to_string() }),
+        NativeScript::ScriptAll(scripts) => json!({
+            "type": "all",
+            "scripts": scripts.iter().map(native_script_to_json).collect::<Vec<_>>(),
+        }),
+        NativeScript::ScriptAny(scripts) => json!({
+            "type": "any",
+            "scripts": scripts.iter().map(native_script_to_json).collect::<Vec<_>>(),
+        }),
+        NativeScript::ScriptNOfK(required, scripts) => json!({
+            "type": "atLeast",
+            "required": required,
+            "scripts": scripts.iter().map(native_script_to_json).collect::<Vec<_>>(),
+        }),
+        NativeScript::InvalidBefore(slot) => json!({ "type": "after", "slot": slot }),
+        NativeScript::InvalidHereafter(slot) => json!({ "type": "before", "slot": slot }),
+    }
+}
+
+fn str_field(value: &Value, key: &str) -> String {
+    value.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string()
+}
+
+fn u64_field(value: &Value, key: &str) -> u64 {
+    value.get(key).and_then(|v| v.as_u64()).unwrap_or(0)
+}
+
+fn input_ref(input: &Value) -> InputRef {
+    InputRef { tx_hash: str_field(input, "tx_id"), index: u64_field(input, "index") }
+}
+
+/// Script credentials a transaction record does not carry itself
+#[derive(Debug, Default)]
+pub struct KnownCredentials {
+    pub spent: HashMap<(String, u64), String>, //(tx hash, output index) -> script of the spent output's payment credential
+    pub certs: HashMap<u32, String>, //certificate index -> script of its credential
+}
+
+// Script a redeemer unlocks: mint redeemers point into the sorted policy ids, reward redeemers
+// into the withdrawals in ledger order, spend redeemers into the sorted inputs and cert redeemers
+// into the certificates. None when the credential is a key or not known, never a guess
+fn redeemer_script(tag: &str, index: usize, tx_obj: &Value, known: &KnownCredentials) -> Option<String> {
+    match tag {
+        "mint" => {
+            let mut policies: Vec<String> = tx_obj
+                .get("mint")
+                .and_then(|m| m.as_array())
+                .map(|m| m.iter().map(|asset| str_field(asset, "policy")).collect())
+                .unwrap_or_default();
+            policies.sort();
+            policies.dedup();
+            policies.get(index).cloned()
+        }
+        "reward" => {
+            // header 0xf0 / 0xf1 is a script credential, the ledger orders those before key
+            // credentials and then by hash
+            let mut accounts: Vec<(bool, String)> = tx_obj
+                .get("withdrawals")
+                .and_then(|w| w.as_array())
+                .map(|w| {
+                    w.iter()
+                        .map(|withdrawal| str_field(withdrawal, "reward_account"))
+                        .filter(|account| account.len() == 58)
+                        .map(|account| (!account.starts_with('f'), account[2..].to_string()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            accounts.sort();
+            accounts.get(index).filter(|(is_key, _)| !is_key).map(|(_, hash)| hash.clone())
+        }
+        "spend" => {
+            let mut inputs: Vec<(String, u64)> = tx_obj
+                .get("inputs")
+                .and_then(|i| i.as_array())
+                .map(|i| i.iter().map(|input| (str_field(input, "tx_id"), u64_field(input, "index"))).collect())
+                .unwrap_or_default();
+            inputs.sort();
+            inputs.get(index).and_then(|input| known.spent.get(input)).cloned()
+        }
+        "cert" => known.certs.get(&(index as u32)).cloned(),
+        _ => None,
+    }
+}
+
+/// Datum or redeemer CBOR to its hash and detailed schema JSON
+pub fn decode_data(cbor_hex: &str) -> Result<(String, Value), String> {
+    let cbor = hex::decode(cbor_hex.trim()).map_err(|e| format!("Invalid hex: {}", e))?;
+    let data: PlutusData = pallas_codec::minicbor::decode(&cbor).map_err(|e| format!("Failed to decode Plutus data: {}", e))?;
+    let hash = pallas_crypto::hash::Hasher::<256>::hash(&cbor);
+    Ok((hash.to_string(), data_to_json(&data)))
+}
+
+/// Script witnesses, redeemers, datums and collateral of an oura transaction record
+pub fn script_data_from_record(tx_obj: &Value, tx_hash: &str, known: &KnownCredentials) -> TxScriptData {
+    let array = |key: &str| tx_obj.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+
+    let mut scripts: Vec<ScriptWitness> = array("native_witnesses")
+        .iter()
+        .map(|w| ScriptWitness {
+            hash: str_field(w, "policy_id"),
+            kind: "native".to_string(),
+            size: None,
+            json: w.get("script_json").cloned(),
+        })
+        .collect();
+    scripts.extend(array("plutus_witnesses").iter().map(|w| ScriptWitness {
+        hash: str_field(w, "script_hash"),
+        kind: "plutus".to_string(),
+        size: w.get("script_hex").and_then(|h| h.as_str()).map(|h| h.len() as u64 / 2),
+        json: None,
+    }));
+
+    let redeemers = array("plutus_redeemers")
+        .iter()
+        .map(|r| {
+            let tag = str_field(r, "purpose");
+            let index = u64_field(r, "input_idx") as u32;
+            Redeemer {
+                script_hash: redeemer_script(&tag, index as usize, tx_obj, known),
+                tag,
+                index,
+                data: r.get("plutus_data").cloned().unwrap_or(Value::Null),
+                ex_units_mem: u64_field(r, "ex_units_mem"),
+                ex_units_steps: u64_field(r, "ex_units_steps"),
+                typed: None,
+            }
+        })
+        .collect();
+
+    let mut datums: Vec<Datum> = array("plutus_data")
+        .iter()
+        .map(|d| Datum {
+            hash: str_field(d, "datum_hash"),
+            source: "witness".to_string(),
+            output_index: None,
+            data: d.get("plutus_data").cloned(),
+            script_hash: None,
+            typed: None,
+        })
+        .collect();
+    for (index, output) in array("outputs").iter().enumerate() {
+        let script_hash = address::inspect(&str_field(output, "address"))
+            .ok()
+            .and_then(|info| info.payment)
+            .filter(|payment| payment.kind == "script")
+            .map(|payment| payment.hash);
+        if let Some(inline) = output.get("inline_datum").filter(|d| !d.is_null()) {
+            datums.push(Datum {
+                hash: str_field(inline, "datum_hash"),
+                source: "inline".to_string(),
+                output_index: Some(index as u32),
+                data: inline.get("plutus_data").cloned(),
+                script_hash,
+                typed: None,
+            });
+        } else if let Some(hash) = output.get("datum_hash").and_then(|h| h.as_str()) {
+            // the witness set may carry the datum itself
+            if !datums.iter().any(|d| d.hash == hash) {
+                datums.push(Datum {
+                    hash: hash.to_string(),
+                    source: "hash".to_string(),
+                    output_index: Some(index as u32),
+                    data: None,
+                    script_hash,
+                    typed: None,
+                });
+            }
+        }
+    }
+
+    TxScriptData {
+        tx_hash: tx_hash.to_string(),
+        scripts,
+        redeemers,
+        datums,
+        collateral_inputs: array("collateral_inputs").iter().map(input_ref).collect(),
+        collateral_output: tx_obj.get("collateral_output").filter(|o| !o.is_null()).map(|o| CollateralOutput {
+            address: str_field(o, "address"),
+            lovelace: u64_field(o, "amount"),
+        }),
+    }
+}
+
+/// Hashes of the transactions in a block that failed phase-2 validation
+pub fn invalid_txs(block: &MultiEraBlock) -> Vec<String> {
+    block
+        .txs()
+        .iter()
+        .filter(|tx| !tx.is_valid())
+        .map(|tx| tx.hash().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pallas_codec::minicbor;
+
+    #[test]
+    fn test_data_to_json() {
+        // Constr 0 [42, h'cafe', [1], {1: -1}]
+        let cbor = hex::decode("d8799f182a42cafe9f01ffa10120ff").unwrap();
+        let data: PlutusData = minicbor::decode(&cbor).unwrap();
+        assert_eq!(
+            data_to_json(&data),
+            json!({
+                "constructor": 0,
+                "fields": [
+                    {"int": 42},
+                    {"bytes": "cafe"},
+                    {"list": [{"int": 1}]},
+                    {"map": [{"k": {"int": 1}, "v": {"int": -1}}]},
+                ]
+            })
+        );
+        assert_eq!(constructor_index(1280, None), 7);
+        assert_eq!(constructor_index(102, Some(200)), 200);
+    }
+
+    #[test]
+    fn test_big_ints() {
+        // 2^64 as a positive and negative bignum
+        let big = BigInt::BigUInt(vec![1, 0, 0, 0, 0, 0, 0, 0, 0].into());
+        assert_eq!(big_int_to_json(&big), json!("18446744073709551616"));
+        let negative = BigInt::BigNInt(vec![0xff; 8].into());
+        assert_eq!(big_int_to_json(&negative), json!("-18446744073709551616"));
+    }
+
+    #[test]
+    fn test_script_data_from_record() {
+        let script = "aa".repeat(28);
+        let policy = "bb".repeat(28);
+        let record = json!({
+            "hash": "cc".repeat(32),
+            "mint": [{ "policy": policy, "asset": "", "quantity": 1 }],
+            "native_witnesses": [{ "policy_id": policy, "script_json": { "type": "sig" } }],
+            "plutus_witnesses": [{ "script_hash": script, "script_hex": "4e4d01000033222220051200120011" }],
+            "plutus_redeemers": [
+                { "purpose": "spend", "input_idx": 0, "ex_units_mem": 1000, "ex_units_steps": 5000, "plutus_data": { "int": 1 } },
+                { "purpose": "mint", "input_idx": 0, "ex_units_mem": 10, "ex_units_steps": 50, "plutus_data": { "int": 2 } }
+            ],
+            "plutus_data": [{ "datum_hash": "dd".repeat(32), "plutus_data": { "bytes": "" } }],
+            "outputs": [
+                { "address": "addr_test1", "amount": 2000000, "inline_datum": { "datum_hash": "ee".repeat(32), "plutus_data": { "int": 3 } } },
+                { "address": "addr_test1", "amount": 2000000, "datum_hash": "dd".repeat(32) },
+                { "address": "addr_test1", "amount": 2000000, "datum_hash": "ff".repeat(32) }
+            ],
+            "collateral_inputs": [{ "tx_id": "11".repeat(32), "index": 2 }],
+            "collateral_output": { "address": "addr_test1", "amount": 4000000 }
+        });
+        let data = script_data_from_record(&record, &"cc".repeat(32), &KnownCredentials::default());
+
+        assert_eq!(data.scripts.len(), 2);
+        assert_eq!(data.scripts[1].size, Some(15));
+        // the spent output is not known, so no guess at the lone witness; mint resolves to its policy
+        assert_eq!(data.redeemers[0].script_hash, None);
+        assert_eq!(data.redeemers[1].script_hash.as_deref(), Some(policy.as_str()));
+        let sources: Vec<&str> = data.datums.iter().map(|d| d.source.as_str()).collect();
+        assert_eq!(sources, ["witness", "inline", "hash"]);
+        assert_eq!(data.datums[2].output_index, Some(2));
+        assert_eq!(data.collateral_inputs, vec![InputRef { tx_hash: "11".repeat(32), index: 2 }]);
+        assert_eq!(data.collateral_output.unwrap().lovelace, 4000000);
+
+        assert!(script_data_from_record(&json!({ "hash": "00" }), "00", &KnownCredentials::default()).is_empty());
+
+        // an output at a script address records which script its datum is for
+        let record = json!({ "outputs": [{
+            "address": "addr_test1wrphkx6acpnf78fuvxn0mkew3l0fd058hzquvz7w36x4gtcl6szpr",
+            "amount": 2000000,
+            "datum_hash": "ff".repeat(32)
+        }] });
+        let datum = &script_data_from_record(&record, "00", &KnownCredentials::default()).datums[0];
+        assert_eq!(datum.script_hash.as_deref(), Some("c37b1b5dc0669f1d3c61a6fddb2e8fde96be87b881c60bce8e8d542f"));
+    }
+
+    #[test]
+    fn test_redeemer_scripts() {
+        let (script, reference_script, cert_script) = ("aa".repeat(28), "bb".repeat(28), "cc".repeat(28));
+        // the second input in ledger order is locked by a reference script, not the witness
+        let record = json!({
+            "inputs": [{ "tx_id": "22".repeat(32), "index": 0 }, { "tx_id": "11".repeat(32), "index": 5 }, { "tx_id": "11".repeat(32), "index": 1 }],
+            "withdrawals": [
+                { "reward_account": format!("e0{}", "00".repeat(28)), "coin": 1 },
+                { "reward_account": format!("f0{}", "ff".repeat(28)), "coin": 1 }
+            ],
+            "plutus_witnesses": [{ "script_hash": script, "script_hex": "4e4d" }],
+            "plutus_redeemers": [
+                { "purpose": "spend", "input_idx": 1, "plutus_data": {} },
+                { "purpose": "spend", "input_idx": 2, "plutus_data": {} },
+                { "purpose": "reward", "input_idx": 0, "plutus_data": {} },
+                { "purpose": "cert", "input_idx": 0, "plutus_data": {} },
+                { "purpose": "cert", "input_idx": 1, "plutus_data": {} }
+            ]
+        });
+        let mut known = KnownCredentials::default();
+        known.spent.insert(("11".repeat(32), 5), reference_script.clone());
+        known.certs.insert(0, cert_script.clone());
+        let hashes: Vec<Option<String>> =
+            script_data_from_record(&record, "00", &known).redeemers.into_iter().map(|r| r.script_hash).collect();
+        assert_eq!(hashes, vec![Some(reference_script), None, Some("ff".repeat(28)), Some(cert_script), None]);
+    }
+
+    #[test]
+    fn test_decode_data() {
+        let (hash, json) = decode_data("d87980").unwrap();
+        assert_eq!(json, json!({ "constructor": 0, "fields": [] }));
+        assert_eq!(hash, "923918e403bf43c34b4ef6b48eb2ee04babed17320d8d1b9ff9ad086e86f44ec");
+        assert!(decode_data("zz").is_err());
+    }
+}
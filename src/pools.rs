@@ -0,0 +1,91 @@
+//stake pool ids ra block producer header fields
+use bech32::{Bech32, Hrp};
+use pallas_traverse::{MultiEraBlock, MultiEraHeader};
+
+use crate::models::ProtocolVersion;
+
+/// Bech32 pool id (`pool1...`) from a 28 byte pool key hash
+pub fn pool_hash_to_bech32(pool_hash: &str) -> Option<String> {
+    let bytes = hex::decode(pool_hash).ok()?;
+    if bytes.len() != 28 {
+        return None;
+    }
+    let hrp = Hrp::parse("pool").ok()?;
+    bech32::encode::<Bech32>(hrp, &bytes).ok()
+}
+
+/// Pool id of the block issuer: blake2b-224 of the cold verification key
+pub fn pool_id_from_vkey(issuer_vkey: &str) -> Option<String> {
+    let vkey = hex::decode(issuer_vkey).ok()?;
+    if vkey.len() != 32 {
+        return None;
+    }
+    let hash = pallas_crypto::hash::Hasher::<224>::hash(&vkey);
+    pool_hash_to_bech32(&hex::encode(hash))
+}
+
+/// Accepts `pool1...` or the hex pool hash and returns the bech32 form
+pub fn normalize_pool_id(id: &str) -> Option<String> {
+    let id = id.trim().to_lowercase();
+    if id.starts_with("pool1") {
+        let (hrp, data) = bech32::decode(&id).ok()?;
+        if hrp.as_str() != "pool" || data.len() != 28 {
+            return None;
+        }
+        return Some(id);
+    }
+    pool_hash_to_bech32(&id)
+}
+
+/// Header fields that are not part of oura's JSON block record
+#[derive(Debug, Clone)]
+pub struct HeaderInfo {
+    pub vrf_output: Option<String>,
+    pub protocol_version: Option<ProtocolVersion>,
+    pub op_cert_counter: Option<u64>,
+}
+
+pub fn header_info(block: &MultiEraBlock) -> HeaderInfo {
+    match block.header() {
+        MultiEraHeader::ShelleyCompatible(header) => {
+            let body = &header.header_body;
+            HeaderInfo {
+                vrf_output: Some(hex::encode(body.leader_vrf.0.as_slice())),
+                protocol_version: Some(ProtocolVersion { major: body.protocol_major, minor: body.protocol_minor }),
+                op_cert_counter: Some(body.operational_cert_sequence_number),
+            }
+        }
+        MultiEraHeader::BabbageCompatible(header) => {
+            let body = &header.header_body;
+            let (major, minor) = body.protocol_version;
+            HeaderInfo {
+                vrf_output: Some(hex::encode(body.vrf_result.0.as_slice())),
+                protocol_version: Some(ProtocolVersion { major, minor }),
+                op_cert_counter: Some(body.operational_cert.operational_cert_sequence_number),
+            }
+        }
+        _ => HeaderInfo { vrf_output: None, protocol_version: None, op_cert_counter: None },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_id_encoding() {
+        let hash = "0f292fcaa02b8b2f9b3c8f9fd8e0bb21abedb692a6d5058df3ef2735";
+        let bech = "pool1pu5jlj4q9w9jlxeu370a3c9myx47md5j5m2str0naunn2q3lkdy";
+
+        assert_eq!(pool_hash_to_bech32(hash).as_deref(), Some(bech));
+        assert_eq!(normalize_pool_id(bech).as_deref(), Some(bech));
+        assert_eq!(normalize_pool_id(hash).as_deref(), Some(bech));
+        assert!(normalize_pool_id("pool1xyz").is_none());
+    }
+
+    #[test]
+    fn test_pool_id_from_vkey() {
+        assert!(pool_id_from_vkey(&"00".repeat(32)).unwrap().starts_with("pool1"));
+        assert!(pool_id_from_vkey("abcd").is_none());
+    }
+}
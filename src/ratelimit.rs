@@ -0,0 +1,318 @@
+//rate limits per client ip and per authenticated address with route budgets, and the per ip cap on live connections
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    Error, HttpRequest, ResponseError,
+};
+use governor::clock::{Clock, DefaultClock};
+use governor::{DefaultKeyedRateLimiter, Quota};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::auth::jwt::JwtService;
+use crate::error::ApiError;
+use crate::metrics::METRICS;
+
+// Client ip from Forwarded / X-Forwarded-For, only behind a proxy that sets them (clients could otherwise pick their own)
+static TRUST_FORWARDED: Lazy<bool> = Lazy::new(|| {
+    std::env::var("RATE_LIMIT_TRUST_FORWARDED").ok().and_then(|v| v.parse().ok()).unwrap_or(false)
+});
+// Open websocket, graphql subscription and sse connections per client ip, 0 for no cap
+static LIVE_PER_IP: Lazy<usize> = Lazy::new(|| {
+    std::env::var("LIVE_CONNECTIONS_PER_IP").ok().and_then(|v| v.parse().ok()).unwrap_or(10)
+});
+// Retry-After of a refused live connection, there is no telling when another one closes
+const LIVE_RETRY_AFTER: u64 = 30;
+
+/// Route budgets, each limiting every client separately
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    Default, //every route not listed below
+    Auth,    //challenge, login and refresh: signature verification and token minting
+    User,    //authenticated routes, /api/v1/user/* fans out into Blockfrost calls
+    History, //user transactions, up to 40 Blockfrost calls per request
+    Submit,  //transaction submission to the node
+}
+
+const BUDGETS: [Budget; 5] = [Budget::Default, Budget::Auth, Budget::User, Budget::History, Budget::Submit];
+
+impl Budget {
+    pub fn name(self) -> &'static str {
+        match self {
+            Budget::Default => "default",
+            Budget::Auth => "auth",
+            Budget::User => "user",
+            Budget::History => "history",
+            Budget::Submit => "submit",
+        }
+    }
+
+    fn env(self) -> &'static str {
+        match self {
+            Budget::Default => "RATE_LIMIT_DEFAULT",
+            Budget::Auth => "RATE_LIMIT_AUTH",
+            Budget::User => "RATE_LIMIT_USER",
+            Budget::History => "RATE_LIMIT_HISTORY",
+            Budget::Submit => "RATE_LIMIT_SUBMIT",
+        }
+    }
+
+    fn default_rate(self) -> &'static str {
+        match self {
+            Budget::Default => "600/min",
+            Budget::Auth => "20/min",
+            Budget::User => "60/min",
+            Budget::History => "6/min",
+            Budget::Submit => "10/min",
+        }
+    }
+
+    /// Budget of a versioned path, None for the probes and metrics which are never limited
+    pub fn of(path: &str) -> Option<Budget> {
+        match path {
+            "/metrics" => None,
+            _ if path.starts_with("/health") => None,
+            "/api/v1/auth/challenge" | "/api/v1/auth/login" | "/api/v1/auth/refresh" => Some(Budget::Auth),
+            "/api/v1/user/transactions" => Some(Budget::History),
+            _ if path.starts_with("/api/v1/auth/") || path.starts_with("/api/v1/user/") => Some(Budget::User),
+            "/api/v1/transactions/submit" => Some(Budget::Submit),
+            _ => Some(Budget::Default),
+        }
+    }
+}
+
+/// `count` requests per `period`, refilled evenly over it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    pub count: u32,
+    pub period: Duration,
+}
+
+/// "20/min" (or /s, /h), "off" for no limit
+pub fn parse_rate(spec: &str) -> Result<Option<Rate>, String> {
+    let spec = spec.trim();
+    if spec.eq_ignore_ascii_case("off") {
+        return Ok(None);
+    }
+    let (count, unit) = spec.split_once('/').ok_or_else(|| format!("expected <count>/<s|min|h>, got {}", spec))?;
+    let count: u32 = count.trim().parse().map_err(|_| format!("invalid count in {}", spec))?;
+    if count == 0 {
+        return Err(format!("count must be at least 1 in {}, use off to disable", spec));
+    }
+    let period = match unit.trim() {
+        "s" | "sec" | "second" => Duration::from_secs(1),
+        "m" | "min" | "minute" => Duration::from_secs(60),
+        "h" | "hour" => Duration::from_secs(3600),
+        other => return Err(format!("unknown unit {} in {}", other, spec)),
+    };
+    Ok(Some(Rate { count, period }))
+}
+
+struct Limiter {
+    keys: DefaultKeyedRateLimiter<String>,
+}
+
+impl Limiter {
+    fn new(rate: Rate) -> Self {
+        let burst = NonZeroU32::new(rate.count).expect("count checked by parse_rate");
+        let quota = Quota::with_period(rate.period / rate.count).expect("period above zero").allow_burst(burst);
+        Self { keys: DefaultKeyedRateLimiter::keyed(quota) }
+    }
+
+    // Whole seconds until `key` has budget again, rounded up
+    fn check(&self, key: &str) -> Result<(), u64> {
+        self.keys.check_key(&key.to_string()).map_err(|not_until| {
+            let wait = not_until.wait_time_from(DefaultClock::default().now());
+            (wait.as_millis() as u64).div_ceil(1000).max(1)
+        })
+    }
+}
+
+static LIMITERS: Lazy<Vec<(Budget, Option<Limiter>)>> = Lazy::new(|| {
+    BUDGETS
+        .iter()
+        .map(|&budget| {
+            let configured = std::env::var(budget.env()).ok();
+            let rate = match configured.as_deref().map(parse_rate) {
+                Some(Ok(rate)) => rate,
+                Some(Err(e)) => {
+                    tracing::warn!("Ignoring {}: {}", budget.env(), e);
+                    parse_rate(budget.default_rate()).expect("valid default rate")
+                }
+                None => parse_rate(budget.default_rate()).expect("valid default rate"),
+            };
+            (budget, rate.map(Limiter::new))
+        })
+        .collect()
+});
+
+fn limiter(budget: Budget) -> Option<&'static Limiter> {
+    LIMITERS.iter().find(|(b, _)| *b == budget).and_then(|(_, limiter)| limiter.as_ref())
+}
+
+/// Forgets clients that are back to a full budget, keeps the key sets from growing
+pub fn prune() {
+    for limiter in LIMITERS.iter().filter_map(|(_, limiter)| limiter.as_ref()) {
+        limiter.keys.retain_recent();
+        limiter.keys.shrink_to_fit();
+    }
+}
+
+pub fn client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    let forwarded = TRUST_FORWARDED
+        .then(|| req.connection_info().realip_remote_addr().and_then(parse_ip))
+        .flatten();
+    forwarded.or_else(|| req.peer_addr().map(|addr| addr.ip()))
+}
+
+fn parse_ip(host: &str) -> Option<IpAddr> {
+    host.parse::<IpAddr>().ok().or_else(|| host.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+// IPv6 clients get a whole /64, so one counts as one client
+fn client_key(ip: Option<IpAddr>) -> String {
+    match ip.map(|ip| ip.to_canonical()) {
+        Some(IpAddr::V6(v6)) => {
+            let s = v6.segments();
+            format!("{:x}:{:x}:{:x}:{:x}::/64", s[0], s[1], s[2], s[3])
+        }
+        Some(IpAddr::V4(v4)) => v4.to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+// Address of a valid bearer token, revocation is left to the auth middleware
+fn bearer_address(req: &ServiceRequest) -> Option<String> {
+    let header = req.headers().get("Authorization")?.to_str().ok()?;
+    let token = JwtService::extract_token_from_header(header)?;
+    JwtService::verify_token(&token).ok().map(|claims| claims.sub)
+}
+
+/// Charges the request to its client ip and, with a valid bearer token, to the wallet
+/// address too, answering 429 with Retry-After when either is out of budget
+pub async fn limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    if let Some(budget) = Budget::of(req.path()) {
+        if let Some(limiter) = limiter(budget) {
+            let mut keys = vec![("ip", format!("ip:{}", client_key(client_ip(req.request()))))];
+            if let Some(address) = bearer_address(&req) {
+                keys.push(("address", format!("address:{}", address)));
+            }
+            for (kind, key) in keys {
+                if let Err(retry_after) = limiter.check(&key) {
+                    METRICS.rate_limited.with_label_values(&[budget.name(), kind]).inc();
+                    tracing::warn!(budget = budget.name(), key = kind, retry_after, "Rate limited");
+                    let error = ApiError::RateLimited {
+                        message: format!("Too many requests, retry in {} s", retry_after),
+                        retry_after,
+                    };
+                    return Ok(req.into_response(error.error_response()).map_into_right_body());
+                }
+            }
+        }
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+static LIVE: Lazy<Mutex<HashMap<String, usize>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// One of the live connections a client ip may hold, released when dropped
+pub struct LiveSlot {
+    key: String,
+}
+
+fn acquire(key: String, max: usize) -> Option<LiveSlot> {
+    let mut open = LIVE.lock().unwrap_or_else(|e| e.into_inner());
+    let count = open.entry(key.clone()).or_insert(0);
+    if max > 0 && *count >= max {
+        return None;
+    }
+    *count += 1;
+    Some(LiveSlot { key })
+}
+
+impl Drop for LiveSlot {
+    fn drop(&mut self) {
+        let mut open = LIVE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = open.get_mut(&self.key) {
+            *count -= 1;
+            if *count == 0 {
+                open.remove(&self.key);
+            }
+        }
+    }
+}
+
+/// Slot for a new websocket, graphql subscription or sse connection, 429 past LIVE_CONNECTIONS_PER_IP
+pub fn live_slot(req: &HttpRequest, transport: &'static str) -> Result<LiveSlot, ApiError> {
+    acquire(client_key(client_ip(req)), *LIVE_PER_IP).ok_or_else(|| {
+        METRICS.live_rejected.with_label_values(&[transport]).inc();
+        ApiError::RateLimited {
+            message: format!("At most {} live connections per client", *LIVE_PER_IP),
+            retry_after: LIVE_RETRY_AFTER,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("20/min"), Ok(Some(Rate { count: 20, period: Duration::from_secs(60) })));
+        assert_eq!(parse_rate(" 5 / s "), Ok(Some(Rate { count: 5, period: Duration::from_secs(1) })));
+        assert_eq!(parse_rate("OFF"), Ok(None));
+        assert!(parse_rate("0/min").is_err());
+        assert!(parse_rate("20").is_err());
+        assert!(parse_rate("20/day").is_err());
+    }
+
+    #[test]
+    fn test_budget_of() {
+        assert_eq!(Budget::of("/health/ready"), None);
+        assert_eq!(Budget::of("/metrics"), None);
+        assert_eq!(Budget::of("/api/v1/auth/login"), Some(Budget::Auth));
+        assert_eq!(Budget::of("/api/v1/auth/me"), Some(Budget::User));
+        assert_eq!(Budget::of("/api/v1/user/balance"), Some(Budget::User));
+        assert_eq!(Budget::of("/api/v1/user/transactions"), Some(Budget::History));
+        assert_eq!(Budget::of("/api/v1/transactions/submit"), Some(Budget::Submit));
+        assert_eq!(Budget::of("/api/v1/blocks"), Some(Budget::Default));
+    }
+
+    #[test]
+    fn test_client_key() {
+        assert_eq!(client_key(parse_ip("203.0.113.7:5123")), "203.0.113.7");
+        assert_eq!(client_key(parse_ip("2001:db8:1:2:aaaa::1")), "2001:db8:1:2::/64");
+        assert_eq!(client_key(parse_ip("::ffff:203.0.113.7")), "203.0.113.7");
+        assert_eq!(client_key(None), "unknown");
+    }
+
+    #[test]
+    fn test_limiter() {
+        let limiter = Limiter::new(Rate { count: 2, period: Duration::from_secs(60) });
+        assert!(limiter.check("ip:a").is_ok());
+        assert!(limiter.check("ip:a").is_ok());
+        // refills one request every 30 seconds
+        let retry_after = limiter.check("ip:a").unwrap_err();
+        assert!((29..=30).contains(&retry_after));
+        assert!(limiter.check("ip:b").is_ok());
+    }
+
+    #[test]
+    fn test_live_slots() {
+        let first = acquire("test-live".to_string(), 2).unwrap();
+        let _second = acquire("test-live".to_string(), 2).unwrap();
+        assert!(acquire("test-live".to_string(), 2).is_none());
+        drop(first);
+        assert!(acquire("test-live".to_string(), 2).is_some());
+        assert!(acquire("test-live-unlimited".to_string(), 0).is_some());
+    }
+}
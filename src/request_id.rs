@@ -0,0 +1,61 @@
+//request ids: taken from X-Request-Id or generated, echoed on every response
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+    Error,
+};
+
+pub const HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Id of the request being served, None outside a request
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+// Client supplied ids are kept when they are short and printable, so they can be traced end to end
+fn sane(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 128 && id.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:".contains(c))
+}
+
+pub async fn assign(req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let id = req
+        .headers()
+        .get(HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| sane(id))
+        .map(String::from)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let mut res = REQUEST_ID.scope(id.clone(), next.call(req)).await?;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        res.headers_mut().insert(HeaderName::from_static(HEADER), value);
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sane_request_ids() {
+        assert!(sane("4f9c2a1e-7d3b-4c55-9a0e-1b2c3d4e5f60"));
+        assert!(sane("frontend:1234.5"));
+        assert!(!sane(""));
+        assert!(!sane("has space"));
+        assert!(!sane(&"a".repeat(129)));
+    }
+
+    #[tokio::test]
+    async fn test_current_in_scope() {
+        assert!(current().is_none());
+        let id = REQUEST_ID.scope("abc".to_string(), async { current() }).await;
+        assert_eq!(id.as_deref(), Some("abc"));
+    }
+}
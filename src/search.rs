@@ -0,0 +1,292 @@
+//universal search: classify a pasted identifier and resolve it
+use serde::Serialize;
+use utoipa::ToSchema;
+use serde_json::Value;
+
+use once_cell::sync::Lazy;
+use pallas_addresses::Address;
+use std::str::FromStr;
+
+use crate::{blockfrost, metrics};
+use crate::oura_stream::BlockChainState;
+use crate::pools::normalize_pool_id;
+
+// Policy id ADA Handles are minted under
+pub static ADA_HANDLE_POLICY: Lazy<String> = Lazy::new(|| {
+    std::env::var("ADA_HANDLE_POLICY")
+        .unwrap_or_else(|_| "f0ff48bbb7bbe9d59a40f1ce90e9e9d0ff5002ec48f232b49ca0fb9a".to_string())
+});
+
+// CIP-68 (222) asset name prefix of newer handles
+const HANDLE_CIP68_PREFIX: &str = "000de140";
+
+/// What a search query looks like, before it is resolved
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryKind {
+    Number(u64),          //block number or slot
+    Hash32(String),       //block hash or tx hash
+    Hash28(String),       //pool hash or policy id
+    Address(String),      //addr... / addr_test... / byron base58
+    StakeAddress(String), //stake... / stake_test...
+    PoolId(String),       //pool1...
+    Fingerprint(String),  //asset1...
+    Handle(String),       //$handle
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultType {
+    Block,
+    Transaction,
+    Address,
+    StakeAddress,
+    Pool,
+    Asset,
+    Policy,
+    Handle,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Explorer,
+    Blockfrost,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SearchResult {
+    #[serde(rename = "type")]
+    pub result_type: ResultType,
+    pub source: Source,
+    pub url: Option<String>, //canonical api url, None when there is no endpoint for it yet
+    pub data: Value,
+}
+
+fn is_hex(s: &str) -> bool {
+    s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn bech32_hrp(s: &str) -> Option<String> {
+    bech32::decode(s).ok().map(|(hrp, _)| hrp.to_lowercase())
+}
+
+pub fn classify(query: &str) -> QueryKind {
+    let query = query.trim();
+    if let Some(handle) = query.strip_prefix('$') {
+        let handle = handle.to_lowercase();
+        let valid = !handle.is_empty()
+            && handle.len() <= 15
+            && handle.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+        return if valid { QueryKind::Handle(handle) } else { QueryKind::Unknown };
+    }
+    if !query.is_empty() && query.chars().all(|c| c.is_ascii_digit()) {
+        return query.parse().map(QueryKind::Number).unwrap_or(QueryKind::Unknown);
+    }
+
+    // byron base58, bech32 is never valid base58 since it contains 0 or l
+    if let Ok(Address::Byron(_)) = Address::from_str(query) {
+        return QueryKind::Address(query.to_string());
+    }
+
+    let lower = query.to_lowercase();
+    if is_hex(&lower) {
+        return match lower.len() {
+            64 => QueryKind::Hash32(lower),
+            56 => QueryKind::Hash28(lower),
+            _ => QueryKind::Unknown,
+        };
+    }
+
+    match bech32_hrp(&lower).as_deref() {
+        Some("addr") | Some("addr_test") => QueryKind::Address(lower),
+        Some("stake") | Some("stake_test") => QueryKind::StakeAddress(lower),
+        Some("pool") => QueryKind::PoolId(lower),
+        Some("asset") => QueryKind::Fingerprint(lower),
+        _ => QueryKind::Unknown,
+    }
+}
+
+fn explorer(result_type: ResultType, url: Option<String>, data: impl Serialize) -> SearchResult {
+    SearchResult {
+        result_type,
+        source: Source::Explorer,
+        url,
+        data: serde_json::to_value(data).unwrap_or(Value::Null),
+    }
+}
+
+fn provider(result_type: ResultType, url: Option<String>, data: Value) -> SearchResult {
+    SearchResult { result_type, source: Source::Blockfrost, url, data }
+}
+
+// Blockfrost lookup that treats a missing key or a failed request as no result
+async fn lookup(path: &str) -> Option<Value> {
+    let key = blockfrost::api_key()?;
+    match blockfrost::find(path, &key).await {
+        Ok(found) => found,
+        Err(e) => {
+            tracing::warn!("Search lookup {} failed: {}", metrics::blockfrost_endpoint(path), e);
+            None
+        }
+    }
+}
+
+fn block_url(hash: &str) -> Option<String> {
+    Some(format!("/api/v1/blocks/{}", hash))
+}
+
+fn stake_url(stake_address: &str) -> Option<String> {
+    Some(format!("/api/v1/stake/{}", stake_address))
+}
+
+/// Resolve a query against the explorer state first and Blockfrost second
+pub async fn resolve(state: &BlockChainState, kind: QueryKind) -> Vec<SearchResult> {
+    let mut results = Vec::new();
+
+    match kind {
+        QueryKind::Number(n) => {
+            match state.get_block_by_number(n).await {
+                Some(block) => results.push(explorer(ResultType::Block, block_url(&block.hash), block)),
+                None => {
+                    if let Some(block) = lookup(&format!("/blocks/{}", n)).await {
+                        let url = block.get("hash").and_then(|h| h.as_str()).and_then(block_url);
+                        results.push(provider(ResultType::Block, url, block));
+                    }
+                }
+            }
+            match state.get_block_by_slot(n).await {
+                Some(block) => results.push(explorer(ResultType::Block, block_url(&block.hash), block)),
+                None => {
+                    if let Some(block) = lookup(&format!("/blocks/slot/{}", n)).await {
+                        let url = block.get("hash").and_then(|h| h.as_str()).and_then(block_url);
+                        results.push(provider(ResultType::Block, url, block));
+                    }
+                }
+            }
+            // same block found by number and by slot
+            results.dedup_by(|a, b| a.url.is_some() && a.url == b.url);
+        }
+        QueryKind::Hash32(hash) => {
+            let block_url = block_url(&hash);
+            let tx_url = Some(format!("/api/v1/transactions/{}", hash));
+            if let Some(block) = state.get_block_by_hash(&hash).await {
+                results.push(explorer(ResultType::Block, block_url, block));
+            } else if let Some(tx) = state.get_transaction(&hash).await {
+                results.push(explorer(ResultType::Transaction, tx_url, tx));
+            } else if let Some(block) = lookup(&format!("/blocks/{}", hash)).await {
+                results.push(provider(ResultType::Block, block_url, block));
+            } else if let Some(tx) = lookup(&format!("/txs/{}", hash)).await {
+                results.push(provider(ResultType::Transaction, tx_url, tx));
+            }
+        }
+        QueryKind::Hash28(hash) => {
+            if let Some(pool_id) = normalize_pool_id(&hash) {
+                results.extend(resolve_pool(state, pool_id).await);
+            }
+            let url = Some(format!("/api/v1/assets/policy/{}", hash));
+            let assets = state.get_policy_assets(&hash).await;
+            if !assets.is_empty() {
+                let data = serde_json::json!({ "policy_id": hash, "assets": assets.len() });
+                results.push(explorer(ResultType::Policy, url, data));
+            } else if let Some(assets) = lookup(&format!("/assets/policy/{}?count=1", hash)).await {
+                if assets.as_array().is_some_and(|a| !a.is_empty()) {
+                    let data = serde_json::json!({ "policy_id": hash, "assets": assets });
+                    results.push(provider(ResultType::Policy, url, data));
+                }
+            }
+        }
+        QueryKind::PoolId(pool_id) => {
+            results.extend(resolve_pool(state, pool_id).await);
+        }
+        QueryKind::Address(address) => {
+            let url = Some(format!("/api/v1/addresses/{}/info", address));
+            if let Some(info) = lookup(&format!("/addresses/{}", address)).await {
+                results.push(provider(ResultType::Address, url, info));
+            }
+        }
+        QueryKind::StakeAddress(stake_address) => {
+            if let Some(account) = lookup(&format!("/accounts/{}", stake_address)).await {
+                results.push(provider(ResultType::StakeAddress, stake_url(&stake_address), account));
+            }
+        }
+        QueryKind::Fingerprint(fingerprint) => {
+            // Blockfrost has no fingerprint index, only assets seen on chain resolve
+            if let Some(asset) = state.get_asset(&fingerprint).await {
+                let url = Some(format!("/api/v1/assets/{}", asset.unit));
+                results.push(explorer(ResultType::Asset, url, asset));
+            }
+        }
+        QueryKind::Handle(handle) => {
+            if let Some(result) = resolve_handle(&handle).await {
+                results.push(result);
+            }
+        }
+        QueryKind::Unknown => {}
+    }
+    results
+}
+
+async fn resolve_pool(state: &BlockChainState, pool_id: String) -> Option<SearchResult> {
+    let url = Some(format!("/api/v1/pools/{}/blocks", pool_id));
+    if state.has_pool(&pool_id).await {
+        let data = serde_json::json!({
+            "pool_id": pool_id,
+            "epochs": state.get_pool_epoch_counts(&pool_id).await,
+        });
+        return Some(explorer(ResultType::Pool, url, data));
+    }
+    let pool = lookup(&format!("/pools/{}", pool_id)).await?;
+    Some(provider(ResultType::Pool, url, pool))
+}
+
+// Address currently holding a handle, legacy (CIP-25) or CIP-68 asset name
+async fn resolve_handle(handle: &str) -> Option<SearchResult> {
+    let name = hex::encode(handle.as_bytes());
+    for asset_name in [name.clone(), format!("{}{}", HANDLE_CIP68_PREFIX, name)] {
+        let unit = format!("{}{}", *ADA_HANDLE_POLICY, asset_name);
+        let holders = match lookup(&format!("/assets/{}/addresses", unit)).await {
+            Some(holders) => holders,
+            None => continue,
+        };
+        let address = holders
+            .as_array()
+            .and_then(|h| h.first())
+            .and_then(|h| h.get("address"))
+            .cloned();
+        if let Some(address) = address {
+            let data = serde_json::json!({ "handle": handle, "unit": unit, "address": address });
+            return Some(provider(ResultType::Handle, Some(format!("/api/v1/assets/{}", unit)), data));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(" 1234 "), QueryKind::Number(1234));
+        assert_eq!(classify(&"AB".repeat(32)), QueryKind::Hash32("ab".repeat(32)));
+        assert_eq!(classify(&"0f".repeat(28)), QueryKind::Hash28("0f".repeat(28)));
+        assert_eq!(classify(&"0f".repeat(20)), QueryKind::Unknown);
+        assert_eq!(classify("$Charles"), QueryKind::Handle("charles".to_string()));
+        assert_eq!(classify("$bad handle"), QueryKind::Unknown);
+
+        let pool = "pool1pu5jlj4q9w9jlxeu370a3c9myx47md5j5m2str0naunn2q3lkdy";
+        assert_eq!(classify(pool), QueryKind::PoolId(pool.to_string()));
+        let fingerprint = "asset1rjklcrnsdzqp65wjgrg55sy9723kw09mlgvlc3";
+        assert_eq!(classify(fingerprint), QueryKind::Fingerprint(fingerprint.to_string()));
+        let stake = "stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw";
+        assert_eq!(classify(stake), QueryKind::StakeAddress(stake.to_string()));
+        assert_eq!(stake_url(stake), Some(format!("/api/v1/stake/{}", stake)));
+        let addr = "addr_test1vz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzerspjrlsz";
+        assert_eq!(classify(addr), QueryKind::Address(addr.to_string()));
+        let byron = "Ae2tdPwUPEZFRbyhz3cpfC2CumGzNkFBN2L42rcUc2yjQpEkxDbkPodpMAi";
+        assert_eq!(classify(byron), QueryKind::Address(byron.to_string()));
+        // checksum must be valid
+        assert_eq!(classify("pool1pu5jlj4q9w9jlxeu370a3c9myx47md5j5m2str0naunn2q3lkdx"), QueryKind::Unknown);
+    }
+}
@@ -0,0 +1,125 @@
+//coordinated shutdown: stop accepting, close live connections, stop oura, persist state, all within a deadline
+use actix_web::dev::Server;
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use crate::auth::SessionStore;
+use crate::oura_stream::BlockChainState;
+use crate::snapshot;
+
+// Seconds from the signal to exit, in flight requests and oura get at most this long
+pub static DEADLINE: Lazy<Duration> = Lazy::new(|| {
+    Duration::from_secs(std::env::var("SHUTDOWN_TIMEOUT").ok().and_then(|v| v.parse().ok()).unwrap_or(30))
+});
+// Polling interval while waiting for the oura child to exit
+const SOURCE_POLL: Duration = Duration::from_millis(100);
+
+static REQUESTED: Lazy<watch::Sender<bool>> = Lazy::new(|| watch::channel(false).0);
+// Pid of the running oura child, 0 when there is none
+static SOURCE_PID: AtomicU32 = AtomicU32::new(0);
+
+/// Tells live connections and background loops to finish
+pub fn begin() {
+    REQUESTED.send_replace(true);
+}
+
+pub fn is_requested() -> bool {
+    *REQUESTED.borrow()
+}
+
+/// Resolves once shutdown has begun, for select! branches of long running tasks
+pub async fn requested() {
+    let mut rx = REQUESTED.subscribe();
+    let _ = rx.wait_for(|requested| *requested).await;
+}
+
+pub fn set_source_pid(pid: u32) {
+    SOURCE_PID.store(pid, Ordering::SeqCst);
+}
+
+/// Called once the child was reaped, so its pid is never signalled after reuse
+pub fn clear_source_pid() {
+    SOURCE_PID.store(0, Ordering::SeqCst);
+}
+
+#[cfg(unix)]
+fn signal_source(pid: u32, signal: libc::c_int) {
+    // SAFETY: kill has no memory effects, the pid is our own unreaped child
+    if unsafe { libc::kill(pid as libc::pid_t, signal) } != 0 {
+        tracing::warn!("Failed to signal oura ({}): {}", pid, std::io::Error::last_os_error());
+    }
+}
+
+/// SIGTERM to oura, SIGKILL when it is still running after `grace`
+pub async fn stop_source(grace: Duration) {
+    let pid = SOURCE_PID.load(Ordering::SeqCst);
+    if pid == 0 {
+        return;
+    }
+    tracing::info!("Stopping oura ({})", pid);
+    #[cfg(unix)]
+    signal_source(pid, libc::SIGTERM);
+
+    let exited = tokio::time::timeout(grace, async {
+        while SOURCE_PID.load(Ordering::SeqCst) != 0 {
+            tokio::time::sleep(SOURCE_POLL).await;
+        }
+    })
+    .await;
+    if exited.is_err() {
+        tracing::warn!("Oura did not exit within {:?}, killing it", grace);
+        #[cfg(unix)]
+        signal_source(pid, libc::SIGKILL);
+    }
+}
+
+// SIGTERM from the orchestrator, or Ctrl-C
+async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                _ = terminate.recv() => tracing::info!("Received SIGTERM"),
+                _ = tokio::signal::ctrl_c() => tracing::info!("Received SIGINT"),
+            },
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Runs the server until a signal, then shuts down in order. The server must be built
+/// with `disable_signals()` and a `shutdown_timeout` of the deadline
+pub async fn run(server: Server, state: Arc<BlockChainState>, sessions: Arc<SessionStore>) -> std::io::Result<()> {
+    let handle = server.handle();
+    let mut server = actix_web::rt::spawn(server);
+    tokio::select! {
+        result = &mut server => return result.map_err(std::io::Error::other)?,
+        _ = signal() => {}
+    }
+
+    let started = Instant::now();
+    tracing::info!("Shutting down, deadline {}s", DEADLINE.as_secs());
+    // websocket, graphql and sse streams close first, the server waits for open connections
+    begin();
+    let stopping = async {
+        // stop accepting connections, in flight requests finish until the deadline
+        tokio::join!(handle.stop(true), stop_source(*DEADLINE / 2));
+        snapshot::save(&state, &sessions).await;
+    };
+    if tokio::time::timeout(*DEADLINE, stopping).await.is_err() {
+        tracing::warn!("Shutdown deadline passed, exiting anyway");
+        // state goes to disk even when requests were still running
+        snapshot::save(&state, &sessions).await;
+    }
+    tracing::info!("Shutdown complete in {:.1}s", started.elapsed().as_secs_f64());
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//state kept across restarts: the last chain point and the wallet sessions, as json files in STATE_DIR
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::auth::{SessionSnapshot, SessionStore};
+use crate::oura_stream::BlockChainState;
+
+pub static STATE_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    std::env::var("STATE_DIR").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("data"))
+});
+
+const CHAIN_POINT_FILE: &str = "chain_point.json";
+const SESSIONS_FILE: &str = "sessions.json";
+
+/// Newest block ingested before shutdown, oura resumes reading from it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainPoint {
+    pub slot: u64,
+    pub hash: String,
+    pub block_number: u64,
+}
+
+// Written next to the target and renamed over it, a crash mid write leaves the old file
+fn write_json<T: Serialize>(dir: &Path, name: &str, value: &T) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let target = dir.join(name);
+    let partial = dir.join(format!("{}.tmp", name));
+    let json = serde_json::to_vec_pretty(value).map_err(|e| format!("Failed to encode {}: {}", name, e))?;
+    std::fs::write(&partial, json).map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+    std::fs::rename(&partial, &target).map_err(|e| format!("Failed to replace {}: {}", target.display(), e))
+}
+
+// Missing files are a first start, unreadable ones are logged and ignored
+fn read_json<T: DeserializeOwned>(dir: &Path, name: &str) -> Option<T> {
+    let path = dir.join(name);
+    let raw = std::fs::read(&path).ok()?;
+    match serde_json::from_slice(&raw) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::warn!("Ignoring {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+pub fn save_chain_point(dir: &Path, point: &ChainPoint) -> Result<(), String> {
+    write_json(dir, CHAIN_POINT_FILE, point)
+}
+
+pub fn load_chain_point(dir: &Path) -> Option<ChainPoint> {
+    read_json(dir, CHAIN_POINT_FILE)
+}
+
+pub fn save_sessions(dir: &Path, sessions: &SessionSnapshot) -> Result<(), String> {
+    write_json(dir, SESSIONS_FILE, sessions)
+}
+
+pub fn load_sessions(dir: &Path) -> Option<SessionSnapshot> {
+    read_json(dir, SESSIONS_FILE)
+}
+
+/// Writes the tip and the session store to STATE_DIR, failures are logged
+pub async fn save(state: &BlockChainState, sessions: &SessionStore) {
+    let dir = STATE_DIR.as_path();
+    if let Some(tip) = state.get_blocks(1).await.into_iter().next() {
+        let point = ChainPoint { slot: tip.slot, hash: tip.hash, block_number: tip.number };
+        match save_chain_point(dir, &point) {
+            Ok(()) => tracing::info!("Saved chain point #{} at slot {}", point.block_number, point.slot),
+            Err(e) => tracing::error!("{}", e),
+        }
+    }
+    let snapshot = sessions.snapshot().await;
+    match save_sessions(dir, &snapshot) {
+        Ok(()) => tracing::info!(
+            "Saved {} sessions and {} revoked tokens",
+            snapshot.sessions.len(),
+            snapshot.revoked_tokens.len()
+        ),
+        Err(e) => tracing::error!("{}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let dir = std::env::temp_dir().join(format!("explorer-snapshot-{}", uuid::Uuid::new_v4()));
+        assert!(load_chain_point(&dir).is_none());
+
+        let point = ChainPoint { slot: 42, hash: "ab".repeat(32), block_number: 7 };
+        save_chain_point(&dir, &point).unwrap();
+        assert_eq!(load_chain_point(&dir), Some(point.clone()));
+
+        let store = SessionStore::new();
+        store.create_session("addr_test1".to_string()).await;
+        store.revoked_token("jti-1".to_string()).await;
+        save_sessions(&dir, &store.snapshot().await).unwrap();
+
+        let restored = SessionStore::new();
+        restored.restore(load_sessions(&dir).unwrap()).await;
+        assert!(restored.get_session("addr_test1").await.is_some());
+        assert!(restored.is_token_revoked("jti-1").await);
+
+        std::fs::write(dir.join(CHAIN_POINT_FILE), "not json").unwrap();
+        assert!(load_chain_point(&dir).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,247 @@
+//rolling network statistics over sliding windows
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::collections::VecDeque;
+
+use once_cell::sync::Lazy;
+
+// Longest window, samples older than this are dropped
+const MAX_WINDOW: u64 = 86400;
+
+// Windows reported by the stats api: (name, seconds)
+pub const WINDOWS: [(&str, u64); 3] = [("10m", 600), ("1h", 3600), ("1d", 86400)];
+
+// Current max block body size protocol parameter
+pub static MAX_BLOCK_BODY_SIZE: Lazy<u64> = Lazy::new(|| {
+    std::env::var("MAX_BLOCK_BODY_SIZE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(90112)
+});
+
+#[derive(Debug, Clone, Copy)]
+struct BlockSample {
+    block_number: u64,
+    timestamp: u64,
+    body_size: u64,
+    tx_count: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TxSample {
+    block_number: u64,
+    timestamp: u64,
+    fee: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Default, PartialEq, ToSchema)]
+pub struct FeeStats {
+    pub min: u64,
+    pub max: u64,
+    pub average: f64,
+    pub median: u64,
+    pub p90: u64,
+    pub p99: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct WindowStats {
+    pub window: &'static str,
+    pub seconds: u64,
+    pub blocks: usize,
+    pub transactions: usize,
+    pub tps: f64,
+    pub fees: FeeStats,
+    pub average_block_size: f64,
+    pub block_fullness: f64, //average body size / max block body size
+    pub empty_block_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct StatsSnapshot {
+    pub as_of: u64, //timestamp of the latest block, windows end here
+    pub max_block_size: u64,
+    pub windows: Vec<WindowStats>,
+}
+
+#[derive(Default)]
+pub struct NetworkStats {
+    blocks: VecDeque<BlockSample>,
+    transactions: VecDeque<TxSample>,
+}
+
+// nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], pct: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl NetworkStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_block(&mut self, block_number: u64, timestamp: u64, body_size: u64, tx_count: u32) {
+        self.blocks.push_back(BlockSample { block_number, timestamp, body_size, tx_count });
+        self.prune(timestamp);
+    }
+
+    pub fn record_transaction(&mut self, block_number: u64, timestamp: u64, fee: u64) {
+        self.transactions.push_back(TxSample { block_number, timestamp, fee });
+    }
+
+    /// Drops the samples of rolled back blocks and their transactions
+    pub fn remove_blocks(&mut self, block_numbers: &[u64]) {
+        self.blocks.retain(|b| !block_numbers.contains(&b.block_number));
+        self.transactions.retain(|t| !block_numbers.contains(&t.block_number));
+    }
+
+    /// Drops one sample of a rolled back transaction, its block stays
+    pub fn remove_transaction(&mut self, block_number: u64, fee: u64) {
+        if let Some(index) = self.transactions.iter().rposition(|t| t.block_number == block_number && t.fee == fee) {
+            self.transactions.remove(index);
+        }
+    }
+
+    fn prune(&mut self, latest: u64) {
+        let cutoff = latest.saturating_sub(MAX_WINDOW);
+        while self.blocks.front().is_some_and(|b| b.timestamp < cutoff) {
+            self.blocks.pop_front();
+        }
+        while self.transactions.front().is_some_and(|t| t.timestamp < cutoff) {
+            self.transactions.pop_front();
+        }
+    }
+
+    fn window(&self, name: &'static str, seconds: u64, as_of: u64, max_block_size: u64) -> WindowStats {
+        let start = as_of.saturating_sub(seconds);
+        let blocks: Vec<&BlockSample> = self.blocks.iter().filter(|b| b.timestamp > start).collect();
+        let mut fees: Vec<u64> = self
+            .transactions
+            .iter()
+            .filter(|t| t.timestamp > start)
+            .map(|t| t.fee)
+            .collect();
+        fees.sort_unstable();
+
+        let block_count = blocks.len();
+        let average_block_size = if block_count == 0 {
+            0.0
+        } else {
+            blocks.iter().map(|b| b.body_size).sum::<u64>() as f64 / block_count as f64
+        };
+        let empty_blocks = blocks.iter().filter(|b| b.tx_count == 0).count();
+        // until the window has filled only the time since the oldest sample was observed
+        let observed = self.blocks.front().map(|b| as_of.saturating_sub(b.timestamp)).unwrap_or(0).min(seconds);
+
+        WindowStats {
+            window: name,
+            seconds,
+            blocks: block_count,
+            transactions: fees.len(),
+            tps: if observed == 0 { 0.0 } else { fees.len() as f64 / observed as f64 },
+            fees: if fees.is_empty() {
+                FeeStats::default()
+            } else {
+                FeeStats {
+                    min: fees[0],
+                    max: fees[fees.len() - 1],
+                    average: fees.iter().sum::<u64>() as f64 / fees.len() as f64,
+                    median: percentile(&fees, 50.0),
+                    p90: percentile(&fees, 90.0),
+                    p99: percentile(&fees, 99.0),
+                }
+            },
+            average_block_size,
+            block_fullness: if max_block_size == 0 { 0.0 } else { average_block_size / max_block_size as f64 },
+            empty_block_rate: if block_count == 0 { 0.0 } else { empty_blocks as f64 / block_count as f64 },
+        }
+    }
+
+    pub fn snapshot(&self, max_block_size: u64) -> StatsSnapshot {
+        let as_of = self.blocks.back().map(|b| b.timestamp).unwrap_or(0);
+        StatsSnapshot {
+            as_of,
+            max_block_size,
+            windows: WINDOWS
+                .iter()
+                .map(|(name, seconds)| self.window(name, *seconds, as_of, max_block_size))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let values = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        assert_eq!(percentile(&values, 50.0), 5);
+        assert_eq!(percentile(&values, 90.0), 9);
+        assert_eq!(percentile(&values, 99.0), 10);
+        assert_eq!(percentile(&[], 50.0), 0);
+    }
+
+    #[test]
+    fn test_windows() {
+        let mut stats = NetworkStats::new();
+        // one old block outside the 10 minute window
+        stats.record_block(1, 1000, 1000, 1);
+        stats.record_transaction(1, 1000, 500);
+        stats.record_block(2, 2000, 0, 0);
+        stats.record_block(3, 2020, 2000, 2);
+        stats.record_transaction(3, 2020, 100);
+        stats.record_transaction(3, 2020, 300);
+
+        let snapshot = stats.snapshot(4000);
+        assert_eq!(snapshot.as_of, 2020);
+
+        let ten_minutes = &snapshot.windows[0];
+        assert_eq!(ten_minutes.blocks, 2);
+        assert_eq!(ten_minutes.transactions, 2);
+        assert_eq!(ten_minutes.fees.median, 100);
+        assert_eq!(ten_minutes.fees.max, 300);
+        assert_eq!(ten_minutes.empty_block_rate, 0.5);
+        assert_eq!(ten_minutes.block_fullness, 0.25);
+        assert_eq!(ten_minutes.tps, 2.0 / 600.0);
+
+        let hour = &snapshot.windows[1];
+        assert_eq!(hour.blocks, 3);
+        assert_eq!(hour.transactions, 3);
+        // samples only go back 1020 seconds
+        assert_eq!(hour.tps, 3.0 / 1020.0);
+        assert_eq!(snapshot.windows[2].tps, 3.0 / 1020.0);
+    }
+
+    #[test]
+    fn test_tps_single_block() {
+        let mut stats = NetworkStats::new();
+        stats.record_block(1, 1000, 1000, 1);
+        stats.record_transaction(1, 1000, 500);
+        assert_eq!(stats.snapshot(4000).windows[0].tps, 0.0);
+    }
+
+    #[test]
+    fn test_remove_samples() {
+        let mut stats = NetworkStats::new();
+        stats.record_block(1, 1000, 1000, 1);
+        stats.record_transaction(1, 1000, 500);
+        stats.record_block(2, 1020, 2000, 2);
+        stats.record_transaction(2, 1020, 100);
+        stats.record_transaction(2, 1020, 300);
+
+        stats.remove_transaction(2, 300);
+        let hour = &stats.snapshot(4000).windows[1];
+        assert_eq!((hour.blocks, hour.transactions, hour.fees.max), (2, 2, 500));
+
+        stats.remove_blocks(&[2]);
+        let snapshot = stats.snapshot(4000);
+        assert_eq!(snapshot.as_of, 1000);
+        assert_eq!((snapshot.windows[1].blocks, snapshot.windows[1].transactions), (1, 1));
+    }
+}
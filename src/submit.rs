@@ -0,0 +1,167 @@
+//transaction submission through a local node or a submit api
+use once_cell::sync::Lazy;
+use pallas_network::facades::NodeClient;
+use pallas_network::miniprotocols::localtxsubmission::{EraTx, Response};
+use pallas_traverse::{Era, MultiEraTx};
+
+use crate::network::NETWORK;
+
+/// Where submitted transactions are forwarded to
+#[derive(Debug, Clone)]
+pub enum SubmitTarget {
+    Node { socket: String, magic: u64 },
+    Api { url: String }, //cardano-submit-api compatible, e.g. http://localhost:8090/api/submit/tx
+}
+
+impl SubmitTarget {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubmitTarget::Node { .. } => "node",
+            SubmitTarget::Api { .. } => "submit_api",
+        }
+    }
+}
+
+// SUBMIT_API_URL wins over CARDANO_NODE_SOCKET_PATH, None disables submission
+pub static SUBMIT_TARGET: Lazy<Option<SubmitTarget>> = Lazy::new(|| {
+    if let Ok(url) = std::env::var("SUBMIT_API_URL") {
+        return Some(SubmitTarget::Api { url });
+    }
+    let socket = std::env::var("CARDANO_NODE_SOCKET_PATH").ok()?;
+    let magic = std::env::var("CARDANO_NODE_MAGIC")
+        .ok()
+        .and_then(|m| m.parse().ok())
+        .unwrap_or(NETWORK.magic);
+    Some(SubmitTarget::Node { socket, magic })
+});
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubmitError {
+    Rejected(String),    //the node or submit api refused the transaction
+    Unavailable(String), //could not reach it
+}
+
+impl std::fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubmitError::Rejected(reason) => write!(f, "Transaction rejected: {}", reason),
+            SubmitError::Unavailable(reason) => write!(f, "Submission unavailable: {}", reason),
+        }
+    }
+}
+
+/// A decodable transaction ready to submit
+#[derive(Debug, Clone)]
+pub struct ValidTx {
+    pub hash: String,
+    pub era: Era,
+    pub cbor: Vec<u8>,
+}
+
+pub fn validate(cbor_hex: &str) -> Result<ValidTx, String> {
+    let cbor = hex::decode(cbor_hex.trim()).map_err(|e| format!("Invalid hex: {}", e))?;
+    let tx = MultiEraTx::decode(&cbor).map_err(|e| format!("Failed to decode transaction: {}", e))?;
+    if tx.vkey_witnesses().is_empty() && tx.bootstrap_witnesses().is_empty() {
+        return Err("Transaction is not signed".to_string());
+    }
+    Ok(ValidTx { hash: tx.hash().to_string(), era: tx.era(), cbor })
+}
+
+// Hard fork combinator index of an era (Byron 0 .. Conway 6), the block
+// wrapper tag pallas converts to is one higher since it counts the EBB
+fn era_index(era: Era) -> u16 {
+    u16::from(era).saturating_sub(1)
+}
+
+pub async fn submit(target: &SubmitTarget, tx: &ValidTx) -> Result<(), SubmitError> {
+    match target {
+        SubmitTarget::Node { socket, magic } => submit_to_node(socket, *magic, tx).await,
+        SubmitTarget::Api { url } => submit_to_api(url, &tx.cbor).await,
+    }
+}
+
+async fn submit_to_node(socket: &str, magic: u64, tx: &ValidTx) -> Result<(), SubmitError> {
+    let mut client = NodeClient::connect(socket, magic)
+        .await
+        .map_err(|e| SubmitError::Unavailable(format!("Node connection failed: {}", e)))?;
+
+    let response = client
+        .submission()
+        .submit_tx(EraTx(era_index(tx.era), tx.cbor.clone()))
+        .await;
+    client.abort().await;
+
+    match response {
+        Ok(Response::Accepted) => Ok(()),
+        Ok(Response::Rejected(reason)) => Err(SubmitError::Rejected(hex::encode(reason.0))),
+        Err(e) => Err(SubmitError::Unavailable(format!("Local tx submission failed: {}", e))),
+    }
+}
+
+async fn submit_to_api(url: &str, cbor: &[u8]) -> Result<(), SubmitError> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .header("Content-Type", "application/cbor")
+        .body(cbor.to_vec())
+        .send()
+        .await
+        .map_err(|e| SubmitError::Unavailable(format!("Submit api request failed: {}", e)))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    if status.is_client_error() {
+        Err(SubmitError::Rejected(body))
+    } else {
+        Err(SubmitError::Unavailable(format!("Submit api error: {} {}", status, body)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Stand-in submit api answering every request with the given status line
+    async fn stand_in(status: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let body = "\"ok\"";
+                let reply = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+                let _ = socket.write_all(reply.as_bytes()).await;
+            }
+        });
+        format!("http://{}/api/submit/tx", addr)
+    }
+
+    #[tokio::test]
+    async fn test_submit_api() {
+        let accepted = SubmitTarget::Api { url: stand_in("202 Accepted").await };
+        let tx = ValidTx { hash: String::new(), era: Era::Conway, cbor: vec![0x84] };
+        assert_eq!(submit(&accepted, &tx).await, Ok(()));
+
+        let rejected = SubmitTarget::Api { url: stand_in("400 Bad Request").await };
+        assert!(matches!(submit(&rejected, &tx).await, Err(SubmitError::Rejected(_))));
+    }
+
+    #[test]
+    fn test_era_index() {
+        assert_eq!(era_index(Era::Byron), 0);
+        assert_eq!(era_index(Era::Babbage), 5);
+        assert_eq!(era_index(Era::Conway), 6);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("zz").is_err());
+        // decodable but without witnesses
+        let body = format!("a30081825820{}00018182581d60{}1a000f4240021a00030d40", "11".repeat(32), "22".repeat(28));
+        assert_eq!(validate(&format!("84{}a0f5f6", body)).unwrap_err(), "Transaction is not signed");
+    }
+}
@@ -0,0 +1,280 @@
+//structured logs, tracing spans with OpenTelemetry export, and redaction of addresses and tokens in them
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::HeaderMap,
+    middleware::Next,
+    Error,
+};
+use once_cell::sync::Lazy;
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::trace::{TraceContextExt, TracerProvider};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::time::Instant;
+use tracing::{field, Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::request_id;
+
+const SERVICE_NAME: &str = "cardano-explorer-backend";
+
+/// How much of an address or token ends up in logs and span fields
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Redaction {
+    Off,     //logged as is
+    Partial, //addresses keep their head and tail, tokens their last 4 characters
+    Full,    //replaced by a placeholder
+}
+
+impl Redaction {
+    fn parse(level: &str) -> Option<Self> {
+        match level.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" => Some(Redaction::Off),
+            "partial" => Some(Redaction::Partial),
+            "full" => Some(Redaction::Full),
+            _ => None,
+        }
+    }
+
+    pub fn address(self, address: &str) -> String {
+        // long enough that head and tail still leave most of it hidden
+        const HEAD: usize = 12;
+        const TAIL: usize = 6;
+        match self {
+            Redaction::Off => address.to_string(),
+            Redaction::Partial if address.chars().count() > 3 * (HEAD + TAIL) / 2 => {
+                let chars: Vec<char> = address.chars().collect();
+                let head: String = chars[..HEAD].iter().collect();
+                let tail: String = chars[chars.len() - TAIL..].iter().collect();
+                format!("{}…{}", head, tail)
+            }
+            _ => "[address]".to_string(),
+        }
+    }
+
+    pub fn token(self, token: &str) -> String {
+        match self {
+            Redaction::Off => token.to_string(),
+            Redaction::Partial if token.chars().count() > 16 => {
+                let chars: Vec<char> = token.chars().collect();
+                format!("…{}", chars[chars.len() - 4..].iter().collect::<String>())
+            }
+            _ => "[token]".to_string(),
+        }
+    }
+
+    /// Request path with address and stake address segments redacted, the query only when redaction is off
+    pub fn target(self, path: &str, query: &str) -> String {
+        let path = path
+            .split('/')
+            .map(|segment| if looks_like_address(segment) { self.address(segment) } else { segment.to_string() })
+            .collect::<Vec<_>>()
+            .join("/");
+        match self {
+            Redaction::Off if !query.is_empty() => format!("{}?{}", path, query),
+            _ => path,
+        }
+    }
+}
+
+fn looks_like_address(segment: &str) -> bool {
+    segment.len() > 20 && (segment.starts_with("addr") || segment.starts_with("stake"))
+}
+
+pub static REDACTION: Lazy<Redaction> = Lazy::new(|| {
+    std::env::var("LOG_REDACTION").ok().and_then(|v| Redaction::parse(&v)).unwrap_or(Redaction::Partial)
+});
+
+/// Address as it may appear in logs, per LOG_REDACTION
+pub fn redact_address(address: &str) -> String {
+    REDACTION.address(address)
+}
+
+/// Token (or token id) as it may appear in logs, per LOG_REDACTION
+pub fn redact_token(token: &str) -> String {
+    REDACTION.token(token)
+}
+
+/// Flushes exported spans on shutdown
+pub struct Telemetry {
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    pub fn shutdown(self) {
+        if let Some(provider) = self.tracer_provider {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush traces: {}", e);
+            }
+        }
+    }
+}
+
+// OTLP over http/protobuf, to OTEL_EXPORTER_OTLP_ENDPOINT (or the traces specific variable)
+fn tracer_provider() -> Option<SdkTracerProvider> {
+    let configured = ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"]
+        .iter()
+        .any(|var| std::env::var(var).is_ok_and(|v| !v.is_empty()));
+    if !configured {
+        return None;
+    }
+    let exporter = match opentelemetry_otlp::SpanExporter::builder().with_http().build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("Trace export disabled, failed to build the OTLP exporter: {}", e);
+            return None;
+        }
+    };
+    let service_name = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| SERVICE_NAME.to_string());
+    Some(
+        SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(opentelemetry_sdk::Resource::builder().with_service_name(service_name).build())
+            .build(),
+    )
+}
+
+/// Installs the subscriber: RUST_LOG filter, JSON (or LOG_FORMAT=text) output with the span
+/// list on every line, `log` records bridged in, spans exported when an OTLP endpoint is set
+pub fn init() -> Telemetry {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let output = match std::env::var("LOG_FORMAT").as_deref() {
+        Ok("text") => tracing_subscriber::fmt::layer().with_ansi(std::io::stdout().is_terminal()).boxed(),
+        _ => tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(true)
+            .boxed(),
+    };
+
+    let tracer_provider = tracer_provider();
+    let export = tracer_provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)));
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    tracing_subscriber::registry().with(output).with(export).with(filter).init();
+    if tracer_provider.is_some() {
+        tracing::info!("Exporting traces over OTLP");
+    }
+    Telemetry { tracer_provider }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+// Trace id of the span once the exporter gave it one, logs and traces are joined on it
+fn record_trace_id(span: &Span) {
+    let context = span.context();
+    let span_context = context.span().span_context().clone();
+    if span_context.is_valid() {
+        span.record("trace_id", field::display(span_context.trace_id()));
+    }
+}
+
+/// W3C trace context of the current span, as headers for an outgoing request
+pub fn trace_headers() -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&Span::current().context(), &mut headers)
+    });
+    headers
+}
+
+/// Span around each request, continuing the caller's trace from `traceparent`, with the
+/// request id on it and the completed request logged by route pattern, status and latency
+pub async fn trace_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let span = tracing::info_span!(
+        "http_request",
+        otel.kind = "server",
+        otel.status_code = field::Empty,
+        http.method = %req.method(),
+        http.route = field::Empty,
+        http.target = %REDACTION.target(req.path(), req.query_string()),
+        http.status_code = field::Empty,
+        client.address = req.connection_info().realip_remote_addr().unwrap_or("-"),
+        request_id = request_id::current().as_deref().unwrap_or("-"),
+        trace_id = field::Empty,
+    );
+    let parent = TraceContextPropagator::new().extract(&HeaderExtractor(req.headers()));
+    if parent.span().span_context().is_valid() {
+        let _ = span.set_parent(parent);
+    }
+    record_trace_id(&span);
+
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let res = next.call(req).instrument(span.clone()).await;
+
+    let (route, status) = match &res {
+        Ok(res) => (res.request().match_pattern(), res.status()),
+        Err(e) => (None, e.as_response_error().status_code()),
+    };
+    let route = route.unwrap_or_else(|| "unmatched".to_string());
+    // the exported span has started by now, so it is renamed rather than given otel.name
+    span.context().span().update_name(format!("{} {}", method, route));
+    span.record("http.route", route.as_str());
+    span.record("http.status_code", status.as_u16());
+    if status.is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+    span.in_scope(|| {
+        tracing::info!(
+            status = status.as_u16(),
+            duration_ms = started.elapsed().as_secs_f64() * 1000.0,
+            "request completed"
+        )
+    });
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "addr_test1qz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgs68faae";
+
+    #[test]
+    fn test_redact_address() {
+        assert_eq!(Redaction::Off.address(ADDRESS), ADDRESS);
+        assert_eq!(Redaction::Partial.address(ADDRESS), "addr_test1qz…68faae");
+        assert_eq!(Redaction::Partial.address("addr_test1short"), "[address]");
+        assert_eq!(Redaction::Full.address(ADDRESS), "[address]");
+    }
+
+    #[test]
+    fn test_redact_token() {
+        assert_eq!(Redaction::Partial.token("eyJhbGciOiJIUzI1NiJ9.payload.sig1"), "…sig1");
+        assert_eq!(Redaction::Partial.token("short"), "[token]");
+        assert_eq!(Redaction::Full.token("eyJhbGciOiJIUzI1NiJ9.payload.sig1"), "[token]");
+    }
+
+    #[test]
+    fn test_redact_target() {
+        let path = format!("/api/v1/addresses/{}", ADDRESS);
+        assert_eq!(Redaction::Partial.target(&path, "limit=5"), "/api/v1/addresses/addr_test1qz…68faae");
+        assert_eq!(Redaction::Full.target("/api/v1/blocks/42", "q=x"), "/api/v1/blocks/42");
+        assert_eq!(Redaction::Off.target(&path, "limit=5"), format!("{}?limit=5", path));
+        assert_eq!(Redaction::parse(" FULL"), Some(Redaction::Full));
+        assert_eq!(Redaction::parse("bogus"), None);
+    }
+}
@@ -0,0 +1,297 @@
+//slot <-> posix time <-> epoch conversion from genesis parameters
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::network::NETWORK;
+
+/// Era history of a network: a byron era followed by shelley-based eras
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkTime {
+    pub network: String,
+    pub system_start: u64, //posix seconds
+    pub byron_slot_length: u64, //milliseconds
+    pub byron_epoch_length: u64, //slots
+    pub shelley_start_epoch: u64,
+    pub shelley_slot_length: u64, //milliseconds, genesis files allow fractions of a second
+    pub shelley_epoch_length: u64, //slots
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct SlotInfo {
+    pub slot: u64,
+    pub epoch: u64,
+    pub slot_in_epoch: u64,
+    pub epoch_length: u64,
+    pub timestamp: u64,
+    pub epoch_start: u64,
+    pub epoch_end: u64,
+}
+
+pub static NETWORK_TIME: Lazy<NetworkTime> = Lazy::new(|| {
+    let mut time = NETWORK.time();
+
+    if let Ok(path) = std::env::var("BYRON_GENESIS") {
+        if let Err(e) = time.load_byron_genesis(&path) {
+            tracing::error!("Failed to load byron genesis {}: {}", path, e);
+        }
+    }
+    if let Ok(path) = std::env::var("SHELLEY_GENESIS") {
+        if let Err(e) = time.load_shelley_genesis(&path) {
+            tracing::error!("Failed to load shelley genesis {}: {}", path, e);
+        }
+    }
+    if let Some(epoch) = std::env::var("SHELLEY_TRANSITION_EPOCH").ok().and_then(|e| e.parse().ok()) {
+        time.shelley_start_epoch = epoch;
+    }
+    time
+});
+
+impl NetworkTime {
+    pub fn mainnet() -> Self {
+        Self {
+            network: "mainnet".to_string(),
+            system_start: 1506203091,
+            byron_slot_length: 20000,
+            byron_epoch_length: 21600,
+            shelley_start_epoch: 208,
+            shelley_slot_length: 1000,
+            shelley_epoch_length: 432000,
+        }
+    }
+
+    pub fn preprod() -> Self {
+        Self {
+            network: "preprod".to_string(),
+            system_start: 1654041600,
+            byron_slot_length: 20000,
+            byron_epoch_length: 21600,
+            shelley_start_epoch: 4,
+            shelley_slot_length: 1000,
+            shelley_epoch_length: 432000,
+        }
+    }
+
+    pub fn preview() -> Self {
+        Self {
+            network: "preview".to_string(),
+            system_start: 1666656000,
+            byron_slot_length: 20000,
+            byron_epoch_length: 4320,
+            shelley_start_epoch: 0,
+            shelley_slot_length: 1000,
+            shelley_epoch_length: 86400,
+        }
+    }
+
+    /// startTime, protocolConsts.k (epoch = 10k slots) and blockVersionData.slotDuration (ms).
+    /// Nothing is applied when a value is unusable
+    pub fn load_byron_genesis(&mut self, path: &str) -> Result<(), String> {
+        let genesis = read_json(path)?;
+        let k = genesis.pointer("/protocolConsts/k").and_then(|v| v.as_u64());
+        let ms = genesis
+            .pointer("/blockVersionData/slotDuration")
+            .and_then(|v| v.as_str().and_then(|s| s.parse::<u64>().ok()).or_else(|| v.as_u64()));
+        if k == Some(0) {
+            return Err("protocolConsts.k must be positive".to_string());
+        }
+        if ms == Some(0) {
+            return Err("blockVersionData.slotDuration must be positive".to_string());
+        }
+
+        if let Some(start) = genesis.get("startTime").and_then(|v| v.as_u64()) {
+            self.system_start = start;
+        }
+        if let Some(k) = k {
+            self.byron_epoch_length = k.checked_mul(10).ok_or("protocolConsts.k is too large")?;
+        }
+        if let Some(ms) = ms {
+            self.byron_slot_length = ms;
+        }
+        Ok(())
+    }
+
+    /// epochLength and slotLength (seconds, kept in ms); systemStart only matters when there is
+    /// no byron era. Nothing is applied when a value is unusable
+    pub fn load_shelley_genesis(&mut self, path: &str) -> Result<(), String> {
+        let genesis = read_json(path)?;
+        let epoch_length = genesis.get("epochLength").and_then(|v| v.as_u64());
+        if epoch_length == Some(0) {
+            return Err("epochLength must be positive".to_string());
+        }
+        let slot_length = match genesis.get("slotLength").and_then(|v| v.as_f64()) {
+            Some(seconds) => match (seconds * 1000.0).round() {
+                ms if ms >= 1.0 && ms <= u32::MAX as f64 => Some(ms as u64),
+                _ => return Err(format!("slotLength {} is not a usable number of seconds", seconds)),
+            },
+            None => None,
+        };
+        let start = match genesis.get("systemStart").and_then(|v| v.as_str()) {
+            Some(start) => Some(
+                chrono::DateTime::parse_from_rfc3339(start).map_err(|e| format!("Invalid systemStart: {}", e))?,
+            ),
+            None => None,
+        };
+
+        if let Some(length) = epoch_length {
+            self.shelley_epoch_length = length;
+        }
+        if let Some(ms) = slot_length {
+            self.shelley_slot_length = ms;
+        }
+        if let Some(start) = start {
+            self.system_start = start.timestamp() as u64;
+        }
+        Ok(())
+    }
+
+    pub fn shelley_start_slot(&self) -> u64 {
+        self.shelley_start_epoch.saturating_mul(self.byron_epoch_length)
+    }
+
+    // Milliseconds from the system start to the first shelley slot
+    fn shelley_start_offset(&self) -> Option<u64> {
+        self.shelley_start_slot().checked_mul(self.byron_slot_length)
+    }
+
+    /// Posix seconds of a slot, None past what u64 seconds can hold
+    pub fn try_slot_to_time(&self, slot: u64) -> Option<u64> {
+        let shelley_slot = self.shelley_start_slot();
+        let offset = if slot < shelley_slot {
+            slot.checked_mul(self.byron_slot_length)?
+        } else {
+            (slot - shelley_slot)
+                .checked_mul(self.shelley_slot_length)?
+                .checked_add(self.shelley_start_offset()?)?
+        };
+        self.system_start.checked_add(offset / 1000)
+    }
+
+    /// Posix seconds of a slot seen on chain, saturating where `try_slot_to_time` gives up
+    pub fn slot_to_time(&self, slot: u64) -> u64 {
+        self.try_slot_to_time(slot).unwrap_or(u64::MAX)
+    }
+
+    /// Slot in progress at a posix time, None before the system start or past the last slot
+    pub fn time_to_slot(&self, timestamp: u64) -> Option<u64> {
+        let offset = timestamp.checked_sub(self.system_start)?.checked_mul(1000)?;
+        let shelley_offset = self.shelley_start_offset()?;
+        if offset < shelley_offset {
+            Some(offset / self.byron_slot_length)
+        } else {
+            self.shelley_start_slot().checked_add((offset - shelley_offset) / self.shelley_slot_length)
+        }
+    }
+
+    /// (epoch, slot in epoch)
+    pub fn slot_to_epoch(&self, slot: u64) -> (u64, u64) {
+        let shelley_slot = self.shelley_start_slot();
+        if slot < shelley_slot {
+            (slot / self.byron_epoch_length, slot % self.byron_epoch_length)
+        } else {
+            let offset = slot - shelley_slot;
+            (
+                self.shelley_start_epoch + offset / self.shelley_epoch_length,
+                offset % self.shelley_epoch_length,
+            )
+        }
+    }
+
+    pub fn epoch_first_slot(&self, epoch: u64) -> u64 {
+        if epoch < self.shelley_start_epoch {
+            epoch * self.byron_epoch_length
+        } else {
+            (epoch - self.shelley_start_epoch)
+                .saturating_mul(self.shelley_epoch_length)
+                .saturating_add(self.shelley_start_slot())
+        }
+    }
+
+    pub fn epoch_length(&self, epoch: u64) -> u64 {
+        if epoch < self.shelley_start_epoch {
+            self.byron_epoch_length
+        } else {
+            self.shelley_epoch_length
+        }
+    }
+
+    /// None when the slot or the end of its epoch is past what u64 seconds can hold
+    pub fn slot_info(&self, slot: u64) -> Option<SlotInfo> {
+        let (epoch, slot_in_epoch) = self.slot_to_epoch(slot);
+        let first_slot = self.epoch_first_slot(epoch);
+        let epoch_length = self.epoch_length(epoch);
+        Some(SlotInfo {
+            slot,
+            epoch,
+            slot_in_epoch,
+            epoch_length,
+            timestamp: self.try_slot_to_time(slot)?,
+            epoch_start: self.try_slot_to_time(first_slot)?,
+            epoch_end: self.try_slot_to_time(first_slot.checked_add(epoch_length)?)?,
+        })
+    }
+}
+
+fn read_json(path: &str) -> Result<serde_json::Value, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preprod_conversion() {
+        let time = NetworkTime::preprod();
+        assert_eq!(time.shelley_start_slot(), 86400);
+        // last byron slot and first shelley slot
+        assert_eq!(time.slot_to_time(86399), 1654041600 + 86399 * 20);
+        assert_eq!(time.slot_to_time(86400), 1654041600 + 86400 * 20);
+        assert_eq!(time.slot_to_epoch(86400), (4, 0));
+        assert_eq!(time.slot_to_epoch(86400 + 432000 + 5), (5, 5));
+
+        let slot = 75_000_000;
+        assert_eq!(time.time_to_slot(time.slot_to_time(slot)), Some(slot));
+        assert_eq!(time.time_to_slot(0), None);
+    }
+
+    #[test]
+    fn test_mainnet_conversion() {
+        let time = NetworkTime::mainnet();
+        // first shelley block, epoch 208 started 2020-07-29T21:44:51Z
+        assert_eq!(time.slot_to_time(4492800), 1596059091);
+        assert_eq!(time.slot_to_epoch(4492800), (208, 0));
+        assert_eq!(time.slot_info(4492799).unwrap().epoch_length, 21600);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let time = NetworkTime::preprod();
+        assert_eq!(time.try_slot_to_time(u64::MAX), None);
+        assert_eq!(time.slot_info(u64::MAX), None);
+        assert_eq!(time.time_to_slot(u64::MAX), None);
+        assert_eq!(time.slot_to_time(u64::MAX), u64::MAX);
+        // far off but representable
+        let info = time.slot_info(1 << 50).unwrap();
+        assert!(info.epoch_end > info.timestamp);
+    }
+
+    #[test]
+    fn test_fractional_slot_length() {
+        let path = std::env::temp_dir().join(format!("shelley-genesis-{}.json", uuid::Uuid::new_v4()));
+        let mut time = NetworkTime::preview();
+
+        std::fs::write(&path, r#"{"epochLength": 86400, "slotLength": 0.2}"#).unwrap();
+        time.load_shelley_genesis(path.to_str().unwrap()).unwrap();
+        assert_eq!(time.shelley_slot_length, 200);
+        assert_eq!(time.slot_to_time(10), 1666656000 + 2);
+        assert_eq!(time.time_to_slot(1666656000 + 2), Some(10));
+
+        // a slot length rounding to zero would divide by zero, nothing is applied
+        std::fs::write(&path, r#"{"epochLength": 100, "slotLength": 0.0001}"#).unwrap();
+        assert!(time.load_shelley_genesis(path.to_str().unwrap()).is_err());
+        assert_eq!((time.shelley_slot_length, time.shelley_epoch_length), (200, 86400));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
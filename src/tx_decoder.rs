@@ -0,0 +1,348 @@
+//raw transaction CBOR -> JSON view for the developer tools
+use once_cell::sync::Lazy;
+use pallas_addresses::Address;
+use pallas_primitives::conway::{PseudoScript, RedeemerTag};
+use pallas_primitives::babbage::PseudoDatumOption;
+use pallas_traverse::{ComputeHash, MultiEraInput, MultiEraOutput, MultiEraPolicyAssets, MultiEraTx, OriginalHash};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::certs::{self, Inclusion};
+use crate::governance;
+use crate::metadata;
+use crate::plutus;
+
+/// Fee protocol parameters (txFeePerByte, txFeeFixed, executionUnitPrices)
+#[derive(Debug, Clone, Copy)]
+pub struct FeeParams {
+    pub min_fee_a: u64,
+    pub min_fee_b: u64,
+    pub price_mem: f64,
+    pub price_step: f64,
+}
+
+// Current preprod values unless overridden
+pub static FEE_PARAMS: Lazy<FeeParams> = Lazy::new(|| {
+    fn env<T: std::str::FromStr>(name: &str, default: T) -> T {
+        std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+    }
+    FeeParams {
+        min_fee_a: env("MIN_FEE_A", 44),
+        min_fee_b: env("MIN_FEE_B", 155381),
+        price_mem: env("PRICE_MEM", 0.0577),
+        price_step: env("PRICE_STEP", 0.0000721),
+    }
+});
+
+/// Minimum fee for a tx size and total execution units. Reference script fees are not included
+pub fn min_fee(params: &FeeParams, size: u64, mem: u64, steps: u64) -> u64 {
+    let script_fee = (params.price_mem * mem as f64 + params.price_step * steps as f64).ceil() as u64;
+    params.min_fee_a * size + params.min_fee_b + script_fee
+}
+
+fn input_json(input: &MultiEraInput) -> Value {
+    json!({ "tx_hash": input.hash().to_string(), "index": input.index() })
+}
+
+fn assets_json(policies: &[MultiEraPolicyAssets]) -> Vec<Value> {
+    policies
+        .iter()
+        .flat_map(|policy| {
+            policy.assets().into_iter().map(|asset| {
+                json!({
+                    "policy_id": policy.policy().to_string(),
+                    "asset_name": hex::encode(asset.name()),
+                    "asset_name_ascii": asset.to_ascii_name(),
+                    "quantity": asset.any_coin().to_string(),
+                })
+            })
+        })
+        .collect()
+}
+
+fn output_json(output: &MultiEraOutput) -> Value {
+    let datum = output.datum().map(|datum| match datum {
+        PseudoDatumOption::Hash(hash) => json!({ "hash": hash.to_string() }),
+        PseudoDatumOption::Data(data) => json!({
+            "hash": data.0.original_hash().to_string(),
+            "inline": plutus::data_to_json(&data.0),
+        }),
+    });
+    let script_ref = output.script_ref().map(|script| match script {
+        PseudoScript::NativeScript(s) => json!({
+            "type": "native",
+            "hash": s.original_hash().to_string(),
+            "json": plutus::native_script_to_json(&s),
+        }),
+        PseudoScript::PlutusV1Script(s) => json!({ "type": "plutus_v1", "hash": s.compute_hash().to_string() }),
+        PseudoScript::PlutusV2Script(s) => json!({ "type": "plutus_v2", "hash": s.compute_hash().to_string() }),
+        PseudoScript::PlutusV3Script(s) => json!({ "type": "plutus_v3", "hash": s.compute_hash().to_string() }),
+    });
+
+    let value = output.value();
+    json!({
+        "address": output.address().map(|a| a.to_string()).ok(),
+        "lovelace": value.coin(),
+        "assets": assets_json(&value.assets()),
+        "datum": datum,
+        "script_ref": script_ref,
+    })
+}
+
+// A bare transaction is in no block yet
+const NOT_INCLUDED: Inclusion = Inclusion { block_number: 0, slot: 0, timestamp: 0 };
+
+// Certificate, vote or proposal as ingestion decodes it, without the block fields it has no value for
+fn without_inclusion(item: impl Serialize, block_fields: &[&str]) -> Value {
+    let mut value = serde_json::to_value(item).unwrap_or(Value::Null);
+    if let Some(object) = value.as_object_mut() {
+        for field in block_fields {
+            object.remove(*field);
+        }
+    }
+    value
+}
+
+pub fn redeemer_tag(tag: &RedeemerTag) -> &'static str {
+    match tag {
+        RedeemerTag::Spend => "spend",
+        RedeemerTag::Mint => "mint",
+        RedeemerTag::Cert => "cert",
+        RedeemerTag::Reward => "reward",
+        RedeemerTag::Vote => "vote",
+        RedeemerTag::Propose => "propose",
+    }
+}
+
+/// Decode a signed or unsigned transaction (hex CBOR) of any era
+pub fn decode_tx(cbor_hex: &str) -> Result<Value, String> {
+    let cbor = hex::decode(cbor_hex.trim()).map_err(|e| format!("Invalid hex: {}", e))?;
+    let tx = MultiEraTx::decode(&cbor).map_err(|e| format!("Failed to decode transaction: {}", e))?;
+
+    let withdrawals: Vec<Value> = tx
+        .withdrawals_sorted_set()
+        .into_iter()
+        .map(|(account, amount)| {
+            json!({
+                "stake_address": Address::from_bytes(account).ok().map(|a| a.to_string()),
+                "amount": amount,
+            })
+        })
+        .collect();
+    let required_signers: Vec<String> = tx
+        .required_signers()
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|h| h.to_string())
+        .collect();
+
+    let vkeys: Vec<Value> = tx
+        .vkey_witnesses()
+        .iter()
+        .map(|w| {
+            json!({
+                "vkey": hex::encode(w.vkey.as_slice()),
+                "key_hash": pallas_crypto::hash::Hasher::<224>::hash(w.vkey.as_slice()).to_string(),
+                "signature": hex::encode(w.signature.as_slice()),
+            })
+        })
+        .collect();
+    let bootstrap: Vec<Value> = tx
+        .bootstrap_witnesses()
+        .iter()
+        .map(|w| json!({ "public_key": hex::encode(w.public_key.as_slice()), "signature": hex::encode(w.signature.as_slice()) }))
+        .collect();
+    let native_scripts: Vec<Value> = tx
+        .native_scripts()
+        .iter()
+        .map(|s| json!({ "hash": s.original_hash().to_string(), "json": plutus::native_script_to_json(s) }))
+        .collect();
+
+    let mut plutus_scripts = Vec::new();
+    for s in tx.plutus_v1_scripts() {
+        plutus_scripts.push(json!({ "version": "plutus_v1", "hash": s.compute_hash().to_string(), "size": s.0.len(), "cbor": hex::encode(s.0.as_slice()) }));
+    }
+    for s in tx.plutus_v2_scripts() {
+        plutus_scripts.push(json!({ "version": "plutus_v2", "hash": s.compute_hash().to_string(), "size": s.0.len(), "cbor": hex::encode(s.0.as_slice()) }));
+    }
+    for s in tx.plutus_v3_scripts() {
+        plutus_scripts.push(json!({ "version": "plutus_v3", "hash": s.compute_hash().to_string(), "size": s.0.len(), "cbor": hex::encode(s.0.as_slice()) }));
+    }
+
+    let datums: Vec<Value> = tx
+        .plutus_data()
+        .iter()
+        .map(|d| json!({ "hash": d.original_hash().to_string(), "json": plutus::data_to_json(d) }))
+        .collect();
+
+    let (mut mem, mut steps) = (0u64, 0u64);
+    let redeemers: Vec<Value> = tx
+        .redeemers()
+        .iter()
+        .map(|r| {
+            let units = r.ex_units();
+            mem += units.mem;
+            steps += units.steps;
+            json!({
+                "tag": redeemer_tag(&r.tag()),
+                "index": r.index(),
+                "data": plutus::data_to_json(r.data()),
+                "ex_units": { "mem": units.mem, "steps": units.steps },
+            })
+        })
+        .collect();
+
+    // cbor as sliced from the auxiliary data, a re-encoding could differ from the signed bytes
+    let metadata: Vec<Value> = metadata::tx_metadata(&tx)
+        .into_iter()
+        .map(|(label, datum, cbor)| {
+            json!({
+                "label": label,
+                "json": metadata::to_detailed_json(&datum),
+                "cbor": cbor,
+            })
+        })
+        .collect();
+
+    let (certificates, _) = certs::from_tx(&tx, NOT_INCLUDED);
+    let (proposals, votes) = governance::from_tx(&tx, NOT_INCLUDED, 0);
+    let inclusion = ["tx_hash", "block_number", "slot", "timestamp"];
+    let (treasury_value, donation) = match &tx {
+        MultiEraTx::Conway(conway) => (conway.transaction_body.treasury_value, conway.transaction_body.donation.as_ref().map(u64::from)),
+        _ => (None, None),
+    };
+
+    let size = tx.size() as u64;
+    let params = *FEE_PARAMS;
+    let min_fee = min_fee(&params, size, mem, steps);
+
+    Ok(json!({
+        "hash": tx.hash().to_string(),
+        "era": tx.era().to_string(),
+        "size": size,
+        "valid": tx.is_valid(),
+        "fee": tx.fee(),
+        "min_fee": min_fee,
+        "body": {
+            "inputs": tx.inputs().iter().map(input_json).collect::<Vec<_>>(),
+            "reference_inputs": tx.reference_inputs().iter().map(input_json).collect::<Vec<_>>(),
+            "collateral": tx.collateral().iter().map(input_json).collect::<Vec<_>>(),
+            "collateral_return": tx.collateral_return().as_ref().map(output_json),
+            "total_collateral": tx.total_collateral(),
+            "outputs": tx.outputs().iter().map(output_json).collect::<Vec<_>>(),
+            "ttl": tx.ttl(),
+            "validity_start": tx.validity_start(),
+            "mint": assets_json(&tx.mints()),
+            "withdrawals": withdrawals,
+            "certificates": certificates.iter().map(|c| without_inclusion(c, &inclusion)).collect::<Vec<_>>(),
+            "voting_procedures": votes.iter().map(|v| without_inclusion(v, &inclusion)).collect::<Vec<_>>(),
+            "proposal_procedures": proposals
+                .iter()
+                .map(|p| without_inclusion(p, &["tx_hash", "block_number", "slot", "timestamp", "epoch"]))
+                .collect::<Vec<_>>(),
+            "treasury_value": treasury_value,
+            "donation": donation,
+            "required_signers": required_signers,
+            "network_id": tx.network_id().map(|n| n as u8),
+        },
+        "witnesses": {
+            "vkeys": vkeys,
+            "bootstrap": bootstrap,
+            "native_scripts": native_scripts,
+            "plutus_scripts": plutus_scripts,
+            "datums": datums,
+            "redeemers": redeemers,
+        },
+        "metadata": metadata,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pallas_codec::minicbor;
+
+    #[test]
+    fn test_min_fee() {
+        let params = FeeParams { min_fee_a: 44, min_fee_b: 155381, price_mem: 0.0577, price_step: 0.0000721 };
+        assert_eq!(min_fee(&params, 300, 0, 0), 168581);
+        // 1000 * 0.0577 + 1_000_000 * 0.0000721 = 57.7 + 72.1 = 129.8 -> 130
+        assert_eq!(min_fee(&params, 300, 1000, 1_000_000), 168711);
+    }
+
+    #[test]
+    fn test_decode_tx() {
+        // one input, one enterprise testnet output, empty witness set
+        let body = format!(
+            "a30081825820{}00018182581d60{}1a000f4240021a00030d40",
+            "11".repeat(32),
+            "9493315cd92eb5d8c4304e67b7e16ae36d61d34502694657811a2c8e"
+        );
+        let decoded = decode_tx(&format!("84{}a0f5f6", body)).unwrap();
+
+        let hash = pallas_crypto::hash::Hasher::<256>::hash(&hex::decode(&body).unwrap());
+        assert_eq!(decoded["hash"], json!(hash.to_string()));
+        assert_eq!(decoded["fee"], json!(200000));
+        assert_eq!(decoded["body"]["inputs"][0]["index"], json!(0));
+        assert_eq!(
+            decoded["body"]["outputs"][0]["address"],
+            json!("addr_test1vz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzerspjrlsz")
+        );
+        assert_eq!(decoded["body"]["outputs"][0]["lovelace"], json!(1000000));
+        assert_eq!(decoded["min_fee"], json!(44 * decoded["size"].as_u64().unwrap() + 155381));
+    }
+
+    #[test]
+    fn test_metadata_cbor_as_signed() {
+        let body = format!(
+            "a30081825820{}00018182581d60{}1a000f4240021a00030d40",
+            "11".repeat(32),
+            "9493315cd92eb5d8c4304e67b7e16ae36d61d34502694657811a2c8e"
+        );
+        // {674: 5} with the 5 encoded in three bytes, re-encoding would give "05"
+        let decoded = decode_tx(&format!("84{}a0f5a11902a2190005", body)).unwrap();
+        assert_eq!(decoded["metadata"][0]["label"], json!(674));
+        assert_eq!(decoded["metadata"][0]["cbor"], json!("190005"));
+        assert_eq!(decoded["metadata"][0]["json"], json!({ "int": 5 }));
+    }
+
+    #[test]
+    fn test_conway_body() -> Result<(), minicbor::encode::Error<std::convert::Infallible>> {
+        // certificate, vote, info proposal and a treasury donation
+        let mut e = minicbor::Encoder::new(Vec::new());
+        e.array(4)?.map(7)?;
+        e.u8(0)?.array(1)?.array(2)?.bytes(&[0x11; 32])?.u8(0)?;
+        e.u8(1)?.array(1)?.array(2)?.bytes(&[0x60; 29])?.u64(5_000_000)?;
+        e.u8(2)?.u64(180_000)?;
+        e.u8(4)?.array(1)?.array(2)?.u8(1)?.array(2)?.u8(1)?.bytes(&[0x77; 28])?;
+        e.u8(19)?.map(1)?.array(2)?.u8(2)?.bytes(&[0x33; 28])?;
+        e.map(1)?.array(2)?.bytes(&[0x44; 32])?.u8(0)?.array(2)?.u8(0)?.null()?;
+        e.u8(20)?.array(1)?.array(4)?.u64(100_000_000_000)?.bytes(&[0xe0; 29])?;
+        e.array(1)?.u8(6)?.array(2)?.str("https://example.com/info.json")?.bytes(&[0x22; 32])?;
+        e.u8(22)?.u64(1_000_000)?;
+        e.map(0)?.bool(true)?.null()?;
+        let decoded = decode_tx(&hex::encode(e.into_writer())).unwrap();
+        let body = &decoded["body"];
+
+        let cert = &body["certificates"][0];
+        assert_eq!((cert["kind"].as_str(), cert["cert_index"].as_u64()), (Some("stake_deregistration"), Some(0)));
+        assert!(cert["stake_address"].as_str().unwrap().starts_with("stake_test1"));
+        assert!(cert.get("block_number").is_none());
+
+        let vote = &body["voting_procedures"][0];
+        assert_eq!((vote["voter_role"].as_str(), vote["vote"].as_str()), (Some("drep"), Some("no")));
+        assert_eq!(vote["proposal_id"], json!(format!("{}#0", "44".repeat(32))));
+
+        let proposal = &body["proposal_procedures"][0];
+        assert_eq!((proposal["action"].as_str(), proposal["deposit"].as_u64()), (Some("info"), Some(100_000_000_000)));
+        assert!(proposal.get("epoch").is_none());
+        assert_eq!((body["donation"].as_u64(), body["treasury_value"].as_u64()), (Some(1_000_000), None));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_rejects_garbage() {
+        assert!(decode_tx("zz").is_err());
+        assert!(decode_tx("8201").is_err());
+    }
+}
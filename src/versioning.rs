@@ -0,0 +1,80 @@
+//api versioning: the unversioned /api/... paths are deprecated aliases of /api/v1/...
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        header::{HeaderName, HeaderValue, LINK},
+        Uri,
+    },
+    middleware::Next,
+    Error,
+};
+
+pub const API_V1: &str = "/api/v1";
+
+// RFC 9745 structured date of when the unversioned paths were deprecated (2026-10-19)
+const DEPRECATED_SINCE: &str = "@1792368000";
+
+// Old paths that were renamed in v1 rather than just moved under it
+const RENAMED: [(&str, &str); 3] = [
+    ("/api/transaction", "/api/v1/transactions"),
+    ("/api/tx/submit", "/api/v1/transactions/submit"),
+    ("/api/auth/referesh", "/api/v1/auth/refresh"),
+];
+
+/// v1 path a deprecated path is served by, None for paths that are not aliases
+pub fn successor(path: &str) -> Option<String> {
+    if let Some((_, new)) = RENAMED.iter().find(|(old, _)| *old == path) {
+        return Some(new.to_string());
+    }
+    let rest = path.strip_prefix("/api/")?;
+    if rest == "v1" || rest.starts_with("v1/") {
+        return None;
+    }
+    Some(format!("{}/{}", API_V1, rest))
+}
+
+/// Routes deprecated paths to their v1 handler and marks the response with
+/// `Deprecation` and a `Link` to the successor
+pub async fn deprecated_aliases(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(path) = successor(req.path()) else {
+        return next.call(req).await;
+    };
+    let target = match req.query_string() {
+        "" => path.clone(),
+        query => format!("{}?{}", path, query),
+    };
+    if let Ok(uri) = target.parse::<Uri>() {
+        req.match_info_mut().get_mut().update(&uri);
+        req.head_mut().uri = uri;
+    }
+
+    let mut res = next.call(req).await?;
+    let headers = res.headers_mut();
+    headers.insert(HeaderName::from_static("deprecation"), HeaderValue::from_static(DEPRECATED_SINCE));
+    if let Ok(link) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", path)) {
+        headers.insert(LINK, link);
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_successor_paths() {
+        assert_eq!(successor("/api/blocks/latest").as_deref(), Some("/api/v1/blocks/latest"));
+        assert_eq!(successor("/api/transaction").as_deref(), Some("/api/v1/transactions"));
+        assert_eq!(successor("/api/transactions/abc").as_deref(), Some("/api/v1/transactions/abc"));
+        assert_eq!(successor("/api/auth/referesh").as_deref(), Some("/api/v1/auth/refresh"));
+        assert_eq!(successor("/api/tx/submit").as_deref(), Some("/api/v1/transactions/submit"));
+        assert!(successor("/api/v1/blocks").is_none());
+        assert!(successor("/api/v1").is_none());
+        assert!(successor("/health").is_none());
+        assert!(successor("/ws").is_none());
+    }
+}
@@ -1,28 +1,68 @@
 //websockets
-use actix_ws::Message;
+use actix_ws::{CloseCode, CloseReason, Message};
 use actix_web::{web,Error,HttpRequest,HttpResponse};
 use std::sync::Arc;
 use std::time::{Duration,Instant};
+use tokio::sync::broadcast;
 
+use crate::error::ErrorBody;
+use crate::events::{self, TOPICS};
+use crate::metrics::LiveConnection;
 use crate::oura_stream::BlockChainState;
+use crate::ratelimit;
+use crate::shutdown;
 
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 
+#[utoipa::path(
+    get,
+    path = "/ws",
+    tag = "live",
+    params(("topics" = Option<String>, Query, description = "Comma separated topics to start with, default is every topic")),
+    responses((status = 101, description = "WebSocket upgrade, then chain events as {\"type\", \"data\"} text messages"), (status = 429, description = "Too many live connections from this client", body = ErrorBody)),
+)]
 pub async fn websocket_route(
     req:HttpRequest,
     body:web::Payload,
     state: web::Data<Arc<BlockChainState>>,
 ) -> Result<HttpResponse,Error> {
+    let slot = ratelimit::live_slot(&req, "ws")?;
     let (response, mut session, mut msg_stream)=actix_ws::handle(&req, body)?;
 
     let state = state.get_ref().clone();
+    let mut events = state.subscribe();
+
+    // ?topics=blocks,stats narrows the initial subscription, default is every topic
+    let query = web::Query::<std::collections::HashMap<String,String>>::from_query(req.query_string())
+        .map(|q| q.into_inner())
+        .unwrap_or_default();
+    let mut topics = events::parse_topics(query.get("topics").map(|t| t.as_str()));
     actix_web::rt::spawn(async move{
+        let _slot = slot;
+        let connection = LiveConnection::open("ws");
         let mut last_heartbeat = Instant::now();
         let mut interval = actix_web::rt::time::interval(HEARTBEAT_INTERVAL);
 
         loop{
             tokio::select! {
+                event = events.recv() => {
+                    match event {
+                        Ok(event) if !topics.contains(event.event.topic()) => {}
+                        Ok(event) => {
+                            if let Ok(json) = serde_json::to_string(&event.event) {
+                                if session.text(json).await.is_err() {
+                                    break;
+                                }
+                                connection.sent();
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Websocket client lagged, skipped {} events", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
                 Some(Ok(msg)) = msg_stream.recv()=>{
                     match  msg {
                         Message::Ping(bytes) => {
@@ -33,18 +73,49 @@ pub async fn websocket_route(
                             last_heartbeat=Instant::now();
                         }
                         Message::Text(text) => {
-                            log::debug!("Received {}",text);
+                            tracing::debug!("Received {}",text);
+                            // {"action": "subscribe" | "unsubscribe", "topics": ["stats", ...]}
+                            if let Ok(command) = serde_json::from_str::<serde_json::Value>(&text) {
+                                let requested: Vec<&'static str> = command
+                                    .get("topics")
+                                    .and_then(|t| t.as_array())
+                                    .map(|list| {
+                                        TOPICS
+                                            .iter()
+                                            .copied()
+                                            .filter(|topic| list.iter().any(|t| t.as_str() == Some(topic)))
+                                            .collect()
+                                    })
+                                    .unwrap_or_default();
+                                match command.get("action").and_then(|a| a.as_str()) {
+                                    Some("subscribe") => topics.extend(requested),
+                                    Some("unsubscribe") => topics.retain(|t| !requested.contains(t)),
+                                    _ => continue,
+                                }
+                                let mut current: Vec<&str> = topics.iter().copied().collect();
+                                current.sort_unstable();
+                                let reply = serde_json::json!({ "type": "subscriptions", "topics": current });
+                                if session.text(reply.to_string()).await.is_err() {
+                                    break;
+                                }
+                                connection.sent();
+                            }
                         }
                         Message::Close(reason)=>{
-                            log::info!("Webscoket close: {:?}",reason);
+                            tracing::info!("Websocket close: {:?}",reason);
                         }
                         _ => {}
                     }
                 }
+                _ = shutdown::requested() => {
+                    let reason = CloseReason { code: CloseCode::Away, description: Some("server shutting down".to_string()) };
+                    let _ = session.close(Some(reason)).await;
+                    break;
+                }
                 _ = interval.tick() => {
                     //if client is still alive
                     if Instant::now().duration_since(last_heartbeat)>CLIENT_TIMEOUT {
-                         log::warn!("Client timeout, closing connection");
+                         tracing::warn!("Client timeout, closing connection");
                         let _ = session.close(None).await;
                         break;
                     }
@@ -66,6 +137,7 @@ pub async fn websocket_route(
                         if session.text(json).await.is_err() {
                             break;
                         }
+                        connection.sent();
                     }
                 }
             }